@@ -0,0 +1,19 @@
+use csp_solver::examples::benchmark::BenchmarkSuite;
+use std::path::PathBuf;
+
+/// Run every solver on every example problem and write the results as CSV,
+/// to the path given as the first argument or `benchmark.csv`
+fn main() {
+    let output = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("benchmark.csv"));
+
+    match BenchmarkSuite::run(&output) {
+        Ok(records) => println!("Wrote {} results to {}", records.len(), output.display()),
+        Err(err) => {
+            eprintln!("Failed to write {}: {}", output.display(), err);
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,10 +1,45 @@
 use crate::csp::constraint::Constraint;
+use crate::csp::csp::Csp;
+use crate::csp::domain::Domain;
 use crate::csp::variable::Variable;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
 use std::fmt::Debug;
 use std::fmt::Display;
 use std::hash::Hash;
 
+/// A partial assignment waiting in the branch-and-bound queue, ordered so the
+/// lowest bound (then the earliest pushed) is popped first
+struct Candidate<T: Clone + Eq + Hash + Debug> {
+    bound: f64,
+    order: usize,
+    assignment: Assignment<T>,
+}
+
+impl<T: Clone + Eq + Hash + Debug> PartialEq for Candidate<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Clone + Eq + Hash + Debug> Eq for Candidate<T> {}
+
+impl<T: Clone + Eq + Hash + Debug> PartialOrd for Candidate<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Clone + Eq + Hash + Debug> Ord for Candidate<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed: BinaryHeap is a max-heap
+        other
+            .bound
+            .total_cmp(&self.bound)
+            .then_with(|| other.order.cmp(&self.order))
+    }
+}
+
 /// Represents an assignment of values to variables in a CSP
 #[derive(Debug, Clone)]
 pub struct Assignment<T: Clone + Eq + Hash + Debug> {
@@ -39,6 +74,16 @@ impl<T: Clone + Eq + Hash + Debug> Assignment<T> {
         self.assignments.get(var)
     }
 
+    /// Get the value assigned to a variable, or `default` if it is unassigned
+    pub fn get_or_default(&self, var: &Variable<T>, default: T) -> T {
+        self.assignments.get(var).cloned().unwrap_or(default)
+    }
+
+    /// Get the value assigned to a variable, or compute one if it is unassigned
+    pub fn get_or_else<F: FnOnce() -> T>(&self, var: &Variable<T>, f: F) -> T {
+        self.assignments.get(var).cloned().unwrap_or_else(f)
+    }
+
     /// Get the number of assigned variables
     pub fn size(&self) -> usize {
         self.assignments.len()
@@ -59,11 +104,130 @@ impl<T: Clone + Eq + Hash + Debug> Assignment<T> {
         self.assignments.iter()
     }
 
+    /// Returns an iterator over all variable-value pairs, sorted by variable name
+    pub fn ordered_iter(&self) -> impl Iterator<Item = (&Variable<T>, &T)> {
+        let mut pairs: Vec<(&Variable<T>, &T)> = self.assignments.iter().collect();
+        pairs.sort_by_key(|(var, _)| *var);
+        pairs.into_iter()
+    }
+
+    /// Consume the assignment into a map sorted by variable name
+    pub fn into_btree_map(self) -> BTreeMap<Variable<T>, T> {
+        self.assignments.into_iter().collect()
+    }
+
     /// Get a copy of all assignments as a HashMap
     pub fn get_assignments(&self) -> HashMap<Variable<T>, T> {
         self.assignments.clone()
     }
 
+    /// Restrict this assignment to the given variables (those not assigned are skipped)
+    pub fn project(&self, vars: &[Variable<T>]) -> Assignment<T> {
+        let mut projected = Assignment::new();
+        for var in vars {
+            if let Some(value) = self.get(var) {
+                projected.assign(var.clone(), value.clone());
+            }
+        }
+        projected
+    }
+
+    /// Check that no variable is assigned different values here and in `other`
+    pub fn is_compatible_with(&self, other: &Assignment<T>) -> bool {
+        self.iter().all(|(var, value)| {
+            other
+                .get(var)
+                .is_none_or(|other_value| other_value == value)
+        })
+    }
+
+    /// Variables whose values differ between this assignment and `other`, as
+    /// `(variable, value here, value in other)` sorted by name. A variable
+    /// assigned on only one side has `None` on the other
+    pub fn diff(&self, other: &Assignment<T>) -> Vec<(Variable<T>, Option<T>, Option<T>)> {
+        let mut changes: Vec<_> = self
+            .changed_variables(other)
+            .into_iter()
+            .map(|var| (var.clone(), self.get(var).cloned(), other.get(var).cloned()))
+            .collect();
+        changes.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+        changes
+    }
+
+    /// Variables assigned differently here and in `other`, including those
+    /// assigned on only one side
+    pub fn changed_variables<'a>(&'a self, other: &'a Assignment<T>) -> Vec<&'a Variable<T>> {
+        let changed_here = self
+            .iter()
+            .filter(|(var, value)| other.get(var) != Some(*value))
+            .map(|(var, _)| var);
+        let only_in_other = other.variables().filter(|var| !self.is_assigned(var));
+        changed_here.chain(only_in_other).collect()
+    }
+
+    /// Entropy in bits of a variable's remaining domain under a uniform distribution
+    /// Assigned variables and singleton or empty domains carry no entropy
+    pub fn domain_entropy<D: Domain<T>>(&self, var: &Variable<T>, domain: &D) -> f64 {
+        if self.is_assigned(var) || domain.size() <= 1 {
+            return 0.0;
+        }
+        // -sum(p * log2(p)) with p = 1/n reduces to log2(n)
+        (domain.size() as f64).log2()
+    }
+
+    /// Enumerate the consistent complete extensions of `partial`, cheapest first.
+    /// Best-first branch and bound: `objective` of a partial assignment is used as
+    /// its lower bound, so it must never decrease as more variables are assigned
+    pub fn possible_completions<'a, D, F>(
+        partial: &Assignment<T>,
+        csp: &'a Csp<T, D>,
+        objective: F,
+    ) -> impl Iterator<Item = (Assignment<T>, f64)> + 'a
+    where
+        T: 'a,
+        D: Domain<T>,
+        F: Fn(&Assignment<T>) -> f64 + 'a,
+    {
+        let mut variables = csp.get_variables();
+        variables.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut queue = BinaryHeap::new();
+        let mut pushed = 0;
+        if csp.is_consistent(partial) {
+            queue.push(Candidate {
+                bound: objective(partial),
+                order: pushed,
+                assignment: partial.clone(),
+            });
+            pushed += 1;
+        }
+
+        std::iter::from_fn(move || {
+            while let Some(Candidate {
+                bound, assignment, ..
+            }) = queue.pop()
+            {
+                // branch on the first unassigned variable in name order
+                let Some(var) = variables.iter().find(|var| !assignment.is_assigned(var)) else {
+                    return Some((assignment, bound));
+                };
+                for value in csp.get_domain_values(var).unwrap_or_default() {
+                    let mut child = assignment.clone();
+                    child.assign(var.clone(), value);
+                    if csp.is_consistent(&child) {
+                        queue.push(Candidate {
+                            bound: objective(&child),
+                            order: pushed,
+                            assignment: child,
+                        });
+                        pushed += 1;
+                    }
+                }
+            }
+            None
+        })
+    }
+
     /// Check if this assignment is consistent with all given constraints
     pub fn is_consistent(&self, constraints: &[Constraint<T>]) -> bool {
         for constraint in constraints {
@@ -75,11 +239,17 @@ impl<T: Clone + Eq + Hash + Debug> Assignment<T> {
     }
 }
 
+impl<T: Clone + Eq + Hash + Debug> Default for Assignment<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T: Clone + Eq + Hash + Debug + Display> Display for Assignment<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{{")?;
         let mut first = true;
-        for (var, val) in &self.assignments {
+        for (var, val) in self.ordered_iter() {
             if !first {
                 write!(f, ", ")?;
             }
@@ -1,34 +1,121 @@
 use crate::csp::assignment::Assignment;
+use crate::csp::csp::Csp;
+use crate::csp::domain::Domain;
 use crate::csp::variable::Variable;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
-use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+/// The predicate type used to evaluate a constraint against an assignment.
+/// Thread-safe so a `Csp` can be searched from several threads at once
+type Predicate<T> = Arc<dyn Fn(&Assignment<T>) -> bool + Send + Sync>;
+
+/// The smallest and largest integer seen in an arithmetic constraint's domains
+type ValueBounds = Arc<Mutex<Option<(i64, i64)>>>;
 
 /// A constraint in a constraint satisfaction problem
+#[derive(Clone)]
 pub struct Constraint<T: Clone + Eq + Hash + Debug> {
     /// The name of the constraint (for debugging and display)
     name: String,
     /// The variables involved in this constraint
     variables: Vec<Variable<T>>,
     /// The function that determines if the constraint is satisfied
-    predicate: Rc<dyn Fn(&Assignment<T>) -> bool>,
+    predicate: Predicate<T>,
+    /// Whether the predicate can judge partial assignments
+    partial: bool,
+    /// Which built-in relation the predicate implements, if any
+    kind: ConstraintKind,
+    /// Conflict activity for VSIDS-style heuristics, shared between clones
+    activity: Arc<Mutex<f64>>,
+    /// How an arithmetic constraint reads a value as an integer
+    as_integer: Option<fn(&T) -> i32>,
+    /// Holds when a partial assignment already guarantees the constraint
+    entailed: Option<Predicate<T>>,
+    /// Smallest and largest integer in the domains of an arithmetic constraint's
+    /// variables, filled in by the CSPs it is added to and shared between clones
+    value_bounds: Option<ValueBounds>,
+}
+
+/// The built-in relation a constraint was created from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintKind {
+    /// An arbitrary predicate
+    Custom,
+    /// Built by `common::all_different`
+    AllDifferent,
+    /// Built by `common::diff`
+    NotEqual,
+    /// Built by `common::same`
+    Equal,
+    /// Built by `common::sum`, with its target
+    Sum(i32),
+}
+
+/// Outcome of checking a constraint against a possibly partial assignment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintState {
+    /// Holds however the remaining variables are assigned
+    Satisfied,
+    /// Fails however the remaining variables are assigned
+    Violated,
+    /// Depends on variables that are not assigned yet
+    Unknown,
 }
 
 impl<T: Clone + Eq + Hash + Debug> Constraint<T> {
     /// Creates a new constraint with the given name, variables, and predicate
+    ///
+    /// The predicate must be `Send + Sync` so the `Csp` holding it can be shared
+    /// between threads, as `find_all_solutions_parallel` and `PortfolioSolver` do
     pub fn new<F>(name: &str, variables: Vec<Variable<T>>, predicate: F) -> Self
     where
-        F: Fn(&Assignment<T>) -> bool + 'static,
+        F: Fn(&Assignment<T>) -> bool + Send + Sync + 'static,
     {
         Constraint {
             name: String::from(name),
             variables,
-            predicate: Rc::new(predicate),
+            predicate: Arc::new(predicate),
+            partial: false,
+            kind: ConstraintKind::Custom,
+            activity: Arc::new(Mutex::new(0.0)),
+            as_integer: None,
+            entailed: None,
+            value_bounds: None,
         }
     }
 
+    /// Creates a constraint whose predicate is also evaluated on partial assignments,
+    /// so it can prune before all of its variables are assigned
+    pub fn new_partial<F>(name: &str, variables: Vec<Variable<T>>, predicate: F) -> Self
+    where
+        F: Fn(&Assignment<T>) -> bool + Send + Sync + 'static,
+    {
+        Constraint {
+            name: String::from(name),
+            variables,
+            predicate: Arc::new(predicate),
+            partial: true,
+            kind: ConstraintKind::Custom,
+            activity: Arc::new(Mutex::new(0.0)),
+            as_integer: None,
+            entailed: None,
+            value_bounds: None,
+        }
+    }
+
+    /// Lets the constraint report `Satisfied` before all of its variables are
+    /// assigned, whenever `entailed` holds on the partial assignment
+    pub fn with_entailment<F>(mut self, entailed: F) -> Self
+    where
+        F: Fn(&Assignment<T>) -> bool + Send + Sync + 'static,
+    {
+        self.entailed = Some(Arc::new(entailed));
+        self
+    }
+
     /// Returns the name of this constraint
     pub fn name(&self) -> &str {
         &self.name
@@ -39,17 +126,79 @@ impl<T: Clone + Eq + Hash + Debug> Constraint<T> {
         &self.variables
     }
 
-    /// Returns true if the constraint is satisfied by the given assignment
+    /// Returns the built-in relation this constraint implements
+    pub fn kind(&self) -> ConstraintKind {
+        self.kind
+    }
+
+    /// The integer an arithmetic constraint reads the value as, if it is one
+    pub fn integer_value(&self, value: &T) -> Option<i32> {
+        self.as_integer.map(|as_integer| as_integer(value))
+    }
+
+    /// Widen the value bounds of an arithmetic constraint to cover `domain`.
+    /// Called by the CSP for each domain of the constraint's variables
+    pub(crate) fn cover_domain<D: Domain<T>>(&self, domain: &D) {
+        let (Some(bounds), Some(as_integer)) = (&self.value_bounds, self.as_integer) else {
+            return;
+        };
+        let mut bounds = bounds.lock().unwrap();
+        for value in domain.values() {
+            let value = i64::from(as_integer(&value));
+            *bounds = Some(match *bounds {
+                Some((min, max)) => (min.min(value), max.max(value)),
+                None => (value, value),
+            });
+        }
+    }
+
+    /// Returns true if this constraint was built by `common::all_different`
+    pub fn is_all_different(&self) -> bool {
+        self.kind == ConstraintKind::AllDifferent
+    }
+
+    /// Returns true if the constraint is not violated by the given assignment
     pub fn is_satisfied(&self, assignment: &Assignment<T>) -> bool {
-        // Check if all variables in the constraint are assigned
+        self.eval_partial(assignment) != ConstraintState::Violated
+    }
+
+    /// Judge a possibly partial assignment: `Satisfied` or `Violated` once the
+    /// outcome can't change whatever is assigned next, `Unknown` otherwise
+    /// Only this constraint's own variables matter, the rest of the CSP may be unassigned
+    pub fn eval_partial(&self, assignment: &Assignment<T>) -> ConstraintState {
         let all_assigned = self.variables.iter().all(|var| assignment.is_assigned(var));
 
-        // If all variables are assigned, check the predicate
-        if all_assigned {
-            (self.predicate)(assignment)
-        } else {
-            // If not all variables are assigned, the constraint is not violated
-            true
+        match (all_assigned, self.partial) {
+            (true, _) if (self.predicate)(assignment) => ConstraintState::Satisfied,
+            (true, _) => ConstraintState::Violated,
+            // Partial constraints can rule out an assignment early
+            (false, true) if !(self.predicate)(assignment) => ConstraintState::Violated,
+            (false, _)
+                if self
+                    .entailed
+                    .as_ref()
+                    .is_some_and(|entailed| entailed(assignment)) =>
+            {
+                ConstraintState::Satisfied
+            }
+            (false, _) => ConstraintState::Unknown,
+        }
+    }
+
+    /// Record that this constraint took part in a conflict
+    pub fn bump_activity(&self) {
+        *self.activity.lock().unwrap() += 1.0;
+    }
+
+    /// Current conflict activity
+    pub fn activity(&self) -> f64 {
+        *self.activity.lock().unwrap()
+    }
+
+    /// Multiply the activity of every constraint by `factor`, so older conflicts count less
+    pub fn decay_all(constraints: &[Constraint<T>], factor: f64) {
+        for constraint in constraints {
+            *constraint.activity.lock().unwrap() *= factor;
         }
     }
 
@@ -57,6 +206,329 @@ impl<T: Clone + Eq + Hash + Debug> Constraint<T> {
     pub fn involves(&self, variable: &Variable<T>) -> bool {
         self.variables.contains(variable)
     }
+
+    /// The constraint's variables as a set, for constant-time membership tests
+    pub fn scope_as_set(&self) -> HashSet<&Variable<T>> {
+        self.variables.iter().collect()
+    }
+
+    /// Variables of the CSP outside this constraint's scope, sorted by name
+    pub fn scope_complement<D: Domain<T>>(&self, csp: &Csp<T, D>) -> Vec<Variable<T>> {
+        let scope = self.scope_as_set();
+        let mut outside: Vec<Variable<T>> = csp
+            .get_variables()
+            .into_iter()
+            .filter(|var| !scope.contains(var))
+            .collect();
+        outside.sort_by(|a, b| a.name.cmp(&b.name));
+        outside
+    }
+
+    /// Creates a copy of this constraint with `original` replaced by `replacement`,
+    /// where `original` reads the value assigned to `replacement`
+    pub fn substitute(&self, original: &Variable<T>, replacement: &Variable<T>) -> Self
+    where
+        T: Send + Sync + 'static,
+    {
+        let mut variables = Vec::new();
+        for var in &self.variables {
+            let var = if var == original { replacement } else { var };
+            if !variables.contains(var) {
+                variables.push(var.clone());
+            }
+        }
+
+        let predicate = self.predicate.clone();
+        let (original, replacement) = (original.clone(), replacement.clone());
+        let substituted: Predicate<T> = Arc::new(
+            move |assignment: &Assignment<T>| match assignment.get(&replacement) {
+                Some(value) => {
+                    let mut extended = assignment.clone();
+                    extended.assign(original.clone(), value.clone());
+                    predicate(&extended)
+                }
+                None => predicate(assignment),
+            },
+        );
+
+        Constraint {
+            name: self.name.clone(),
+            variables,
+            predicate: substituted,
+            partial: self.partial,
+            kind: ConstraintKind::Custom,
+            activity: Arc::new(Mutex::new(0.0)),
+            as_integer: None,
+            entailed: None,
+            value_bounds: None,
+        }
+    }
+
+    /// Creates the conjunction of two constraints over the union of their variables
+    /// Each side is checked as soon as its own variables are assigned
+    pub fn and(name: &str, c1: Constraint<T>, c2: Constraint<T>) -> Self
+    where
+        T: Send + Sync + 'static,
+    {
+        let variables = Self::union_variables(&c1, &c2);
+        Constraint::new_partial(name, variables, move |assignment| {
+            c1.is_satisfied(assignment) && c2.is_satisfied(assignment)
+        })
+    }
+
+    /// Creates the disjunction of two constraints over the union of their variables
+    pub fn or(name: &str, c1: Constraint<T>, c2: Constraint<T>) -> Self
+    where
+        T: Send + Sync + 'static,
+    {
+        let variables = Self::union_variables(&c1, &c2);
+        Constraint::new(name, variables, move |assignment| {
+            c1.is_satisfied(assignment) || c2.is_satisfied(assignment)
+        })
+    }
+
+    /// Creates the negation of a constraint, checked once all its variables are assigned
+    pub fn negate(name: &str, c: Constraint<T>) -> Self
+    where
+        T: Send + Sync + 'static,
+    {
+        let variables = c.variables.clone();
+        Constraint::new(name, variables, move |assignment| {
+            !c.is_satisfied(assignment)
+        })
+    }
+
+    fn union_variables(c1: &Constraint<T>, c2: &Constraint<T>) -> Vec<Variable<T>> {
+        let mut variables = c1.variables.clone();
+        for var in &c2.variables {
+            if !variables.contains(var) {
+                variables.push(var.clone());
+            }
+        }
+        variables
+    }
+
+    /// Returns true if the constraint is small enough to be materialized as a table
+    pub fn is_table_compatible(&self) -> bool {
+        self.variables.len() <= 4
+    }
+
+    /// Materializes every satisfying tuple over the given domains into a table constraint
+    pub fn to_table_constraint<D: Domain<T>>(
+        &self,
+        domains: &HashMap<Variable<T>, D>,
+    ) -> TableConstraint<T> {
+        let mut tuples = HashSet::new();
+
+        let value_lists: Option<Vec<Vec<T>>> = self
+            .variables
+            .iter()
+            .map(|var| domains.get(var).map(|domain| domain.values()))
+            .collect();
+
+        // A variable without a domain has no values, so no tuple can be built
+        let value_lists = match value_lists {
+            Some(lists) if lists.iter().all(|values| !values.is_empty()) => lists,
+            _ => return TableConstraint::new(&self.name, self.variables.clone(), tuples),
+        };
+
+        // Walk the cartesian product of the domains like an odometer
+        let mut indices = vec![0; value_lists.len()];
+        loop {
+            let mut assignment = Assignment::new();
+            let mut tuple = Vec::with_capacity(indices.len());
+            for (i, var) in self.variables.iter().enumerate() {
+                let value = value_lists[i][indices[i]].clone();
+                assignment.assign(var.clone(), value.clone());
+                tuple.push(value);
+            }
+
+            if self.is_satisfied(&assignment) {
+                tuples.insert(tuple);
+            }
+
+            let mut position = indices.len();
+            loop {
+                if position == 0 {
+                    return TableConstraint::new(&self.name, self.variables.clone(), tuples);
+                }
+                position -= 1;
+                indices[position] += 1;
+                if indices[position] < value_lists[position].len() {
+                    break;
+                }
+                indices[position] = 0;
+            }
+        }
+    }
+}
+
+/// VSIDS-style bookkeeping over a set of constraints: bump the ones involved in
+/// conflicts and periodically decay them all
+pub struct ConstraintActivityTracker<'a, T: Clone + Eq + Hash + Debug> {
+    constraints: &'a mut [Constraint<T>],
+    decay_factor: f64,
+}
+
+impl<'a, T: Clone + Eq + Hash + Debug> ConstraintActivityTracker<'a, T> {
+    /// Track the given constraints, decaying by `decay_factor` on each `decay`
+    pub fn new(constraints: &'a mut [Constraint<T>], decay_factor: f64) -> Self {
+        ConstraintActivityTracker {
+            constraints,
+            decay_factor,
+        }
+    }
+
+    /// Bump the named constraint, returning false if there is none
+    pub fn bump(&mut self, name: &str) -> bool {
+        match self.constraints.iter().find(|c| c.name() == name) {
+            Some(constraint) => {
+                constraint.bump_activity();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Activity of the named constraint
+    pub fn activity(&self, name: &str) -> Option<f64> {
+        self.constraints
+            .iter()
+            .find(|c| c.name() == name)
+            .map(Constraint::activity)
+    }
+
+    /// Decay every tracked constraint by the decay factor
+    pub fn decay(&mut self) {
+        Constraint::decay_all(self.constraints, self.decay_factor);
+    }
+
+    /// The constraint with the highest activity (ties broken by name)
+    pub fn most_active(&self) -> Option<&Constraint<T>> {
+        self.constraints.iter().max_by(|a, b| {
+            a.activity()
+                .total_cmp(&b.activity())
+                .then_with(|| b.name().cmp(a.name()))
+        })
+    }
+}
+
+/// A constraint defined by an explicit set of allowed tuples
+#[derive(Debug, Clone)]
+pub struct TableConstraint<T: Clone + Eq + Hash + Debug> {
+    /// The name of the constraint (for debugging and display)
+    name: String,
+    /// The variables involved in this constraint, in tuple order
+    variables: Vec<Variable<T>>,
+    /// The allowed value tuples
+    tuples: HashSet<Vec<T>>,
+}
+
+impl<T: Clone + Eq + Hash + Debug> TableConstraint<T> {
+    /// Creates a new table constraint from the given allowed tuples
+    pub fn new(name: &str, variables: Vec<Variable<T>>, tuples: HashSet<Vec<T>>) -> Self {
+        TableConstraint {
+            name: String::from(name),
+            variables,
+            tuples,
+        }
+    }
+
+    /// Returns the name of this constraint
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the variables involved in this constraint
+    pub fn variables(&self) -> &[Variable<T>] {
+        &self.variables
+    }
+
+    /// Returns the allowed tuples
+    pub fn tuples(&self) -> &HashSet<Vec<T>> {
+        &self.tuples
+    }
+
+    /// Returns true if the given tuple is allowed
+    pub fn allows(&self, tuple: &[T]) -> bool {
+        self.tuples.contains(tuple)
+    }
+
+    /// Returns true if the constraint is satisfied by the given assignment
+    pub fn is_satisfied(&self, assignment: &Assignment<T>) -> bool {
+        let tuple: Option<Vec<T>> = self
+            .variables
+            .iter()
+            .map(|var| assignment.get(var).cloned())
+            .collect();
+
+        match tuple {
+            Some(tuple) => self.tuples.contains(&tuple),
+            None => true,
+        }
+    }
+}
+
+impl<T: Clone + Eq + Hash + Debug + Send + Sync + 'static> TableConstraint<T> {
+    /// Converts this table into a regular constraint that can be added to a CSP
+    pub fn into_constraint(self) -> Constraint<T> {
+        let name = self.name.clone();
+        let variables = self.variables.clone();
+        Constraint::new(&name, variables, move |assignment| {
+            self.is_satisfied(assignment)
+        })
+    }
+}
+
+/// A constraint that can suggest which variable to branch on next
+pub trait IntelligentConstraint<T: Clone + Eq + Hash + Debug> {
+    /// Suggest the next variable to assign, or `None` to defer to other constraints
+    fn suggest_next_variable<D: Domain<T>>(
+        &self,
+        _assignment: &Assignment<T>,
+        _domains: &HashMap<Variable<T>, D>,
+    ) -> Option<Variable<T>> {
+        None
+    }
+}
+
+impl<T: Clone + Eq + Hash + Debug> IntelligentConstraint<T> for Constraint<T> {
+    /// All-different constraints suggest the unassigned variable in their scope
+    /// with the fewest values not already taken within the scope
+    fn suggest_next_variable<D: Domain<T>>(
+        &self,
+        assignment: &Assignment<T>,
+        domains: &HashMap<Variable<T>, D>,
+    ) -> Option<Variable<T>> {
+        if !self.is_all_different() {
+            return None;
+        }
+
+        let used: Vec<&T> = self
+            .variables
+            .iter()
+            .filter_map(|var| assignment.get(var))
+            .collect();
+
+        self.variables
+            .iter()
+            .filter(|var| !assignment.is_assigned(var))
+            .filter_map(|var| {
+                let domain = domains.get(var)?;
+                let remaining = domain
+                    .values()
+                    .iter()
+                    .filter(|value| !used.contains(value))
+                    .count();
+                Some((var, remaining))
+            })
+            .min_by(|(a, a_remaining), (b, b_remaining)| {
+                a_remaining
+                    .cmp(b_remaining)
+                    .then_with(|| a.name.cmp(&b.name))
+            })
+            .map(|(var, _)| var.clone())
+    }
 }
 
 impl<T: Clone + Eq + Hash + Debug> Display for Constraint<T> {
@@ -77,36 +549,39 @@ impl<T: Clone + Eq + Hash + Debug> Display for Constraint<T> {
 /// Module with common constraint factories
 pub mod common {
     use super::*;
+    use std::ops::Sub;
 
     /// Creates an "all different" constraint for the given variables
-    pub fn all_different<T: Clone + Eq + Hash + Debug + 'static>(
+    pub fn all_different<T: Clone + Eq + Hash + Debug + Send + Sync + 'static>(
         name: &str,
         variables: Vec<Variable<T>>,
     ) -> Constraint<T> {
-        Constraint::new(name, variables.clone(), move |assignment| {
+        let mut constraint = Constraint::new(name, variables.clone(), move |assignment| {
             let mut seen = HashSet::new();
 
             for var in &variables {
-                if let Some(value) = assignment.get(var) {
-                    if !seen.insert(value) {
-                        return false; // Duplicate value found
-                    }
+                if let Some(value) = assignment.get(var)
+                    && !seen.insert(value)
+                {
+                    return false; // Duplicate value found
                 }
             }
 
             true
-        })
+        });
+        constraint.kind = ConstraintKind::AllDifferent;
+        constraint
     }
 
     /// Creates a binary constraint between two variables
-    pub fn diff<T: Clone + Eq + Hash + Debug + 'static>(
+    pub fn diff<T: Clone + Eq + Hash + Debug + Send + Sync + 'static>(
         name: &str,
         var1: Variable<T>,
         var2: Variable<T>,
     ) -> Constraint<T> {
         let variables = vec![var1.clone(), var2.clone()];
 
-        Constraint::new(name, variables, move |assignment| {
+        let mut constraint = Constraint::new(name, variables, move |assignment| {
             let val1 = assignment.get(&var1);
             let val2 = assignment.get(&var2);
 
@@ -114,18 +589,20 @@ pub mod common {
                 (Some(v1), Some(v2)) => v1 != v2,
                 _ => true,
             }
-        })
+        });
+        constraint.kind = ConstraintKind::NotEqual;
+        constraint
     }
 
     /// Creates a binary constraint between two variables
-    pub fn same<T: Clone + Eq + Hash + Debug + 'static>(
+    pub fn same<T: Clone + Eq + Hash + Debug + Send + Sync + 'static>(
         name: &str,
         var1: Variable<T>,
         var2: Variable<T>,
     ) -> Constraint<T> {
         let variables = vec![var1.clone(), var2.clone()];
 
-        Constraint::new(name, variables, move |assignment| {
+        let mut constraint = Constraint::new(name, variables, move |assignment| {
             let val1 = assignment.get(&var1);
             let val2 = assignment.get(&var2);
 
@@ -133,16 +610,386 @@ pub mod common {
                 (Some(v1), Some(v2)) => v1 == v2,
                 _ => true,
             }
+        });
+        constraint.kind = ConstraintKind::Equal;
+        constraint
+    }
+
+    /// Creates a unary constraint forbidding the variable from taking any of the given values
+    pub fn not_in<T: Clone + Eq + Hash + Debug + Send + Sync + 'static>(
+        name: &str,
+        var: Variable<T>,
+        forbidden: Vec<T>,
+    ) -> Constraint<T> {
+        let variables = vec![var.clone()];
+
+        Constraint::new(name, variables, move |assignment| {
+            match assignment.get(&var) {
+                Some(value) => !forbidden.contains(value),
+                None => true,
+            }
+        })
+    }
+
+    /// Creates a unary constraint requiring the variable to take one of the given values
+    pub fn in_set<T: Clone + Eq + Hash + Debug + Send + Sync + 'static>(
+        name: &str,
+        var: Variable<T>,
+        allowed: Vec<T>,
+    ) -> Constraint<T> {
+        let variables = vec![var.clone()];
+
+        Constraint::new(name, variables, move |assignment| {
+            match assignment.get(&var) {
+                Some(value) => allowed.contains(value),
+                None => true,
+            }
+        })
+    }
+
+    /// Creates a constraint that enforces `consequent` whenever `guard` takes `guard_value`
+    pub fn implies<T: Clone + Eq + Hash + Debug + Send + Sync + 'static>(
+        name: &str,
+        guard: Variable<T>,
+        guard_value: T,
+        consequent: Constraint<T>,
+    ) -> Constraint<T> {
+        let mut variables = vec![guard.clone()];
+        for var in consequent.variables() {
+            if !variables.contains(var) {
+                variables.push(var.clone());
+            }
+        }
+        let predicate = consequent.predicate;
+        let (entailed_guard, entailed_value) = (guard.clone(), guard_value.clone());
+
+        Constraint::new(name, variables, move |assignment| {
+            if assignment.get(&guard) == Some(&guard_value) {
+                predicate(assignment)
+            } else {
+                true
+            }
+        })
+        // Once the guard takes another value the consequent no longer matters
+        .with_entailment(move |assignment| {
+            assignment
+                .get(&entailed_guard)
+                .is_some_and(|value| *value != entailed_value)
+        })
+    }
+
+    /// Reifies a constraint: `indicator` takes `on_value` if and only if the constraint holds
+    /// Returns the two directions of the equivalence as separate constraints
+    pub fn reify<T: Clone + Eq + Hash + Debug + Send + Sync + 'static>(
+        name: &str,
+        indicator: Variable<T>,
+        on_value: T,
+        constraint: Constraint<T>,
+    ) -> Vec<Constraint<T>> {
+        let mut variables = vec![indicator.clone()];
+        for var in constraint.variables() {
+            if !variables.contains(var) {
+                variables.push(var.clone());
+            }
+        }
+
+        let predicate = Arc::clone(&constraint.predicate);
+        let off_indicator = indicator.clone();
+        let off_value = on_value.clone();
+        let off =
+            Constraint::new(
+                &format!("{}-off", name),
+                variables,
+                move |assignment| match assignment.get(&off_indicator) {
+                    Some(value) if *value != off_value => !predicate(assignment),
+                    _ => true,
+                },
+            );
+        let on = implies(&format!("{}-on", name), indicator, on_value, constraint);
+
+        vec![on, off]
+    }
+
+    /// Creates a constraint requiring each variable to be strictly less than the next
+    /// Checked incrementally on each pair of consecutive assigned variables
+    pub fn strictly_increasing<T: Clone + Eq + Hash + Debug + Ord + Send + Sync + 'static>(
+        name: &str,
+        variables: Vec<Variable<T>>,
+    ) -> Constraint<T> {
+        Constraint::new_partial(name, variables.clone(), move |assignment| {
+            variables.windows(2).all(|pair| {
+                match (assignment.get(&pair[0]), assignment.get(&pair[1])) {
+                    (Some(a), Some(b)) => a < b,
+                    _ => true,
+                }
+            })
+        })
+    }
+
+    /// Creates a constraint requiring each variable to be at most the next
+    /// Checked incrementally on each pair of consecutive assigned variables
+    pub fn nondecreasing<T: Clone + Eq + Hash + Debug + Ord + Send + Sync + 'static>(
+        name: &str,
+        variables: Vec<Variable<T>>,
+    ) -> Constraint<T> {
+        Constraint::new_partial(name, variables.clone(), move |assignment| {
+            variables.windows(2).all(|pair| {
+                match (assignment.get(&pair[0]), assignment.get(&pair[1])) {
+                    (Some(a), Some(b)) => a <= b,
+                    _ => true,
+                }
+            })
+        })
+    }
+
+    /// Creates a constraint requiring `vars1` to be lexicographically smaller than `vars2`
+    /// Satisfied on partial assignments until the first differing position is decided
+    pub fn lex_less_than<T: Clone + Eq + Hash + Debug + Ord + Send + Sync + 'static>(
+        name: &str,
+        vars1: Vec<Variable<T>>,
+        vars2: Vec<Variable<T>>,
+    ) -> Constraint<T> {
+        lex_ordering(name, vars1, vars2, false)
+    }
+
+    /// Creates a constraint requiring `vars1` to be lexicographically at most `vars2`
+    /// Satisfied on partial assignments until the first differing position is decided
+    pub fn lex_less_than_or_equal<T: Clone + Eq + Hash + Debug + Ord + Send + Sync + 'static>(
+        name: &str,
+        vars1: Vec<Variable<T>>,
+        vars2: Vec<Variable<T>>,
+    ) -> Constraint<T> {
+        lex_ordering(name, vars1, vars2, true)
+    }
+
+    fn lex_ordering<T: Clone + Eq + Hash + Debug + Ord + Send + Sync + 'static>(
+        name: &str,
+        vars1: Vec<Variable<T>>,
+        vars2: Vec<Variable<T>>,
+        or_equal: bool,
+    ) -> Constraint<T> {
+        let mut scope = vars1.clone();
+        for var in &vars2 {
+            if !scope.contains(var) {
+                scope.push(var.clone());
+            }
+        }
+
+        Constraint::new_partial(name, scope, move |assignment| {
+            for (x, y) in vars1.iter().zip(&vars2) {
+                match (assignment.get(x), assignment.get(y)) {
+                    (Some(a), Some(b)) if a == b => continue,
+                    (Some(a), Some(b)) => return a < b,
+                    // the first undecided position can still go either way
+                    _ => return true,
+                }
+            }
+            // equal on the common prefix: the shorter sequence is smaller
+            if or_equal {
+                vars1.len() <= vars2.len()
+            } else {
+                vars1.len() < vars2.len()
+            }
+        })
+    }
+
+    /// Creates a binary constraint forbidding the listed (var1, var2) value pairs
+    pub fn forbidden_pairs<T: Clone + Eq + Hash + Debug + Send + Sync + 'static>(
+        name: &str,
+        var1: Variable<T>,
+        var2: Variable<T>,
+        forbidden: HashSet<(T, T)>,
+    ) -> Constraint<T> {
+        let variables = vec![var1.clone(), var2.clone()];
+
+        Constraint::new(name, variables, move |assignment| {
+            match (assignment.get(&var1), assignment.get(&var2)) {
+                (Some(v1), Some(v2)) => !forbidden.contains(&(v1.clone(), v2.clone())),
+                _ => true,
+            }
+        })
+    }
+
+    /// Creates a binary constraint allowing only the listed (var1, var2) value pairs
+    pub fn allowed_pairs<T: Clone + Eq + Hash + Debug + Send + Sync + 'static>(
+        name: &str,
+        var1: Variable<T>,
+        var2: Variable<T>,
+        allowed: HashSet<(T, T)>,
+    ) -> Constraint<T> {
+        let variables = vec![var1.clone(), var2.clone()];
+
+        Constraint::new(name, variables, move |assignment| {
+            match (assignment.get(&var1), assignment.get(&var2)) {
+                (Some(v1), Some(v2)) => allowed.contains(&(v1.clone(), v2.clone())),
+                _ => true,
+            }
+        })
+    }
+
+    /// Creates a sliding-window constraint: every `window` consecutive variables
+    /// must contain between `lo` and `hi` values from `value_set`
+    /// Partially assigned windows are pruned as soon as the bounds become unreachable
+    pub fn sequence<T: Clone + Eq + Hash + Debug + Send + Sync + 'static>(
+        name: &str,
+        variables: Vec<Variable<T>>,
+        window: usize,
+        value_set: HashSet<T>,
+        lo: usize,
+        hi: usize,
+    ) -> Constraint<T> {
+        Constraint::new_partial(name, variables.clone(), move |assignment| {
+            if window == 0 {
+                return true;
+            }
+
+            variables.windows(window).all(|vars| {
+                let mut in_set = 0;
+                let mut unassigned = 0;
+                for var in vars {
+                    match assignment.get(var) {
+                        Some(value) if value_set.contains(value) => in_set += 1,
+                        Some(_) => {}
+                        None => unassigned += 1,
+                    }
+                }
+                in_set <= hi && in_set + unassigned >= lo
+            })
+        })
+    }
+
+    /// Creates a constraint requiring exactly one of the variables to take `value`
+    pub fn exactly_once<T: Clone + Eq + Hash + Debug + Send + Sync + 'static>(
+        name: &str,
+        variables: Vec<Variable<T>>,
+        value: T,
+    ) -> Constraint<T> {
+        occurrences(name, variables, value, 1, 1)
+    }
+
+    /// Creates a constraint allowing at most one of the variables to take `value`
+    pub fn at_most_once<T: Clone + Eq + Hash + Debug + Send + Sync + 'static>(
+        name: &str,
+        variables: Vec<Variable<T>>,
+        value: T,
+    ) -> Constraint<T> {
+        occurrences(name, variables, value, 0, 1)
+    }
+
+    /// Creates a constraint requiring at least one of the variables to take `value`
+    pub fn at_least_once<T: Clone + Eq + Hash + Debug + Send + Sync + 'static>(
+        name: &str,
+        variables: Vec<Variable<T>>,
+        value: T,
+    ) -> Constraint<T> {
+        occurrences(name, variables, value, 1, usize::MAX)
+    }
+
+    /// Creates a constraint requiring exactly `n` of the boolean variables to be true
+    pub fn exactly_n_true(
+        name: &str,
+        variables: Vec<Variable<bool>>,
+        n: usize,
+    ) -> Constraint<bool> {
+        occurrences(name, variables, true, n, n)
+    }
+
+    /// Creates a constraint allowing at most `n` of the boolean variables to be true
+    pub fn at_most_n_true(
+        name: &str,
+        variables: Vec<Variable<bool>>,
+        n: usize,
+    ) -> Constraint<bool> {
+        occurrences(name, variables, true, 0, n)
+    }
+
+    /// Creates a constraint requiring at least `n` of the boolean variables to be true
+    pub fn at_least_n_true(
+        name: &str,
+        variables: Vec<Variable<bool>>,
+        n: usize,
+    ) -> Constraint<bool> {
+        occurrences(name, variables, true, n, usize::MAX)
+    }
+
+    /// Creates a constraint requiring more than half of the boolean variables to be true
+    pub fn majority_true(name: &str, variables: Vec<Variable<bool>>) -> Constraint<bool> {
+        let n = variables.len() / 2 + 1;
+        at_least_n_true(name, variables, n)
+    }
+
+    /// Bounds the number of variables taking `value` to `lo..=hi`, pruning partial assignments
+    fn occurrences<T: Clone + Eq + Hash + Debug + Send + Sync + 'static>(
+        name: &str,
+        variables: Vec<Variable<T>>,
+        value: T,
+        lo: usize,
+        hi: usize,
+    ) -> Constraint<T> {
+        Constraint::new_partial(name, variables.clone(), move |assignment| {
+            let mut count = 0;
+            let mut unassigned = 0;
+            for var in &variables {
+                match assignment.get(var) {
+                    Some(v) if *v == value => count += 1,
+                    Some(_) => {}
+                    None => unassigned += 1,
+                }
+            }
+            count <= hi && count + unassigned >= lo
+        })
+    }
+
+    /// Creates a binary constraint requiring `|var1 - var2| <= tolerance`
+    pub fn within_tolerance<T>(
+        name: &str,
+        var1: Variable<T>,
+        var2: Variable<T>,
+        tolerance: T,
+    ) -> Constraint<T>
+    where
+        T: Clone + Eq + Hash + Debug + Copy + Ord + Sub<Output = T> + Send + Sync + 'static,
+    {
+        distance(name, var1, var2, move |d| d <= tolerance)
+    }
+
+    /// Creates a binary constraint requiring `|var1 - var2| == delta`
+    pub fn differ_by_exactly<T>(
+        name: &str,
+        var1: Variable<T>,
+        var2: Variable<T>,
+        delta: T,
+    ) -> Constraint<T>
+    where
+        T: Clone + Eq + Hash + Debug + Copy + Ord + Sub<Output = T> + Send + Sync + 'static,
+    {
+        distance(name, var1, var2, move |d| d == delta)
+    }
+
+    /// Binary constraint on the absolute difference of two values, computed
+    /// without negation so unsigned types work
+    fn distance<T, F>(name: &str, var1: Variable<T>, var2: Variable<T>, accept: F) -> Constraint<T>
+    where
+        T: Clone + Eq + Hash + Debug + Copy + Ord + Sub<Output = T> + Send + Sync + 'static,
+        F: Fn(T) -> bool + Send + Sync + 'static,
+    {
+        let variables = vec![var1.clone(), var2.clone()];
+
+        Constraint::new(name, variables, move |assignment| {
+            match (assignment.get(&var1), assignment.get(&var2)) {
+                (Some(&v1), Some(&v2)) => accept(v1.max(v2) - v1.min(v2)),
+                _ => true,
+            }
         })
     }
 
     /// Creates a constraint for a sum of variables
-    pub fn sum<T: Clone + Eq + Hash + Debug + Into<i32> + 'static>(
+    pub fn sum<T: Clone + Eq + Hash + Debug + Into<i32> + Send + Sync + 'static>(
         name: &str,
         variables: Vec<Variable<T>>,
         target: i32,
     ) -> Constraint<T> {
-        Constraint::new(name, variables.clone(), move |assignment| {
+        let mut constraint = Constraint::new(name, variables.clone(), move |assignment| {
             let sum: i32 = variables
                 .iter()
                 .filter_map(|var| {
@@ -154,6 +1001,62 @@ pub mod common {
                 .sum();
 
             sum == target
+        });
+        constraint.kind = ConstraintKind::Sum(target);
+        constraint.as_integer = Some(|value| value.clone().into());
+        constraint
+    }
+
+    /// Creates a constraint requiring the sum of the variables to lie in `lo..=hi`.
+    /// A partial assignment is rejected as soon as even the smallest or largest
+    /// completion would leave the range, and accepted once every completion
+    /// stays in it. The smallest and largest completions use the bounds of the
+    /// variables' domains, taken when the constraint is added to a CSP; until
+    /// then only full assignments are judged
+    pub fn sum_bounded<T: Clone + Eq + Hash + Debug + Into<i32> + Send + Sync + 'static>(
+        name: &str,
+        variables: Vec<Variable<T>>,
+        lo: i32,
+        hi: i32,
+    ) -> Constraint<T> {
+        let bounds: ValueBounds = Arc::new(Mutex::new(None));
+        let (lo, hi) = (i64::from(lo), i64::from(hi));
+
+        // The sums of the smallest and largest completions of the assignment,
+        // or None while the domain bounds are unknown
+        let completion_sums = {
+            let (variables, bounds) = (variables.clone(), Arc::clone(&bounds));
+            move |assignment: &Assignment<T>| {
+                let mut sum: i64 = 0;
+                let mut unassigned: i64 = 0;
+                for var in &variables {
+                    match assignment.get(var) {
+                        Some(value) => sum = sum.saturating_add(i64::from(value.clone().into())),
+                        None => unassigned += 1,
+                    }
+                }
+                if unassigned == 0 {
+                    return Some((sum, sum));
+                }
+                let (min_value, max_value) = (*bounds.lock().unwrap())?;
+                Some((
+                    sum.saturating_add(min_value.saturating_mul(unassigned)),
+                    sum.saturating_add(max_value.saturating_mul(unassigned)),
+                ))
+            }
+        };
+        let entailed_sums = completion_sums.clone();
+
+        let mut constraint = Constraint::new_partial(name, variables, move |assignment| {
+            completion_sums(assignment)
+                .is_none_or(|(smallest, largest)| smallest <= hi && largest >= lo)
         })
+        .with_entailment(move |assignment| {
+            entailed_sums(assignment)
+                .is_some_and(|(smallest, largest)| smallest >= lo && largest <= hi)
+        });
+        constraint.as_integer = Some(|value| value.clone().into());
+        constraint.value_bounds = Some(bounds);
+        constraint
     }
 }
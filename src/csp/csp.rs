@@ -1,18 +1,106 @@
 use crate::csp::assignment::Assignment;
-use crate::csp::constraint::Constraint;
+use crate::csp::constraint::{Constraint, ConstraintKind, ConstraintState, common};
 use crate::csp::domain::Domain;
 use crate::csp::variable::Variable;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::Debug;
 use std::fmt::Display;
 use std::hash::Hash;
+use std::path::Path;
 
 /// A Constraint Satisfaction Problem
+#[derive(Clone)]
 pub struct Csp<T: Clone + Eq + Debug + Hash, D: Domain<T>> {
     domains: HashMap<Variable<T>, D>,
     constraints: Vec<Constraint<T>>,
 }
 
+/// The domains changed since a snapshot was taken, saved with their values at
+/// that time, so restoring touches only the changed variables. A snapshot taken
+/// with `Csp::get_domains_snapshot` also holds every domain, so changes made
+/// without going through it are undone as well
+#[derive(Debug, Clone)]
+pub struct DomainsSnapshot<T: Clone + Eq + Debug + Hash, D: Domain<T>> {
+    saved: Vec<(Variable<T>, D)>,
+    captured: Option<HashMap<Variable<T>, D>>,
+}
+
+impl<T: Clone + Eq + Debug + Hash, D: Domain<T>> DomainsSnapshot<T, D> {
+    /// Start tracking changes from the current state
+    pub fn new() -> Self {
+        DomainsSnapshot {
+            saved: Vec::new(),
+            captured: None,
+        }
+    }
+
+    /// Record every domain in `domains`, so `restore` puts all of them back
+    pub fn capture(domains: &HashMap<Variable<T>, D>) -> Self {
+        DomainsSnapshot {
+            saved: Vec::new(),
+            captured: Some(domains.clone()),
+        }
+    }
+
+    /// Set the domain of `var`, saving its previous domain on the first change
+    pub fn replace(&mut self, domains: &mut HashMap<Variable<T>, D>, var: &Variable<T>, domain: D) {
+        if let Some(previous) = domains.insert(var.clone(), domain)
+            && !self.saved.iter().any(|(saved, _)| saved == var)
+        {
+            self.saved.push((var.clone(), previous));
+        }
+    }
+
+    /// Put back every domain changed since the snapshot was taken
+    pub fn restore(self, domains: &mut HashMap<Variable<T>, D>) {
+        if let Some(captured) = self.captured {
+            *domains = captured;
+            return;
+        }
+        for (var, domain) in self.saved {
+            domains.insert(var, domain);
+        }
+    }
+
+    /// Number of variables whose domains changed through `replace`
+    pub fn len(&self) -> usize {
+        self.saved.len()
+    }
+
+    /// Returns true if no domain has changed through `replace`
+    pub fn is_empty(&self) -> bool {
+        self.saved.is_empty()
+    }
+}
+
+impl<T: Clone + Eq + Debug + Hash, D: Domain<T>> Default for DomainsSnapshot<T, D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How far a search has got: search counters, assigned variables and how many
+/// of the fully assigned constraints hold, for reporting from progress callbacks
+#[derive(Debug, Clone)]
+pub struct SolveProgress<T: Clone + Eq + Hash + Debug> {
+    /// Number of value assignments tried so far, 0 outside a search
+    pub nodes_explored: u64,
+    /// Number of times the search has backed up so far, 0 outside a search
+    pub backtracks: u64,
+    /// Number of variables currently assigned
+    pub current_depth: usize,
+    /// Number of variables in the CSP
+    pub total_variables: usize,
+    /// Constraints whose variables are all assigned
+    pub checkable_constraints: usize,
+    /// Checkable constraints that are satisfied
+    pub satisfied_constraints: usize,
+    /// `satisfied_constraints / checkable_constraints`, 1.0 when nothing is checkable
+    pub satisfaction_rate: f64,
+    /// The deepest consistent partial assignment reached so far
+    pub best_partial: Assignment<T>,
+}
+
 impl<T: Clone + Eq + Debug + Hash, D: Domain<T>> Csp<T, D> {
     /// Create a new empty CSP
     pub fn new() -> Self {
@@ -38,15 +126,364 @@ impl<T: Clone + Eq + Debug + Hash, D: Domain<T>> Csp<T, D> {
                 return Err(format!("Variable {} does not exist in the CSP", var.name));
             }
         }
+        for var in constraint.variables() {
+            constraint.cover_domain(&self.domains[var]);
+        }
         self.constraints.push(constraint);
         Ok(())
     }
 
+    /// Restrict each variable's domain to the values allowed by its unary constraints
+    pub fn apply_unary_constraints(&mut self) {
+        for constraint in &self.constraints {
+            if let [var] = constraint.variables()
+                && let Some(domain) = self.domains.get(var)
+            {
+                let allowed: Vec<T> = domain
+                    .values()
+                    .into_iter()
+                    .filter(|value| {
+                        let mut assignment = Assignment::new();
+                        assignment.assign(var.clone(), value.clone());
+                        constraint.is_satisfied(&assignment)
+                    })
+                    .collect();
+                let restricted = domain.restrict_to(allowed);
+                self.domains.insert(var.clone(), restricted);
+            }
+        }
+    }
+
+    /// Split into one sub-problem per value of the given variable, with that
+    /// variable's domain fixed to the single value
+    pub fn split(&self, var: &Variable<T>) -> Vec<Csp<T, D>> {
+        match self.domains.get(var) {
+            Some(domain) => domain
+                .values()
+                .into_iter()
+                .map(|value| self.with_domain_restricted(var, vec![value]))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Split into at most `n` sub-problems by partitioning the given variable's
+    /// domain into roughly equal groups of values
+    pub fn split_n_way(&self, var: &Variable<T>, n: usize) -> Vec<Csp<T, D>> {
+        let values = match self.domains.get(var) {
+            Some(domain) => domain.values(),
+            None => return Vec::new(),
+        };
+        if n == 0 || values.is_empty() {
+            return Vec::new();
+        }
+
+        let chunk_size = values.len().div_ceil(n);
+        values
+            .chunks(chunk_size)
+            .map(|chunk| self.with_domain_restricted(var, chunk.to_vec()))
+            .collect()
+    }
+
+    /// Copy of this CSP assuming `var = value`: the variable's domain becomes
+    /// `{value}`, or empty if the value was not in it
+    pub fn with_assumption(&self, var: &Variable<T>, value: T) -> Csp<T, D> {
+        self.with_domain_restricted(var, vec![value])
+    }
+
+    /// Copy of this CSP with every (variable, value) assumption applied at once
+    pub fn with_assumptions(&self, pairs: &[(Variable<T>, T)]) -> Csp<T, D> {
+        let mut assumed = self.clone();
+        for (var, value) in pairs {
+            if let Some(domain) = assumed.domains.get(var) {
+                let restricted = domain.restrict_to([value.clone()]);
+                assumed.domains.insert(var.clone(), restricted);
+            }
+        }
+        assumed
+    }
+
+    /// Clone this CSP with the given variable's domain restricted to the given values
+    fn with_domain_restricted(&self, var: &Variable<T>, values: Vec<T>) -> Csp<T, D> {
+        let mut sub = self.clone();
+        if let Some(domain) = sub.domains.get(var) {
+            let restricted = domain.restrict_to(values);
+            sub.domains.insert(var.clone(), restricted);
+        }
+        sub
+    }
+
+    /// Merge `original` into `replacement`: every constraint on `original` is
+    /// rewritten to use `replacement`, whose domain becomes the intersection of both
+    pub fn substitute_variable(
+        &mut self,
+        original: &Variable<T>,
+        replacement: &Variable<T>,
+    ) -> Result<(), String>
+    where
+        T: Send + Sync + 'static,
+    {
+        if original == replacement {
+            return Err(format!("Cannot substitute {} for itself", original.name));
+        }
+        let original_domain = self
+            .domains
+            .get(original)
+            .ok_or_else(|| format!("Variable {} does not exist in the CSP", original.name))?;
+        let replacement_domain = self
+            .domains
+            .get(replacement)
+            .ok_or_else(|| format!("Variable {} does not exist in the CSP", replacement.name))?;
+
+        let merged = replacement_domain.restrict_to(original_domain.values());
+        self.domains.insert(replacement.clone(), merged);
+        self.domains.remove(original);
+
+        for constraint in &mut self.constraints {
+            if constraint.involves(original) {
+                *constraint = constraint.substitute(original, replacement);
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the pairwise not-equals constraints implied by every all-different
+    /// constraint, then carry them across `same` constraints: `X = Y` and
+    /// `Y != Z` imply `X != Z`. Returns the number of binary constraints added
+    pub fn add_implied_binary_not_equals(&mut self) -> usize
+    where
+        T: Send + Sync + 'static,
+    {
+        let mut added = self.decompose_global_constraints();
+
+        let ordered = |x: &Variable<T>, y: &Variable<T>| {
+            if x.name <= y.name {
+                (x.clone(), y.clone())
+            } else {
+                (y.clone(), x.clone())
+            }
+        };
+        let pair_of = |constraint: &Constraint<T>| match constraint.variables() {
+            [x, y] if x != y => Some(ordered(x, y)),
+            _ => None,
+        };
+        let mut different: HashSet<(Variable<T>, Variable<T>)> = self
+            .constraints
+            .iter()
+            .filter(|c| c.kind() == ConstraintKind::NotEqual)
+            .filter_map(pair_of)
+            .collect();
+        let equal: Vec<(Variable<T>, Variable<T>)> = self
+            .constraints
+            .iter()
+            .filter(|c| c.kind() == ConstraintKind::Equal)
+            .filter_map(pair_of)
+            .collect();
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            let mut implied = Vec::new();
+            for (x, y) in &equal {
+                for (a, b) in &different {
+                    // the not-equals partner of whichever side of `x = y` it mentions
+                    let (from, to) = match (a == y || b == y, a == x || b == x) {
+                        (true, false) => (x, if a == y { b } else { a }),
+                        (false, true) => (y, if a == x { b } else { a }),
+                        _ => continue,
+                    };
+                    let pair = ordered(from, to);
+                    if from != to && !different.contains(&pair) {
+                        implied.push(pair);
+                    }
+                }
+            }
+            for (x, y) in implied {
+                if different.insert((x.clone(), y.clone())) {
+                    let name = format!("implied-{}-{}", x.name, y.name);
+                    self.constraints.push(common::diff(&name, x, y));
+                    added += 1;
+                    changed = true;
+                }
+            }
+        }
+        added
+    }
+
+    /// Decompose each all-different constraint into binary `diff` constraints,
+    /// keeping the original. Returns the number of binary constraints added
+    pub fn decompose_global_constraints(&mut self) -> usize
+    where
+        T: Send + Sync + 'static,
+    {
+        let mut implied = Vec::new();
+        for constraint in self.constraints.iter().filter(|c| c.is_all_different()) {
+            let vars = constraint.variables();
+            for (i, x) in vars.iter().enumerate() {
+                for y in &vars[i + 1..] {
+                    let name = format!("{}-{}-{}", constraint.name(), x.name, y.name);
+                    if !self.constraints.iter().any(|c| c.name() == name) {
+                        implied.push(common::diff(&name, x.clone(), y.clone()));
+                    }
+                }
+            }
+        }
+
+        let added = implied.len();
+        self.constraints.extend(implied);
+        added
+    }
+
+    /// Break value symmetry when the values are interchangeable: every variable
+    /// has the same domain and every constraint is a `diff`, `same` or
+    /// all-different. Adds a value precedence constraint so that, taking
+    /// variables in name order, each newly used value is the next unused one in
+    /// domain order. Returns false (and changes nothing) if values are not interchangeable
+    pub fn apply_value_symmetry_breaking(&mut self) -> bool
+    where
+        T: Send + Sync + 'static,
+    {
+        let mut variables: Vec<Variable<T>> = self.domains.keys().cloned().collect();
+        variables.sort_by(|a, b| a.name.cmp(&b.name));
+        let Some(first) = variables.first() else {
+            return false;
+        };
+        let values = self.domains[first].values();
+
+        let same_domains = self.domains.values().all(|domain| {
+            domain.size() == values.len() && values.iter().all(|v| domain.contains(v))
+        });
+        let symmetric_constraints = !self.constraints.is_empty()
+            && self.constraints.iter().all(|constraint| {
+                matches!(
+                    constraint.kind(),
+                    ConstraintKind::NotEqual | ConstraintKind::Equal | ConstraintKind::AllDifferent
+                )
+            });
+        if !same_domains || !symmetric_constraints {
+            return false;
+        }
+
+        let order = variables.clone();
+        let precedence =
+            Constraint::new_partial("value-symmetry-breaking", variables, move |assignment| {
+                let mut used = 0;
+                // only the assigned prefix can be checked
+                for var in &order {
+                    let Some(value) = assignment.get(var) else {
+                        break;
+                    };
+                    match values.iter().position(|v| v == value) {
+                        Some(index) if index < used => {}
+                        Some(index) if index == used => used += 1,
+                        _ => return false,
+                    }
+                }
+                true
+            });
+        self.constraints.push(precedence);
+        true
+    }
+
+    /// Extract the sub-problem over the given variables, keeping only the
+    /// constraints whose variables all lie in the subset
+    pub fn induce_subproblem(&self, vars: &[Variable<T>]) -> Csp<T, D> {
+        let mut sub = Csp::new();
+        for var in vars {
+            if let Some(domain) = self.domains.get(var) {
+                sub.domains.insert(var.clone(), domain.clone());
+            }
+        }
+        sub.constraints = self
+            .constraints
+            .iter()
+            .filter_map(|constraint| Self::project_constraint(constraint, vars))
+            .collect();
+        sub
+    }
+
+    /// Returns the constraint if all of its variables are in `subvars`, otherwise `None`
+    pub fn project_constraint(
+        constraint: &Constraint<T>,
+        subvars: &[Variable<T>],
+    ) -> Option<Constraint<T>> {
+        constraint
+            .variables()
+            .iter()
+            .all(|var| subvars.contains(var))
+            .then(|| constraint.clone())
+    }
+
+    /// Record the current domains so `restore_domains_snapshot` can put them back
+    pub fn get_domains_snapshot(&self) -> DomainsSnapshot<T, D> {
+        DomainsSnapshot::capture(&self.domains)
+    }
+
+    /// Replace the domain of a variable, widening the value bounds of the
+    /// arithmetic constraints on it to cover the new domain
+    pub fn set_domain(&mut self, var: &Variable<T>, domain: D) -> Result<(), String> {
+        match self.domains.get_mut(var) {
+            Some(current) => {
+                for constraint in self.constraints.iter().filter(|c| c.involves(var)) {
+                    constraint.cover_domain(&domain);
+                }
+                *current = domain;
+                Ok(())
+            }
+            None => Err(format!("Variable {} does not exist in the CSP", var.name)),
+        }
+    }
+
+    /// Copy of this CSP with the given domains; variables not in the CSP are ignored
+    pub fn with_domains(&self, domains: HashMap<Variable<T>, D>) -> Csp<T, D> {
+        let mut copy = self.clone();
+        for (var, domain) in domains {
+            let _ = copy.set_domain(&var, domain);
+        }
+        copy
+    }
+
+    /// Replace the domain of a variable, recording the old one in `snapshot`
+    pub fn update_domain(
+        &mut self,
+        var: &Variable<T>,
+        domain: D,
+        snapshot: &mut DomainsSnapshot<T, D>,
+    ) -> Result<(), String> {
+        if !self.domains.contains_key(var) {
+            return Err(format!("Variable {} does not exist in the CSP", var.name));
+        }
+        snapshot.replace(&mut self.domains, var, domain);
+        Ok(())
+    }
+
+    /// Undo every domain change recorded in `snapshot`
+    pub fn restore_domains_snapshot(&mut self, snapshot: DomainsSnapshot<T, D>) {
+        snapshot.restore(&mut self.domains);
+    }
+
     /// Get the domain for the given variable
     pub fn get_domain(&self, variable: &Variable<T>) -> Option<&D> {
         self.domains.get(variable)
     }
 
+    /// Get the values in the domain of the given variable
+    pub fn get_domain_values(&self, variable: &Variable<T>) -> Option<Vec<T>> {
+        self.domains.get(variable).map(|domain| domain.values())
+    }
+
+    /// Get the size of the domain of the given variable
+    pub fn get_domain_size(&self, variable: &Variable<T>) -> Option<usize> {
+        self.domains.get(variable).map(|domain| domain.size())
+    }
+
+    /// Check if the domain of the given variable contains the value
+    pub fn domain_contains(&self, variable: &Variable<T>, value: &T) -> bool {
+        self.domains
+            .get(variable)
+            .is_some_and(|domain| domain.contains(value))
+    }
+
     /// Get all constraints that involve the given variable
     pub fn get_constraints_for_variable(&self, var: &Variable<T>) -> Vec<&Constraint<T>> {
         self.constraints
@@ -55,6 +492,115 @@ impl<T: Clone + Eq + Debug + Hash, D: Domain<T>> Csp<T, D> {
             .collect()
     }
 
+    /// Get all constraints that do not involve the given variable
+    pub fn constraints_not_involving(&self, var: &Variable<T>) -> Vec<&Constraint<T>> {
+        self.constraints
+            .iter()
+            .filter(|c| !c.involves(var))
+            .collect()
+    }
+
+    /// Get all variables that share at least one constraint with the given variable
+    pub fn adjacent_variables(&self, var: &Variable<T>) -> Vec<&Variable<T>> {
+        let mut neighbors: Vec<&Variable<T>> = Vec::new();
+        for constraint in self.get_constraints_for_variable(var) {
+            for other in constraint.variables() {
+                if other != var && !neighbors.contains(&other) {
+                    neighbors.push(other);
+                }
+            }
+        }
+        neighbors
+    }
+
+    /// Connected components of the constraint graph, each sorted by name and
+    /// ordered by their first variable. Found breadth first, so long chains of
+    /// variables do not grow the call stack
+    pub fn connected_components(&self) -> Vec<Vec<Variable<T>>> {
+        let mut variables: Vec<&Variable<T>> = self.domains.keys().collect();
+        variables.sort_by(|a, b| a.name.cmp(&b.name));
+        let index: HashMap<&Variable<T>, usize> = variables
+            .iter()
+            .enumerate()
+            .map(|(i, var)| (*var, i))
+            .collect();
+
+        // linking every variable of a constraint to its first one keeps them connected
+        let mut edges: Vec<Vec<usize>> = vec![Vec::new(); variables.len()];
+        for constraint in &self.constraints {
+            let mut scope = constraint.variables().iter().map(|var| index[var]);
+            if let Some(first) = scope.next() {
+                for other in scope.filter(|&other| other != first) {
+                    edges[first].push(other);
+                    edges[other].push(first);
+                }
+            }
+        }
+
+        let mut seen = vec![false; variables.len()];
+        let mut components = Vec::new();
+        for start in 0..variables.len() {
+            if seen[start] {
+                continue;
+            }
+            seen[start] = true;
+            let mut component = vec![start];
+            let mut queue = VecDeque::from([start]);
+            while let Some(node) = queue.pop_front() {
+                for &next in &edges[node] {
+                    if !seen[next] {
+                        seen[next] = true;
+                        component.push(next);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            component.sort_unstable();
+            components.push(
+                component
+                    .into_iter()
+                    .map(|i| variables[i].clone())
+                    .collect(),
+            );
+        }
+        components
+    }
+
+    /// All variables grouped by connected component, in `connected_components` order
+    pub fn component_order(&self) -> Vec<Variable<T>> {
+        self.connected_components().into_iter().flatten().collect()
+    }
+
+    /// Check if two variables share at least one constraint
+    pub fn are_adjacent(&self, v1: &Variable<T>, v2: &Variable<T>) -> bool {
+        v1 != v2
+            && self
+                .constraints
+                .iter()
+                .any(|c| c.involves(v1) && c.involves(v2))
+    }
+
+    /// Get all pairs of variables that share no constraint, sorted by name
+    pub fn find_unconstrained_variable_pairs(&self) -> Vec<(Variable<T>, Variable<T>)> {
+        let mut variables = self.get_variables();
+        variables.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut pairs = Vec::new();
+        for (i, v1) in variables.iter().enumerate() {
+            for v2 in &variables[i + 1..] {
+                if !self.are_adjacent(v1, v2) {
+                    pairs.push((v1.clone(), v2.clone()));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Check if every pair of variables shares at least one constraint
+    pub fn is_fully_constrained(&self) -> bool {
+        self.find_unconstrained_variable_pairs().is_empty()
+    }
+
     /// Get all variables
     pub fn get_variables(&self) -> Vec<Variable<T>> {
         self.domains.keys().cloned().collect()
@@ -78,17 +624,754 @@ impl<T: Clone + Eq + Debug + Hash, D: Domain<T>> Csp<T, D> {
     /// Check if the given assignment is consistent with all constraints
     pub fn is_consistent(&self, assignment: &Assignment<T>) -> bool {
         for constraint in &self.constraints {
-            if !constraint.is_satisfied(assignment) {
+            if constraint.eval_partial(assignment) == ConstraintState::Violated {
                 return false;
             }
         }
         true
     }
 
+    /// Sum of the domain entropies of all unassigned variables over the given domains
+    pub fn total_remaining_entropy(
+        &self,
+        assignment: &Assignment<T>,
+        domains: &HashMap<Variable<T>, D>,
+    ) -> f64 {
+        self.domains
+            .keys()
+            .filter_map(|var| domains.get(var).map(|domain| (var, domain)))
+            .map(|(var, domain)| assignment.domain_entropy(var, domain))
+            .sum()
+    }
+
     /// Check if the assignment is complete and consistent
     pub fn is_solution(&self, assignment: &Assignment<T>) -> bool {
         assignment.is_complete(self.num_variables()) && self.is_consistent(assignment)
     }
+
+    /// Fraction of the checkable constraints (all variables assigned) that are
+    /// satisfied, 1.0 when no constraint is checkable yet
+    pub fn constraint_satisfaction_rate(&self, assignment: &Assignment<T>) -> f64 {
+        self.progress(assignment).satisfaction_rate
+    }
+
+    /// Summarize how far `assignment` has got, for progress reporting. The
+    /// search counters are left at 0 and `assignment` is the best partial
+    pub fn progress(&self, assignment: &Assignment<T>) -> SolveProgress<T> {
+        let checkable: Vec<&Constraint<T>> = self
+            .constraints
+            .iter()
+            .filter(|constraint| {
+                constraint
+                    .variables()
+                    .iter()
+                    .all(|v| assignment.is_assigned(v))
+            })
+            .collect();
+        let satisfied = checkable
+            .iter()
+            .filter(|constraint| constraint.is_satisfied(assignment))
+            .count();
+
+        SolveProgress {
+            nodes_explored: 0,
+            backtracks: 0,
+            current_depth: assignment.size(),
+            total_variables: self.num_variables(),
+            checkable_constraints: checkable.len(),
+            satisfied_constraints: satisfied,
+            satisfaction_rate: if checkable.is_empty() {
+                1.0
+            } else {
+                satisfied as f64 / checkable.len() as f64
+            },
+            best_partial: assignment.clone(),
+        }
+    }
+
+    /// Count the assignments of `variable_set` that satisfy the `diff` and
+    /// all-different constraints among them, i.e. the proper colorings of that
+    /// subgraph. Other constraints are ignored, so the count is only the number
+    /// of solutions when the subgraph is purely all-different.
+    /// A complete subgraph over a shared domain of size d is counted directly as d!/(d-n)!
+    pub fn count_solutions_all_different(&self, variable_set: &[Variable<T>]) -> u64 {
+        let n = variable_set.len();
+        let mut neighbors: Vec<Vec<usize>> = vec![Vec::new(); n];
+        for constraint in &self.constraints {
+            if !matches!(
+                constraint.kind(),
+                ConstraintKind::NotEqual | ConstraintKind::AllDifferent
+            ) {
+                continue;
+            }
+            let Some(indices) = constraint
+                .variables()
+                .iter()
+                .map(|var| variable_set.iter().position(|v| v == var))
+                .collect::<Option<Vec<usize>>>()
+            else {
+                continue;
+            };
+            for (k, &i) in indices.iter().enumerate() {
+                for &j in &indices[k + 1..] {
+                    if i != j && !neighbors[i].contains(&j) {
+                        neighbors[i].push(j);
+                        neighbors[j].push(i);
+                    }
+                }
+            }
+        }
+
+        let domains: Vec<Vec<T>> = variable_set
+            .iter()
+            .map(|var| self.get_domain_values(var).unwrap_or_default())
+            .collect();
+
+        // complete graph over one shared domain: ordered selections of n of d values
+        let complete = neighbors.iter().all(|adjacent| adjacent.len() == n - 1);
+        let shared = domains.iter().all(|values| {
+            values.len() == domains[0].len() && values.iter().all(|v| domains[0].contains(v))
+        });
+        if n > 0 && complete && shared {
+            let d = domains[0].len() as u64;
+            return (0..n as u64).map(|k| d.saturating_sub(k)).product();
+        }
+
+        let mut chosen: Vec<Option<&T>> = vec![None; n];
+        Self::count_colorings(0, &domains, &neighbors, &mut chosen)
+    }
+
+    fn count_colorings<'a>(
+        index: usize,
+        domains: &'a [Vec<T>],
+        neighbors: &[Vec<usize>],
+        chosen: &mut Vec<Option<&'a T>>,
+    ) -> u64 {
+        if index == domains.len() {
+            return 1;
+        }
+        let mut count = 0;
+        for value in &domains[index] {
+            if neighbors[index].iter().all(|&j| chosen[j] != Some(value)) {
+                chosen[index] = Some(value);
+                count += Self::count_colorings(index + 1, domains, neighbors, chosen);
+                chosen[index] = None;
+            }
+        }
+        count
+    }
+
+    /// Cheap infeasibility check: an empty domain, or a unary or binary
+    /// constraint with no supporting values. Returns true if infeasibility is detected
+    pub fn is_trivially_infeasible(&self) -> bool {
+        if self.domains.values().any(|domain| domain.is_empty()) {
+            return true;
+        }
+
+        for constraint in &self.constraints {
+            match constraint.variables() {
+                [var] => {
+                    let domain = &self.domains[var];
+                    let supported = domain.values().into_iter().any(|value| {
+                        let mut assignment = Assignment::new();
+                        assignment.assign(var.clone(), value);
+                        constraint.is_satisfied(&assignment)
+                    });
+                    if !supported {
+                        return true;
+                    }
+                }
+                [var1, var2] => {
+                    let values1 = self.domains[var1].values();
+                    let values2 = self.domains[var2].values();
+                    let supported = values1.iter().any(|value1| {
+                        values2.iter().any(|value2| {
+                            let mut assignment = Assignment::new();
+                            assignment.assign(var1.clone(), value1.clone());
+                            assignment.assign(var2.clone(), value2.clone());
+                            constraint.is_satisfied(&assignment)
+                        })
+                    });
+                    if !supported {
+                        return true;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        false
+    }
+
+    /// Check the CSP is well-formed: every constraint variable has a domain, no
+    /// domain is empty, constraint names are unique and every `common::sum`
+    /// target lies between the smallest and largest sum its domains allow
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        let mut empty: Vec<&str> = self
+            .domains
+            .iter()
+            .filter(|(_, domain)| domain.is_empty())
+            .map(|(var, _)| var.name.as_str())
+            .collect();
+        empty.sort();
+        errors.extend(
+            empty
+                .into_iter()
+                .map(|name| ValidationError::EmptyDomain(name.to_string())),
+        );
+
+        let mut seen = HashSet::new();
+        for constraint in &self.constraints {
+            if !seen.insert(constraint.name()) {
+                errors.push(ValidationError::DuplicateConstraintName(
+                    constraint.name().to_string(),
+                ));
+            }
+
+            let mut complete = true;
+            for var in constraint.variables() {
+                if !self.domains.contains_key(var) {
+                    complete = false;
+                    errors.push(ValidationError::UnknownVariable {
+                        constraint: constraint.name().to_string(),
+                        variable: var.name.clone(),
+                    });
+                }
+            }
+
+            if let ConstraintKind::Sum(target) = constraint.kind()
+                && complete
+                && let Some((min, max)) = self.sum_range(constraint)
+                && !(min..=max).contains(&target)
+            {
+                errors.push(ValidationError::UnreachableSum {
+                    constraint: constraint.name().to_string(),
+                    target,
+                    min,
+                    max,
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Panic on structural validation errors; solvers call this on entry in
+    /// debug builds. Empty domains and unreachable sums only make the problem
+    /// unsatisfiable, so they are left to the search to report as no solution
+    #[cfg(debug_assertions)]
+    pub(crate) fn debug_validate(&self) {
+        let Err(errors) = self.validate() else {
+            return;
+        };
+        let messages: Vec<String> = errors
+            .iter()
+            .filter(|error| {
+                matches!(
+                    error,
+                    ValidationError::UnknownVariable { .. }
+                        | ValidationError::DuplicateConstraintName(_)
+                )
+            })
+            .map(|error| error.to_string())
+            .collect();
+        if !messages.is_empty() {
+            panic!("malformed CSP: {}", messages.join("; "));
+        }
+    }
+
+    /// Smallest and largest sum of an arithmetic constraint's variables over
+    /// their domains, or `None` if a domain is empty
+    fn sum_range(&self, constraint: &Constraint<T>) -> Option<(i32, i32)> {
+        let mut range = (0, 0);
+        for var in constraint.variables() {
+            let values: Vec<i32> = self.domains[var]
+                .values()
+                .iter()
+                .filter_map(|value| constraint.integer_value(value))
+                .collect();
+            range.0 += values.iter().min()?;
+            range.1 += values.iter().max()?;
+        }
+        Some(range)
+    }
+}
+
+impl<T: Clone + Eq + Debug + Display + Hash + Ord, D: Domain<T>> Csp<T, D> {
+    /// Format the problem with variables sorted by name, domain values sorted,
+    /// and constraints grouped by arity and sorted by name
+    pub fn format_pretty(&self) -> String {
+        let mut out = format!(
+            "CSP with {} variables and {} constraints\n",
+            self.num_variables(),
+            self.num_constraints()
+        );
+
+        out.push_str("Variables:\n");
+        let mut variables: Vec<&Variable<T>> = self.domains.keys().collect();
+        variables.sort_by(|a, b| a.name.cmp(&b.name));
+        for var in variables {
+            let mut values = self.domains[var].values();
+            values.sort();
+            let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+            out.push_str(&format!("  {}: {{{}}}\n", var, values.join(", ")));
+        }
+
+        out.push_str("Constraints:\n");
+        let mut constraints: Vec<&Constraint<T>> = self.constraints.iter().collect();
+        constraints.sort_by(|a, b| {
+            a.variables()
+                .len()
+                .cmp(&b.variables().len())
+                .then_with(|| a.name().cmp(b.name()))
+        });
+        let mut current_arity = None;
+        for constraint in constraints {
+            let arity = constraint.variables().len();
+            if current_arity != Some(arity) {
+                out.push_str(&format!("  Arity {}:\n", arity));
+                current_arity = Some(arity);
+            }
+            out.push_str(&format!("    {}\n", constraint));
+        }
+
+        out
+    }
+
+    /// Print the problem to stdout in a deterministic, human-readable format
+    pub fn pretty_print(&self) {
+        print!("{}", self.format_pretty());
+    }
+}
+
+/// A problem found by `Csp::validate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// A constraint refers to a variable with no domain; `add_constraint`
+    /// rejects these, so this flags a broken invariant
+    UnknownVariable {
+        constraint: String,
+        variable: String,
+    },
+    /// A variable has no values left
+    EmptyDomain(String),
+    /// Two or more constraints share a name
+    DuplicateConstraintName(String),
+    /// A sum constraint's target is outside the sums its domains can reach
+    UnreachableSum {
+        constraint: String,
+        target: i32,
+        min: i32,
+        max: i32,
+    },
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::UnknownVariable {
+                constraint,
+                variable,
+            } => write!(
+                f,
+                "constraint {} refers to unknown variable {}",
+                constraint, variable
+            ),
+            ValidationError::EmptyDomain(var) => write!(f, "variable {} has an empty domain", var),
+            ValidationError::DuplicateConstraintName(name) => {
+                write!(f, "more than one constraint is named {}", name)
+            }
+            ValidationError::UnreachableSum {
+                constraint,
+                target,
+                min,
+                max,
+            } => write!(
+                f,
+                "constraint {} targets {} but its sums range over {}..={}",
+                constraint, target, min, max
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Why a CSP cannot be written in XCSP3 format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Xcsp3Error {
+    /// A variable name or symbolic value is not a valid XCSP3 identifier
+    InvalidIdentifier(String),
+    /// A constraint's predicate has no XCSP3 equivalent
+    UnsupportedConstraint(String),
+}
+
+impl Display for Xcsp3Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Xcsp3Error::InvalidIdentifier(id) => write!(f, "invalid XCSP3 identifier: {}", id),
+            Xcsp3Error::UnsupportedConstraint(name) => {
+                write!(f, "constraint {} has no XCSP3 equivalent", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Xcsp3Error {}
+
+/// Largest number of value combinations enumerated to encode one constraint as clauses
+const MAX_SAT_TUPLES: usize = 1 << 16;
+
+/// Why a CSP cannot be written as a DIMACS CNF formula
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SatConversionError {
+    /// A constraint's scope has too many value combinations to enumerate
+    ConstraintTooLarge(String),
+}
+
+impl Display for SatConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SatConversionError::ConstraintTooLarge(name) => {
+                write!(f, "constraint {} has too many tuples to encode", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SatConversionError {}
+
+impl<D: Domain<bool>> Csp<bool, D> {
+    /// Write a boolean CSP as DIMACS CNF using the direct encoding: the i-th
+    /// variable by name is literal i, true when the variable is. Missing domain
+    /// values become unit clauses and each violating tuple of a constraint is
+    /// forbidden by one clause, so `diff(x, y)` gives `(x ∨ y) ∧ (¬x ∨ ¬y)`
+    pub fn to_dimacs_sat(&self) -> Result<String, SatConversionError> {
+        let variables = self.sorted_variables();
+        let literal = |var: &Variable<bool>, value: bool| {
+            let index = variables.iter().position(|v| *v == var).unwrap() as i64 + 1;
+            if value { index } else { -index }
+        };
+
+        let mut clauses = Vec::new();
+        for var in &variables {
+            for value in [false, true] {
+                if !self.domains[*var].contains(&value) {
+                    clauses.push(vec![literal(var, !value)]);
+                }
+            }
+        }
+        for constraint in &self.constraints {
+            for tuple in self.violating_tuples(constraint)? {
+                let clause = constraint
+                    .variables()
+                    .iter()
+                    .zip(tuple)
+                    .map(|(var, value)| literal(var, !value))
+                    .collect();
+                clauses.push(clause);
+            }
+        }
+
+        let comments = variables
+            .iter()
+            .enumerate()
+            .map(|(i, var)| format!("{} {}", i + 1, var.name))
+            .collect();
+        Ok(Self::format_dimacs(variables.len(), comments, &clauses))
+    }
+}
+
+impl<T: Clone + Eq + Debug + Display + Hash, D: Domain<T>> Csp<T, D> {
+    /// Write the CSP as DIMACS CNF using the support encoding: one literal per
+    /// variable-value pair, numbered by variable name then domain order. Each
+    /// variable takes exactly one value, each value of a binary constraint
+    /// implies one of its supports, and other constraints forbid their
+    /// violating tuples
+    pub fn to_dimacs_sat_with_support_encoding(&self) -> Result<String, SatConversionError> {
+        let variables = self.sorted_variables();
+        let mut literals: HashMap<&Variable<T>, Vec<(T, i64)>> = HashMap::new();
+        let mut comments = Vec::new();
+        let mut next = 1;
+        for var in &variables {
+            let numbered = self.domains[*var]
+                .values()
+                .into_iter()
+                .map(|value| {
+                    comments.push(format!("{} {}={}", next, var.name, value));
+                    next += 1;
+                    (value, next - 1)
+                })
+                .collect();
+            literals.insert(var, numbered);
+        }
+        let literal = |var: &Variable<T>, value: &T| {
+            literals[var].iter().find(|(v, _)| v == value).unwrap().1
+        };
+
+        let mut clauses: Vec<Vec<i64>> = Vec::new();
+        for var in &variables {
+            let values = &literals[*var];
+            clauses.push(values.iter().map(|(_, lit)| *lit).collect());
+            for (i, (_, a)) in values.iter().enumerate() {
+                for (_, b) in &values[i + 1..] {
+                    clauses.push(vec![-a, -b]);
+                }
+            }
+        }
+
+        for constraint in &self.constraints {
+            if let [x, y] = constraint.variables() {
+                for (from, to) in [(x, y), (y, x)] {
+                    for (a, lit) in &literals[from] {
+                        let mut clause = vec![-lit];
+                        for (b, support) in &literals[to] {
+                            let mut pair = Assignment::new();
+                            pair.assign(from.clone(), a.clone());
+                            pair.assign(to.clone(), b.clone());
+                            if constraint.is_satisfied(&pair) {
+                                clause.push(*support);
+                            }
+                        }
+                        clauses.push(clause);
+                    }
+                }
+            } else {
+                for tuple in self.violating_tuples(constraint)? {
+                    let clause = constraint
+                        .variables()
+                        .iter()
+                        .zip(&tuple)
+                        .map(|(var, value)| -literal(var, value))
+                        .collect();
+                    clauses.push(clause);
+                }
+            }
+        }
+
+        Ok(Self::format_dimacs(next as usize - 1, comments, &clauses))
+    }
+
+    fn sorted_variables(&self) -> Vec<&Variable<T>> {
+        let mut variables: Vec<&Variable<T>> = self.domains.keys().collect();
+        variables.sort_by(|a, b| a.name.cmp(&b.name));
+        variables
+    }
+
+    /// Every combination of domain values for the constraint's variables that
+    /// violates it, refusing scopes with more than `MAX_SAT_TUPLES` combinations
+    fn violating_tuples(
+        &self,
+        constraint: &Constraint<T>,
+    ) -> Result<Vec<Vec<T>>, SatConversionError> {
+        let domains: Vec<Vec<T>> = constraint
+            .variables()
+            .iter()
+            .map(|var| self.domains[var].values())
+            .collect();
+        let combinations = domains.iter().try_fold(1usize, |product, values| {
+            product
+                .checked_mul(values.len())
+                .filter(|&n| n <= MAX_SAT_TUPLES)
+        });
+        if combinations.is_none() {
+            return Err(SatConversionError::ConstraintTooLarge(
+                constraint.name().to_string(),
+            ));
+        }
+
+        let mut tuples = vec![Vec::new()];
+        for values in &domains {
+            tuples = tuples
+                .into_iter()
+                .flat_map(|prefix: Vec<T>| {
+                    values.iter().map(move |value| {
+                        let mut tuple = prefix.clone();
+                        tuple.push(value.clone());
+                        tuple
+                    })
+                })
+                .collect();
+        }
+
+        Ok(tuples
+            .into_iter()
+            .filter(|tuple| {
+                let mut assignment = Assignment::new();
+                for (var, value) in constraint.variables().iter().zip(tuple) {
+                    assignment.assign(var.clone(), value.clone());
+                }
+                !constraint.is_satisfied(&assignment)
+            })
+            .collect())
+    }
+
+    /// DIMACS CNF text: comment lines, the `p cnf` header, then one clause per line
+    fn format_dimacs(num_literals: usize, comments: Vec<String>, clauses: &[Vec<i64>]) -> String {
+        let mut dimacs = String::new();
+        for comment in comments {
+            dimacs.push_str(&format!("c {}\n", comment));
+        }
+        dimacs.push_str(&format!("p cnf {} {}\n", num_literals, clauses.len()));
+        for clause in clauses {
+            for lit in clause {
+                dimacs.push_str(&format!("{} ", lit));
+            }
+            dimacs.push_str("0\n");
+        }
+        dimacs
+    }
+
+    /// Write the CSP as an XCSP3 instance. Domains are listed in extension,
+    /// `diff`/`same` become `ne`/`eq` intensions and all-different constraints
+    /// become `<allDifferent>`; other constraints cannot be exported
+    pub fn to_xcsp3(&self) -> Result<String, Xcsp3Error> {
+        let mut variables: Vec<&Variable<T>> = self.domains.keys().collect();
+        variables.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut xml = String::from("<instance format=\"XCSP3\" type=\"CSP\">\n");
+        xml.push_str("  <variables>\n");
+        for var in variables {
+            Self::check_identifier(&var.name)?;
+            let mut values: Vec<String> = self.domains[var]
+                .values()
+                .iter()
+                .map(|value| value.to_string())
+                .collect();
+
+            let integer = values.iter().all(|value| value.parse::<i64>().is_ok());
+            if integer {
+                values.sort_by_key(|value| value.parse::<i64>().unwrap());
+                xml.push_str(&format!(
+                    "    <var id=\"{}\"> {} </var>\n",
+                    var.name,
+                    values.join(" ")
+                ));
+            } else {
+                for value in &values {
+                    Self::check_identifier(value)?;
+                }
+                values.sort();
+                xml.push_str(&format!(
+                    "    <var id=\"{}\" type=\"symbolic\"> {} </var>\n",
+                    var.name,
+                    values.join(" ")
+                ));
+            }
+        }
+        xml.push_str("  </variables>\n");
+
+        xml.push_str("  <constraints>\n");
+        for constraint in &self.constraints {
+            let names: Vec<&str> = constraint
+                .variables()
+                .iter()
+                .map(|var| var.name.as_str())
+                .collect();
+            let element = match (constraint.kind(), names.as_slice()) {
+                (ConstraintKind::NotEqual, [x, y]) => {
+                    format!("<intension> ne({},{}) </intension>", x, y)
+                }
+                (ConstraintKind::Equal, [x, y]) => {
+                    format!("<intension> eq({},{}) </intension>", x, y)
+                }
+                (ConstraintKind::AllDifferent, _) => {
+                    format!("<allDifferent> {} </allDifferent>", names.join(" "))
+                }
+                _ => {
+                    return Err(Xcsp3Error::UnsupportedConstraint(
+                        constraint.name().to_string(),
+                    ));
+                }
+            };
+            xml.push_str(&format!("    {}\n", element));
+        }
+        xml.push_str("  </constraints>\n");
+        xml.push_str("</instance>\n");
+
+        Ok(xml)
+    }
+
+    /// Write the XCSP3 form of the CSP to a file
+    pub fn save_xcsp3(&self, path: &Path) -> std::io::Result<()> {
+        let xml = self
+            .to_xcsp3()
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        std::fs::write(path, xml)
+    }
+
+    /// A JSON schema for solutions: an object with one string property per
+    /// variable, limited to its domain values, all required. The constraints
+    /// are listed under `constraints` with their variables and arity; schema
+    /// validators ignore them, so only domain membership is checked
+    #[cfg(feature = "serde")]
+    pub fn to_json_schema(&self) -> serde_json::Value {
+        use serde_json::json;
+
+        let mut properties = serde_json::Map::new();
+        for var in self.sorted_variables() {
+            let mut values: Vec<String> = self.domains[var]
+                .values()
+                .iter()
+                .map(|value| value.to_string())
+                .collect();
+            values.sort();
+            properties.insert(
+                var.name.clone(),
+                json!({ "type": "string", "enum": values }),
+            );
+        }
+        let required: Vec<&String> = properties.keys().collect();
+        let constraints: Vec<serde_json::Value> = self
+            .constraints
+            .iter()
+            .map(|constraint| {
+                let variables: Vec<&str> = constraint
+                    .variables()
+                    .iter()
+                    .map(|var| var.name.as_str())
+                    .collect();
+                json!({
+                    "name": constraint.name(),
+                    "variables": variables,
+                    "arity": variables.len(),
+                })
+            })
+            .collect();
+
+        json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": false,
+            "constraints": constraints,
+        })
+    }
+
+    /// XCSP3 identifiers start with a letter and continue with letters, digits or `_`
+    fn check_identifier(id: &str) -> Result<(), Xcsp3Error> {
+        let mut chars = id.chars();
+        let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if valid {
+            Ok(())
+        } else {
+            Err(Xcsp3Error::InvalidIdentifier(id.to_string()))
+        }
+    }
+}
+
+impl<T: Clone + Eq + Debug + Hash, D: Domain<T>> Default for Csp<T, D> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T: Clone + Eq + Debug + Display + Hash, D: Domain<T>> Display for Csp<T, D> {
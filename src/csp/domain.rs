@@ -1,7 +1,9 @@
 use std::cmp::Ord;
 use std::collections::{BTreeSet, HashSet};
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
 use std::hash::Hash;
+use std::ops::{Add, Sub};
+use std::sync::{Arc, OnceLock};
 
 /// Trait defining the behavior of a variable domain
 pub trait Domain<T: Clone + Eq + Debug>: Clone + Debug {
@@ -17,6 +19,77 @@ pub trait Domain<T: Clone + Eq + Debug>: Clone + Debug {
     fn remove(&self, value: &T) -> Self;
     /// Creates a copy of this domain with only the specified values kept
     fn restrict_to<I: IntoIterator<Item = T>>(&self, values_to_keep: I) -> Self;
+    /// Creates a domain of the values in `universe` that are not in this domain.
+    /// Available for domains that can be collected from values
+    fn complement_in<U: Domain<T>>(&self, universe: &U) -> Self
+    where
+        Self: FromIterator<T>,
+    {
+        universe
+            .values()
+            .into_iter()
+            .filter(|v| !self.contains(v))
+            .collect()
+    }
+
+    /// Creates a domain of the values in exactly one of this domain and `other`.
+    /// Available for domains that can be collected from values
+    fn symmetric_difference_with(&self, other: &Self) -> Self
+    where
+        Self: FromIterator<T>,
+    {
+        let only_other = other.values().into_iter().filter(|v| !self.contains(v));
+        self.values()
+            .into_iter()
+            .filter(|v| !other.contains(v))
+            .chain(only_other)
+            .collect()
+    }
+
+    /// Returns all values in the domain in ascending order
+    fn to_sorted_vec(&self) -> Vec<T>
+    where
+        T: Ord,
+    {
+        let mut values = self.values();
+        values.sort();
+        values
+    }
+
+    /// Renders the domain as `{1, 2, 3}` in ascending order
+    fn to_debug_string(&self) -> String
+    where
+        T: Display + Ord,
+    {
+        let values: Vec<String> = self.to_sorted_vec().iter().map(|v| v.to_string()).collect();
+        format!("{{{}}}", values.join(", "))
+    }
+
+    /// Creates a `VecDomain` of the values transformed by `f`, in domain order;
+    /// values `f` maps together appear once
+    fn map<U, F>(&self, f: F) -> VecDomain<U>
+    where
+        U: Clone + Eq + Debug,
+        F: Fn(T) -> U,
+    {
+        self.filter_map(|value| Some(f(value)))
+    }
+
+    /// Creates a `VecDomain` of the values `f` maps to `Some`, in domain order;
+    /// values `f` maps together appear once
+    fn filter_map<U, F>(&self, f: F) -> VecDomain<U>
+    where
+        U: Clone + Eq + Debug,
+        F: Fn(T) -> Option<U>,
+    {
+        let mut mapped: Vec<U> = Vec::new();
+        for value in self.values().into_iter().filter_map(f) {
+            if !mapped.contains(&value) {
+                mapped.push(value);
+            }
+        }
+        VecDomain::new(mapped)
+    }
 }
 
 /// Domain implementation using a HashSet
@@ -74,6 +147,30 @@ impl<T: Clone + Eq + Hash + Debug> Domain<T> for HashSetDomain<T> {
             .collect();
         HashSetDomain { values: new_values }
     }
+
+    fn complement_in<U: Domain<T>>(&self, universe: &U) -> Self {
+        let new_values: HashSet<T> = universe
+            .values()
+            .into_iter()
+            .filter(|v| !self.values.contains(v))
+            .collect();
+        HashSetDomain { values: new_values }
+    }
+
+    fn symmetric_difference_with(&self, other: &Self) -> Self {
+        let new_values: HashSet<T> = self
+            .values
+            .symmetric_difference(&other.values)
+            .cloned()
+            .collect();
+        HashSetDomain { values: new_values }
+    }
+}
+
+impl<T: Clone + Eq + Hash + Debug> FromIterator<T> for HashSetDomain<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(values: I) -> Self {
+        Self::new(values)
+    }
 }
 
 /// Domain implementation using a sorted BTreeSet
@@ -131,6 +228,30 @@ impl<T: Clone + Eq + Ord + Debug> Domain<T> for BTreeSetDomain<T> {
             .collect();
         BTreeSetDomain { values: new_values }
     }
+
+    fn complement_in<U: Domain<T>>(&self, universe: &U) -> Self {
+        let new_values: BTreeSet<T> = universe
+            .values()
+            .into_iter()
+            .filter(|v| !self.values.contains(v))
+            .collect();
+        BTreeSetDomain { values: new_values }
+    }
+
+    fn symmetric_difference_with(&self, other: &Self) -> Self {
+        let new_values: BTreeSet<T> = self
+            .values
+            .symmetric_difference(&other.values)
+            .cloned()
+            .collect();
+        BTreeSetDomain { values: new_values }
+    }
+}
+
+impl<T: Clone + Eq + Ord + Debug> FromIterator<T> for BTreeSetDomain<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(values: I) -> Self {
+        Self::new(values)
+    }
 }
 
 /// Domain implementation using a Vec (useful for small domains)
@@ -194,6 +315,12 @@ impl<T: Clone + Eq + Debug> Domain<T> for VecDomain<T> {
     }
 }
 
+impl<T: Clone + Eq + Debug> FromIterator<T> for VecDomain<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(values: I) -> Self {
+        Self::new(values)
+    }
+}
+
 /// Domain implementation using a sorted Vec
 #[derive(Debug, Clone)]
 pub struct SortedVecDomain<T: Clone + Eq + Ord + Debug> {
@@ -273,6 +400,543 @@ impl<T: Clone + Eq + Ord + Debug> Domain<T> for SortedVecDomain<T> {
         }
         SortedVecDomain { values: new_values }
     }
+
+    fn complement_in<U: Domain<T>>(&self, universe: &U) -> Self {
+        let mut universe_vec = universe.values();
+        universe_vec.sort();
+        universe_vec.dedup();
+        // Merge both sorted lists, keeping universe values missing from self
+        let mut new_values = Vec::new();
+        let mut self_iter = self.values.iter().peekable();
+        for value in universe_vec {
+            while self_iter.next_if(|v| **v < value).is_some() {}
+            if self_iter.peek() != Some(&&value) {
+                new_values.push(value);
+            }
+        }
+        SortedVecDomain { values: new_values }
+    }
+
+    fn symmetric_difference_with(&self, other: &Self) -> Self {
+        let mut new_values = Vec::new();
+        let mut left = self.values.iter().peekable();
+        let mut right = other.values.iter().peekable();
+        loop {
+            match (left.peek(), right.peek()) {
+                (Some(l), Some(r)) => match l.cmp(r) {
+                    std::cmp::Ordering::Less => new_values.push(left.next().unwrap().clone()),
+                    std::cmp::Ordering::Greater => new_values.push(right.next().unwrap().clone()),
+                    std::cmp::Ordering::Equal => {
+                        left.next();
+                        right.next();
+                    }
+                },
+                (Some(_), None) => new_values.push(left.next().unwrap().clone()),
+                (None, Some(_)) => new_values.push(right.next().unwrap().clone()),
+                (None, None) => break,
+            }
+        }
+        SortedVecDomain { values: new_values }
+    }
+}
+
+impl<T: Clone + Eq + Ord + Debug> FromIterator<T> for SortedVecDomain<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(values: I) -> Self {
+        Self::new(values)
+    }
+}
+
+/// Domain implementation using a fixed-size array (stack allocated, for small domains)
+#[derive(Debug, Clone)]
+pub struct SmallDomain<T: Clone + Eq + Debug, const N: usize> {
+    slots: [Option<T>; N],
+    len: usize,
+}
+
+impl<T: Clone + Eq + Debug, const N: usize> SmallDomain<T, N> {
+    /// Create a new domain from a collection of values, failing if there are more than N
+    pub fn new<I: IntoIterator<Item = T>>(values: I) -> Result<Self, String> {
+        let mut domain = Self::empty();
+        for value in values {
+            if !domain.contains(&value) && !domain.push(value) {
+                return Err(format!("SmallDomain holds at most {} values", N));
+            }
+        }
+        Ok(domain)
+    }
+
+    fn empty() -> Self {
+        SmallDomain {
+            slots: std::array::from_fn(|_| None),
+            len: 0,
+        }
+    }
+
+    /// Append a value, returning false if the domain is full
+    fn push(&mut self, value: T) -> bool {
+        if self.len == N {
+            return false;
+        }
+        self.slots[self.len] = Some(value);
+        self.len += 1;
+        true
+    }
+
+    /// Values in `universe` that are not in this domain, failing if there are more than N.
+    /// `SmallDomain` is not `FromIterator`, so this replaces `Domain::complement_in`
+    pub fn try_complement_in<U: Domain<T>>(&self, universe: &U) -> Result<Self, String> {
+        Self::new(universe.values().into_iter().filter(|v| !self.contains(v)))
+    }
+
+    /// Values in exactly one of this domain and `other`, failing if there are more than N.
+    /// `SmallDomain` is not `FromIterator`, so this replaces `Domain::symmetric_difference_with`
+    pub fn try_symmetric_difference_with(&self, other: &Self) -> Result<Self, String> {
+        let only_self = self.iter().filter(|v| !other.contains(v));
+        let only_other = other.iter().filter(|v| !self.contains(v));
+        Self::new(only_self.chain(only_other).cloned())
+    }
+
+    /// Build from at most N values already known to be distinct
+    fn from_distinct<I: IntoIterator<Item = T>>(values: I) -> Self {
+        let mut domain = Self::empty();
+        for value in values {
+            if !domain.push(value) {
+                break;
+            }
+        }
+        domain
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        self.slots[..self.len].iter().flatten()
+    }
+}
+
+impl<T: Clone + Eq + Debug, const N: usize> Domain<T> for SmallDomain<T, N> {
+    fn contains(&self, value: &T) -> bool {
+        self.iter().any(|v| v == value)
+    }
+
+    fn size(&self) -> usize {
+        self.len
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn values(&self) -> Vec<T> {
+        self.iter().cloned().collect()
+    }
+
+    fn remove(&self, value: &T) -> Self {
+        Self::from_distinct(self.iter().filter(|v| *v != value).cloned())
+    }
+
+    fn restrict_to<I: IntoIterator<Item = T>>(&self, values_to_keep: I) -> Self {
+        let keep_vec: Vec<T> = values_to_keep.into_iter().collect();
+        Self::from_distinct(self.iter().filter(|v| keep_vec.contains(v)).cloned())
+    }
+}
+
+/// Domain implementation over an integer interval [lo, hi], for range
+/// splitting in branch-and-bound. Values removed from the interior are kept as holes
+#[derive(Debug, Clone)]
+pub struct IntervalDomain<T: Copy + Ord + Debug> {
+    lo: T,
+    hi: T,
+    holes: BTreeSet<T>,
+}
+
+impl<T> IntervalDomain<T>
+where
+    T: Copy + Ord + Debug + Add<Output = T> + Sub<Output = T> + From<u8> + TryInto<usize>,
+{
+    /// Create the domain of every value from `lo` to `hi` inclusive
+    pub fn new(lo: T, hi: T) -> Result<Self, String> {
+        if lo > hi {
+            return Err(format!(
+                "IntervalDomain needs lo <= hi, got [{:?}, {:?}]",
+                lo, hi
+            ));
+        }
+        Ok(IntervalDomain {
+            lo,
+            hi,
+            holes: BTreeSet::new(),
+        })
+    }
+
+    /// Lower bound of the interval
+    pub fn lo(&self) -> T {
+        self.lo
+    }
+
+    /// Upper bound of the interval
+    pub fn hi(&self) -> T {
+        self.hi
+    }
+
+    /// Split into [lo, midpoint] and [midpoint + 1, hi]; a side outside the
+    /// interval comes back empty
+    pub fn split_at(&self, midpoint: T) -> (Self, Self) {
+        let left = Self::bounded(self.lo, midpoint.min(self.hi), &self.holes);
+        let right = if midpoint >= self.hi {
+            Self::empty()
+        } else {
+            Self::bounded((midpoint + T::from(1)).max(self.lo), self.hi, &self.holes)
+        };
+        (left, right)
+    }
+
+    /// Intersect two intervals, None if they share no value
+    pub fn intersect_with(&self, other: &Self) -> Option<Self> {
+        let holes = self.holes.union(&other.holes).copied().collect();
+        let intersection = Self::bounded(self.lo.max(other.lo), self.hi.min(other.hi), &holes);
+        (!intersection.is_empty()).then_some(intersection)
+    }
+
+    fn empty() -> Self {
+        IntervalDomain {
+            lo: T::from(1),
+            hi: T::from(0),
+            holes: BTreeSet::new(),
+        }
+    }
+
+    /// Build [lo, hi] minus `holes`, trimming holes at either end
+    fn bounded(mut lo: T, mut hi: T, holes: &BTreeSet<T>) -> Self {
+        while lo <= hi && holes.contains(&lo) {
+            if lo == hi {
+                return Self::empty();
+            }
+            lo = lo + T::from(1);
+        }
+        while lo <= hi && holes.contains(&hi) {
+            hi = hi - T::from(1);
+        }
+        if lo > hi {
+            return Self::empty();
+        }
+        IntervalDomain {
+            lo,
+            hi,
+            holes: holes.range(lo..=hi).copied().collect(),
+        }
+    }
+
+    /// Smallest interval holding `values`, with the gaps as holes
+    fn hull(values: Vec<T>) -> Self {
+        let (Some(&lo), Some(&hi)) = (values.iter().min(), values.iter().max()) else {
+            return Self::empty();
+        };
+        let keep: BTreeSet<T> = values.into_iter().collect();
+        let holes = Self::bounded(lo, hi, &BTreeSet::new())
+            .values()
+            .into_iter()
+            .filter(|v| !keep.contains(v))
+            .collect();
+        Self::bounded(lo, hi, &holes)
+    }
+}
+
+impl<T> Domain<T> for IntervalDomain<T>
+where
+    T: Copy + Ord + Debug + Add<Output = T> + Sub<Output = T> + From<u8> + TryInto<usize>,
+{
+    fn contains(&self, value: &T) -> bool {
+        self.lo <= *value && *value <= self.hi && !self.holes.contains(value)
+    }
+
+    fn size(&self) -> usize {
+        if self.lo > self.hi {
+            return 0;
+        }
+        let width: usize = (self.hi - self.lo).try_into().unwrap_or(usize::MAX - 1);
+        width + 1 - self.holes.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.lo > self.hi
+    }
+
+    fn values(&self) -> Vec<T> {
+        let mut values = Vec::new();
+        if self.is_empty() {
+            return values;
+        }
+        let mut v = self.lo;
+        loop {
+            if !self.holes.contains(&v) {
+                values.push(v);
+            }
+            if v == self.hi {
+                return values;
+            }
+            v = v + T::from(1);
+        }
+    }
+
+    fn remove(&self, value: &T) -> Self {
+        if !self.contains(value) {
+            return self.clone();
+        }
+        let mut holes = self.holes.clone();
+        holes.insert(*value);
+        Self::bounded(self.lo, self.hi, &holes)
+    }
+
+    fn restrict_to<I: IntoIterator<Item = T>>(&self, values_to_keep: I) -> Self {
+        Self::hull(
+            values_to_keep
+                .into_iter()
+                .filter(|v| self.contains(v))
+                .collect(),
+        )
+    }
+}
+
+impl<T> FromIterator<T> for IntervalDomain<T>
+where
+    T: Copy + Ord + Debug + Add<Output = T> + Sub<Output = T> + From<u8> + TryInto<usize>,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(values: I) -> Self {
+        Self::hull(values.into_iter().collect())
+    }
+}
+
+/// Domain computed by a generator the first time its values are needed.
+/// Clones share the generator and the cached values, so it runs at most once,
+/// even when clones are used from several threads
+#[derive(Clone)]
+pub struct LazyDomain<T: Clone + Eq + Debug> {
+    generator: Arc<dyn Fn() -> Vec<T> + Send + Sync>,
+    cache: Arc<OnceLock<VecDomain<T>>>,
+}
+
+impl<T: Clone + Eq + Debug> LazyDomain<T> {
+    /// Create a domain whose values come from `generator`, called on first use
+    pub fn new<F: Fn() -> Vec<T> + Send + Sync + 'static>(generator: F) -> Self {
+        LazyDomain {
+            generator: Arc::new(generator),
+            cache: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// Returns true once the generator has run
+    pub fn is_materialized(&self) -> bool {
+        self.cache.get().is_some()
+    }
+
+    fn materialize(&self) -> &VecDomain<T> {
+        self.cache
+            .get_or_init(|| VecDomain::new((self.generator)()))
+    }
+
+    /// A domain that is already materialized with the given values
+    fn with_values(&self, domain: VecDomain<T>) -> Self {
+        LazyDomain {
+            generator: Arc::clone(&self.generator),
+            cache: Arc::new(OnceLock::from(domain)),
+        }
+    }
+}
+
+impl<T: Clone + Eq + Debug> Debug for LazyDomain<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.cache.get() {
+            Some(domain) => f.debug_tuple("LazyDomain").field(domain).finish(),
+            None => write!(f, "LazyDomain(<not generated>)"),
+        }
+    }
+}
+
+impl<T: Clone + Eq + Debug> Domain<T> for LazyDomain<T> {
+    fn contains(&self, value: &T) -> bool {
+        self.materialize().contains(value)
+    }
+
+    fn size(&self) -> usize {
+        self.materialize().size()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.materialize().is_empty()
+    }
+
+    fn values(&self) -> Vec<T> {
+        self.materialize().values()
+    }
+
+    fn remove(&self, value: &T) -> Self {
+        self.with_values(self.materialize().remove(value))
+    }
+
+    fn restrict_to<I: IntoIterator<Item = T>>(&self, values_to_keep: I) -> Self {
+        self.with_values(self.materialize().restrict_to(values_to_keep))
+    }
+}
+
+impl<T: Clone + Eq + Debug + Send + Sync + 'static> FromIterator<T> for LazyDomain<T> {
+    /// A domain that is already materialized with the given values
+    fn from_iter<I: IntoIterator<Item = T>>(values: I) -> Self {
+        let domain = VecDomain::new(values);
+        LazyDomain {
+            generator: Arc::new({
+                let domain = domain.clone();
+                move || domain.values()
+            }),
+            cache: Arc::new(OnceLock::from(domain)),
+        }
+    }
+}
+
+/// A `Domain<T>` seen as a `Domain<U>` through a bijection between `T` and `U`.
+/// Every operation converts its arguments to `T`, delegates to the inner
+/// domain and converts the results back
+#[derive(Clone)]
+pub struct DomainAdapter<T: Clone + Eq + Debug, U, D: Domain<T>> {
+    inner: D,
+    to_outer: Arc<dyn Fn(&T) -> U + Send + Sync>,
+    to_inner: Arc<dyn Fn(&U) -> T + Send + Sync>,
+}
+
+impl<T: Clone + Eq + Debug, U, D: Domain<T>> DomainAdapter<T, U, D> {
+    /// Wrap `inner`; `to_outer` and `to_inner` must be inverses of each other
+    pub fn new<F, G>(inner: D, to_outer: F, to_inner: G) -> Self
+    where
+        F: Fn(&T) -> U + Send + Sync + 'static,
+        G: Fn(&U) -> T + Send + Sync + 'static,
+    {
+        DomainAdapter {
+            inner,
+            to_outer: Arc::new(to_outer),
+            to_inner: Arc::new(to_inner),
+        }
+    }
+
+    /// The wrapped domain
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    /// An adapter sharing this one's mapping around a different inner domain
+    fn with_inner(&self, inner: D) -> Self {
+        DomainAdapter {
+            inner,
+            to_outer: Arc::clone(&self.to_outer),
+            to_inner: Arc::clone(&self.to_inner),
+        }
+    }
+}
+
+impl<T: Clone + Eq + Debug, U, D: Domain<T>> Debug for DomainAdapter<T, U, D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("DomainAdapter").field(&self.inner).finish()
+    }
+}
+
+impl<T, U, D> Domain<U> for DomainAdapter<T, U, D>
+where
+    T: Clone + Eq + Debug,
+    U: Clone + Eq + Debug,
+    D: Domain<T>,
+{
+    fn contains(&self, value: &U) -> bool {
+        self.inner.contains(&(self.to_inner)(value))
+    }
+
+    fn size(&self) -> usize {
+        self.inner.size()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    fn values(&self) -> Vec<U> {
+        self.inner
+            .values()
+            .iter()
+            .map(|v| (self.to_outer)(v))
+            .collect()
+    }
+
+    fn remove(&self, value: &U) -> Self {
+        self.with_inner(self.inner.remove(&(self.to_inner)(value)))
+    }
+
+    fn restrict_to<I: IntoIterator<Item = U>>(&self, values_to_keep: I) -> Self {
+        let kept: Vec<T> = values_to_keep
+            .into_iter()
+            .map(|v| (self.to_inner)(&v))
+            .collect();
+        self.with_inner(self.inner.restrict_to(kept))
+    }
+}
+
+/// Domain of every variant of an enum deriving `strum::EnumIter`, backed by a `VecDomain`
+#[cfg(feature = "enum-domain")]
+#[derive(Debug, Clone)]
+pub struct EnumDomain<T: Clone + Eq + Debug> {
+    inner: VecDomain<T>,
+}
+
+#[cfg(feature = "enum-domain")]
+impl<T: Clone + Eq + Debug + strum::IntoEnumIterator> EnumDomain<T> {
+    /// Create a domain holding every variant of `T`
+    pub fn new() -> Self {
+        EnumDomain {
+            inner: VecDomain::new(T::iter()),
+        }
+    }
+}
+
+#[cfg(feature = "enum-domain")]
+impl<T: Clone + Eq + Debug + strum::IntoEnumIterator> Default for EnumDomain<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "enum-domain")]
+impl<T: Clone + Eq + Debug> Domain<T> for EnumDomain<T> {
+    fn contains(&self, value: &T) -> bool {
+        self.inner.contains(value)
+    }
+
+    fn size(&self) -> usize {
+        self.inner.size()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    fn values(&self) -> Vec<T> {
+        self.inner.values()
+    }
+
+    fn remove(&self, value: &T) -> Self {
+        EnumDomain {
+            inner: self.inner.remove(value),
+        }
+    }
+
+    fn restrict_to<I: IntoIterator<Item = T>>(&self, values_to_keep: I) -> Self {
+        EnumDomain {
+            inner: self.inner.restrict_to(values_to_keep),
+        }
+    }
+}
+
+#[cfg(feature = "enum-domain")]
+impl<T: Clone + Eq + Debug> FromIterator<T> for EnumDomain<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(values: I) -> Self {
+        EnumDomain {
+            inner: VecDomain::new(values),
+        }
+    }
 }
 
 /// Factory methods to create domains
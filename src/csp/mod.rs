@@ -1,11 +1,21 @@
 pub mod assignment;
 pub mod constraint;
+#[allow(clippy::module_inception)]
 pub mod csp;
 pub mod domain;
 pub mod variable;
 
 pub use assignment::Assignment;
-pub use constraint::Constraint;
 pub use constraint::common;
-pub use domain::{BTreeSetDomain, Domain, HashSetDomain, SortedVecDomain, VecDomain};
-pub use variable::Variable;
+pub use constraint::{
+    Constraint, ConstraintActivityTracker, ConstraintKind, ConstraintState, IntelligentConstraint,
+    TableConstraint,
+};
+pub use csp::{DomainsSnapshot, SatConversionError, SolveProgress, ValidationError, Xcsp3Error};
+#[cfg(feature = "enum-domain")]
+pub use domain::EnumDomain;
+pub use domain::{
+    BTreeSetDomain, Domain, DomainAdapter, HashSetDomain, IntervalDomain, LazyDomain, SmallDomain,
+    SortedVecDomain, VecDomain,
+};
+pub use variable::{Variable, VariableBuilder, VariableSet};
@@ -1,5 +1,8 @@
+use std::collections::HashSet;
+use std::convert::Infallible;
 use std::fmt;
 use std::hash::Hash;
+use std::str::FromStr;
 
 /// A variable in a constraint satisfaction problem
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -17,6 +20,34 @@ impl<T> Variable<T> {
             _phantom: std::marker::PhantomData,
         }
     }
+
+    /// Creates a new variable taking ownership of the given name
+    pub fn from_name(name: String) -> Self {
+        Variable {
+            name,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<T> FromStr for Variable<T> {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Variable::new(s))
+    }
+}
+
+impl<T> From<&str> for Variable<T> {
+    fn from(name: &str) -> Self {
+        Variable::new(name)
+    }
+}
+
+impl<T> From<String> for Variable<T> {
+    fn from(name: String) -> Self {
+        Variable::from_name(name)
+    }
 }
 
 impl<T> fmt::Display for Variable<T> {
@@ -24,3 +55,126 @@ impl<T> fmt::Display for Variable<T> {
         write!(f, "{}", self.name)
     }
 }
+
+/// Variables are ordered by name. Implemented by hand because deriving would
+/// require `T: Ord`
+impl<T: Eq> PartialOrd for Variable<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Eq> Ord for Variable<T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.name.cmp(&other.name)
+    }
+}
+
+/// Creates a variable from a string literal
+#[macro_export]
+macro_rules! var {
+    ($name:literal) => {
+        $crate::csp::Variable::new($name)
+    };
+}
+
+/// Builder for variables with structured names
+#[derive(Debug, Clone)]
+pub struct VariableBuilder<T> {
+    name: String,
+    _phantom: std::marker::PhantomData<T>,
+}
+
+impl<T> VariableBuilder<T> {
+    /// Creates a new builder with an empty name
+    pub fn new() -> Self {
+        VariableBuilder {
+            name: String::new(),
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Sets the variable name
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = String::from(name);
+        self
+    }
+
+    /// Sets the variable name to the prefix followed by the index, e.g. `Q3`
+    pub fn indexed(mut self, prefix: &str, idx: usize) -> Self {
+        self.name = format!("{}{}", prefix, idx);
+        self
+    }
+
+    /// Returns the name the built variable will have
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Builds the variable
+    pub fn build(self) -> Variable<T> {
+        Variable::new(&self.name)
+    }
+}
+
+impl<T> Default for VariableBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A registry of variables that rejects duplicate names
+#[derive(Debug, Clone)]
+pub struct VariableSet<T> {
+    names: HashSet<String>,
+    _phantom: std::marker::PhantomData<T>,
+}
+
+impl<T> VariableSet<T> {
+    /// Creates a new empty set
+    pub fn new() -> Self {
+        VariableSet {
+            names: HashSet::new(),
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Builds the variable, failing if its name has already been registered
+    pub fn build(&mut self, builder: VariableBuilder<T>) -> Result<Variable<T>, String> {
+        if !self.names.insert(builder.name().to_string()) {
+            return Err(format!("Variable {} already exists", builder.name()));
+        }
+        Ok(builder.build())
+    }
+
+    /// Registers a variable with the given name
+    pub fn named(&mut self, name: &str) -> Result<Variable<T>, String> {
+        self.build(VariableBuilder::new().named(name))
+    }
+
+    /// Registers a variable named by prefix and index
+    pub fn indexed(&mut self, prefix: &str, idx: usize) -> Result<Variable<T>, String> {
+        self.build(VariableBuilder::new().indexed(prefix, idx))
+    }
+
+    /// Returns true if a variable with the given name has been registered
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.contains(name)
+    }
+
+    /// Returns the number of registered variables
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Returns true if no variables have been registered
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+impl<T> Default for VariableSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -2,13 +2,28 @@
 use crate::csp::Assignment;
 use crate::csp::constraint::common;
 use crate::csp::csp::Csp;
-use crate::csp::domain::HashSetDomain;
+#[cfg(feature = "enum-domain")]
+use crate::csp::domain::EnumDomain;
+use crate::csp::domain::{Domain, HashSetDomain};
 use crate::csp::variable::Variable;
 use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
 
 pub fn create_australia_csp() -> Csp<String, HashSetDomain<String>> {
+    // Define the colors as domain values
+    let colors = vec!["red".to_string(), "green".to_string(), "blue".to_string()];
+    create_australia_csp_with_domain(HashSetDomain::new(colors))
+}
+
+/// Build the Australia coloring CSP with every region using the given color domain
+pub fn create_australia_csp_with_domain<T, D>(domain: D) -> Csp<T, D>
+where
+    T: Clone + Eq + Hash + Debug + Send + Sync + 'static,
+    D: Domain<T>,
+{
     // Create a CSP for the Australian map coloring problem
-    let mut australia = Csp::<String, HashSetDomain<String>>::new();
+    let mut australia = Csp::<T, D>::new();
 
     // Define the regions as variables
     let wa = Variable::new("WA");
@@ -19,10 +34,6 @@ pub fn create_australia_csp() -> Csp<String, HashSetDomain<String>> {
     let v = Variable::new("V");
     let t = Variable::new("T");
 
-    // Define the colors as domain values
-    let colors = vec!["red".to_string(), "green".to_string(), "blue".to_string()];
-    let domain = HashSetDomain::new(colors);
-
     // Add variables to the CSP
     australia.add_variable(wa.clone(), domain.clone()).unwrap();
     australia.add_variable(nt.clone(), domain.clone()).unwrap();
@@ -64,6 +75,33 @@ pub fn create_australia_csp() -> Csp<String, HashSetDomain<String>> {
     australia
 }
 
+/// The three map colors as an enum, so the domain can be derived from its variants
+#[cfg(feature = "enum-domain")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, strum::EnumIter)]
+pub enum Color {
+    Red,
+    Green,
+    Blue,
+}
+
+#[cfg(feature = "enum-domain")]
+impl std::fmt::Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Color::Red => "red",
+            Color::Green => "green",
+            Color::Blue => "blue",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The Australia coloring CSP over the `Color` enum instead of strings
+#[cfg(feature = "enum-domain")]
+pub fn create_australia_enum_csp() -> Csp<Color, EnumDomain<Color>> {
+    create_australia_csp_with_domain(EnumDomain::new())
+}
+
 pub fn print_australia_map(assignment: Option<&Assignment<String>>) {
     // ASCII art representation of Australia
     let mut map = vec![
@@ -107,13 +145,13 @@ pub fn print_australia_map(assignment: Option<&Assignment<String>>) {
 
         for (name, (x, y)) in regions {
             let var = Variable::<String>::new(name);
-            if let Some(color) = assignment.get(&var) {
-                if let Some(code) = color_codes.get(color.as_str()) {
-                    let row = &mut map[y];
-                    let colored_char = format!("{}{}{}", code, " ", reset);
-                    let new_row = row[0..x].to_string() + &colored_char + &row[x + 1..].to_string();
-                    map[y] = new_row;
-                }
+            if let Some(color) = assignment.get(&var)
+                && let Some(code) = color_codes.get(color.as_str())
+            {
+                let row = &mut map[y];
+                let colored_char = format!("{}{}{}", code, " ", reset);
+                let new_row = row[0..x].to_string() + &colored_char + &row[x + 1..];
+                map[y] = new_row;
             }
         }
     }
@@ -0,0 +1,267 @@
+// examples/benchmark.rs
+use crate::csp::csp::Csp;
+use crate::csp::{Assignment, Domain};
+use crate::examples::map_coloring::MapColoringCsp;
+use crate::examples::{australia, queens, scheduling, sudoku, zebra};
+use crate::solver::heuristics::{least_constraining_value, minimum_remaining_values, mrv_degree};
+use crate::solver::utils::{domain_order, first_unassigned};
+use crate::solver::{
+    ArcConsistencySolver, BacktrackingSolver, FcCbjSolver, ForwardCheckingSolver, SolveResult,
+};
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Columns of the CSV report, in order
+pub const CSV_COLUMNS: [&str; 7] = [
+    "problem",
+    "algorithm",
+    "repetition",
+    "solved",
+    "timed_out",
+    "duration_ms",
+    "nodes_explored",
+];
+
+/// Names of the benchmark problems; "planar-map" stands for one map per seed
+pub const PROBLEMS: [&str; 6] = [
+    "australia",
+    "8-queens",
+    "sudoku-4x4",
+    "zebra",
+    "timetabling",
+    "planar-map",
+];
+
+/// Settings for a benchmark run
+#[derive(Debug, Clone)]
+pub struct BenchmarkConfig {
+    /// Which of `PROBLEMS` to run
+    pub problems: Vec<String>,
+    /// Number of times each (problem, algorithm) pair is run
+    pub repetitions: usize,
+    /// One random planar map coloring problem is generated per seed
+    pub seeds: Vec<u64>,
+    /// Wall-clock limit for the backtracking runs, which are the ones that take a deadline
+    pub time_limit: Duration,
+}
+
+impl Default for BenchmarkConfig {
+    fn default() -> Self {
+        BenchmarkConfig {
+            problems: PROBLEMS.iter().map(|name| name.to_string()).collect(),
+            repetitions: 3,
+            seeds: vec![1, 2, 3],
+            time_limit: Duration::from_secs(10),
+        }
+    }
+}
+
+/// One run of one algorithm on one problem
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkRecord {
+    pub problem: String,
+    pub algorithm: String,
+    pub repetition: usize,
+    pub solved: bool,
+    pub timed_out: bool,
+    pub duration: Duration,
+    /// Values tried, for the algorithms that count them
+    pub nodes_explored: Option<u64>,
+}
+
+/// An algorithm run under a deadline: the solution, whether it gave up, and nodes explored
+type Algorithm<T, D> = fn(&Csp<T, D>, Instant) -> (Option<Assignment<T>>, bool, Option<u64>);
+
+/// Runs every solver on every example problem and reports the results as CSV
+pub struct BenchmarkSuite;
+
+impl BenchmarkSuite {
+    /// Run the suite with the default configuration and write the CSV report
+    pub fn run(output_path: &Path) -> std::io::Result<Vec<BenchmarkRecord>> {
+        Self::run_with_config(output_path, &BenchmarkConfig::default())
+    }
+
+    /// Run the suite with the given configuration and write the CSV report
+    pub fn run_with_config(
+        output_path: &Path,
+        config: &BenchmarkConfig,
+    ) -> std::io::Result<Vec<BenchmarkRecord>> {
+        let records = Self::collect(config);
+        std::fs::write(output_path, Self::to_csv(&records))?;
+        Ok(records)
+    }
+
+    /// Run every (problem, algorithm) pair `config.repetitions` times
+    pub fn collect(config: &BenchmarkConfig) -> Vec<BenchmarkRecord> {
+        let selected = |name: &str| config.problems.iter().any(|problem| problem == name);
+        let mut records = Vec::new();
+        if selected("australia") {
+            let csp = australia::create_australia_csp();
+            Self::bench("australia", &csp, config, &mut records);
+        }
+        if selected("8-queens") {
+            Self::bench(
+                "8-queens",
+                &queens::create_queens_csp(8),
+                config,
+                &mut records,
+            );
+        }
+        if selected("sudoku-4x4") {
+            let csp = sudoku::create_sample_sudoku();
+            Self::bench("sudoku-4x4", &csp, config, &mut records);
+        }
+        if selected("zebra") {
+            Self::bench("zebra", &zebra::create_zebra_csp(), config, &mut records);
+        }
+        if selected("timetabling") {
+            let csp = scheduling::create_sample_timetabling_csp();
+            Self::bench("timetabling", &csp, config, &mut records);
+        }
+        if selected("planar-map") {
+            for &seed in &config.seeds {
+                let (regions, borders) = MapColoringCsp::random_planar_map(20, 80, seed);
+                let regions: Vec<&str> = regions.iter().map(String::as_str).collect();
+                let borders: Vec<(&str, &str)> = borders
+                    .iter()
+                    .map(|(a, b)| (a.as_str(), b.as_str()))
+                    .collect();
+                let map = MapColoringCsp::from_adjacency_list(&regions, &borders, 4);
+                let name = format!("planar-map-seed-{}", seed);
+                Self::bench(&name, &map, config, &mut records);
+            }
+        }
+        records
+    }
+
+    /// Render records as CSV with a header row; durations are in milliseconds
+    /// and a missing node count is an empty field
+    pub fn to_csv(records: &[BenchmarkRecord]) -> String {
+        let mut csv = CSV_COLUMNS.join(",");
+        csv.push('\n');
+        for record in records {
+            let nodes = record
+                .nodes_explored
+                .map(|nodes| nodes.to_string())
+                .unwrap_or_default();
+            let fields = [
+                Self::escape(&record.problem),
+                Self::escape(&record.algorithm),
+                record.repetition.to_string(),
+                record.solved.to_string(),
+                record.timed_out.to_string(),
+                format!("{:.3}", record.duration.as_secs_f64() * 1000.0),
+                nodes,
+            ];
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Quote a field if it contains a comma, quote or newline
+    fn escape(field: &str) -> String {
+        if field.contains([',', '"', '\n']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    }
+
+    fn bench<T, D>(
+        problem: &str,
+        csp: &Csp<T, D>,
+        config: &BenchmarkConfig,
+        records: &mut Vec<BenchmarkRecord>,
+    ) where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        for (algorithm, solve) in Self::algorithms::<T, D>() {
+            for repetition in 0..config.repetitions {
+                let start = Instant::now();
+                let (solution, timed_out, nodes_explored) = solve(csp, start + config.time_limit);
+                let duration = start.elapsed();
+
+                records.push(BenchmarkRecord {
+                    problem: problem.to_string(),
+                    algorithm: algorithm.to_string(),
+                    repetition,
+                    solved: solution.is_some_and(|solution| csp.is_solution(&solution)),
+                    timed_out,
+                    duration,
+                    nodes_explored,
+                });
+            }
+        }
+    }
+
+    fn algorithms<T, D>() -> Vec<(&'static str, Algorithm<T, D>)>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        fn bounded<T: Clone + Eq + Hash + Debug>(
+            result: SolveResult<T>,
+        ) -> (Option<Assignment<T>>, bool, Option<u64>) {
+            let nodes = Some(result.stats().nodes_explored);
+            match result {
+                SolveResult::Solved(solution, _) => (Some(solution), false, nodes),
+                SolveResult::NoSolution(_) => (None, false, nodes),
+                SolveResult::Timeout(_) => (None, true, nodes),
+            }
+        }
+
+        vec![
+            ("Backtracking", |csp, deadline| {
+                bounded(BacktrackingSolver::solve_full(
+                    csp,
+                    first_unassigned,
+                    domain_order,
+                    Some(deadline),
+                    None,
+                ))
+            }),
+            ("MRV", |csp, deadline| {
+                bounded(BacktrackingSolver::solve_full(
+                    csp,
+                    minimum_remaining_values,
+                    domain_order,
+                    Some(deadline),
+                    None,
+                ))
+            }),
+            ("MRV+LCV", |csp, deadline| {
+                bounded(BacktrackingSolver::solve_full(
+                    csp,
+                    minimum_remaining_values,
+                    least_constraining_value,
+                    Some(deadline),
+                    None,
+                ))
+            }),
+            ("MRV+Degree", |csp, deadline| {
+                bounded(BacktrackingSolver::solve_full(
+                    csp,
+                    mrv_degree,
+                    domain_order,
+                    Some(deadline),
+                    None,
+                ))
+            }),
+            ("Forward Checking", |csp, _| {
+                let (solution, stats) = ForwardCheckingSolver::solve_with_stats(csp);
+                (solution, false, Some(stats.nodes_explored))
+            }),
+            ("FC-CBJ", |csp, _| {
+                let (solution, stats) = FcCbjSolver::solve_with_stats(csp);
+                (solution, false, Some(stats.nodes_explored))
+            }),
+            ("Arc Consistency", |csp, _| {
+                (ArcConsistencySolver::solve(csp), false, None)
+            }),
+        ]
+    }
+}
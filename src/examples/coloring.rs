@@ -0,0 +1,160 @@
+// examples/coloring.rs
+use crate::csp::Assignment;
+use crate::csp::constraint::common;
+use crate::csp::csp::Csp;
+use crate::csp::domain::VecDomain;
+use crate::csp::variable::Variable;
+
+/// The 50 US states by postal code
+pub const US_STATES: [&str; 50] = [
+    "AK", "AL", "AR", "AZ", "CA", "CO", "CT", "DE", "FL", "GA", "HI", "IA", "ID", "IL", "IN", "KS",
+    "KY", "LA", "MA", "MD", "ME", "MI", "MN", "MO", "MS", "MT", "NC", "ND", "NE", "NH", "NJ", "NM",
+    "NV", "NY", "OH", "OK", "OR", "PA", "RI", "SC", "SD", "TN", "TX", "UT", "VA", "VT", "WA", "WI",
+    "WV", "WY",
+];
+
+/// Pairs of US states sharing a land border (corner-only contacts excluded)
+pub const US_BORDERS: [(&str, &str); 105] = [
+    ("AL", "FL"),
+    ("AL", "GA"),
+    ("AL", "MS"),
+    ("AL", "TN"),
+    ("AR", "LA"),
+    ("AR", "MO"),
+    ("AR", "MS"),
+    ("AR", "OK"),
+    ("AR", "TN"),
+    ("AR", "TX"),
+    ("AZ", "CA"),
+    ("AZ", "NM"),
+    ("AZ", "NV"),
+    ("AZ", "UT"),
+    ("CA", "NV"),
+    ("CA", "OR"),
+    ("CO", "KS"),
+    ("CO", "NE"),
+    ("CO", "NM"),
+    ("CO", "OK"),
+    ("CO", "UT"),
+    ("CO", "WY"),
+    ("CT", "MA"),
+    ("CT", "NY"),
+    ("CT", "RI"),
+    ("DE", "MD"),
+    ("DE", "NJ"),
+    ("DE", "PA"),
+    ("FL", "GA"),
+    ("GA", "NC"),
+    ("GA", "SC"),
+    ("GA", "TN"),
+    ("IA", "IL"),
+    ("IA", "MN"),
+    ("IA", "MO"),
+    ("IA", "NE"),
+    ("IA", "SD"),
+    ("IA", "WI"),
+    ("ID", "MT"),
+    ("ID", "NV"),
+    ("ID", "OR"),
+    ("ID", "UT"),
+    ("ID", "WA"),
+    ("ID", "WY"),
+    ("IL", "IN"),
+    ("IL", "KY"),
+    ("IL", "MO"),
+    ("IL", "WI"),
+    ("IN", "KY"),
+    ("IN", "MI"),
+    ("IN", "OH"),
+    ("KS", "MO"),
+    ("KS", "NE"),
+    ("KS", "OK"),
+    ("KY", "MO"),
+    ("KY", "OH"),
+    ("KY", "TN"),
+    ("KY", "VA"),
+    ("KY", "WV"),
+    ("LA", "MS"),
+    ("LA", "TX"),
+    ("MA", "NH"),
+    ("MA", "NY"),
+    ("MA", "RI"),
+    ("MA", "VT"),
+    ("MD", "PA"),
+    ("MD", "VA"),
+    ("MD", "WV"),
+    ("ME", "NH"),
+    ("MI", "OH"),
+    ("MI", "WI"),
+    ("MN", "ND"),
+    ("MN", "SD"),
+    ("MN", "WI"),
+    ("MO", "NE"),
+    ("MO", "OK"),
+    ("MO", "TN"),
+    ("MS", "TN"),
+    ("MT", "ND"),
+    ("MT", "SD"),
+    ("MT", "WY"),
+    ("NC", "SC"),
+    ("NC", "TN"),
+    ("NC", "VA"),
+    ("ND", "SD"),
+    ("NE", "SD"),
+    ("NE", "WY"),
+    ("NH", "VT"),
+    ("NJ", "NY"),
+    ("NJ", "PA"),
+    ("NM", "OK"),
+    ("NM", "TX"),
+    ("NV", "OR"),
+    ("NV", "UT"),
+    ("NY", "PA"),
+    ("NY", "VT"),
+    ("OH", "PA"),
+    ("OH", "WV"),
+    ("OK", "TX"),
+    ("OR", "WA"),
+    ("PA", "WV"),
+    ("SD", "WY"),
+    ("TN", "VA"),
+    ("UT", "WY"),
+    ("VA", "WV"),
+];
+
+pub fn create_map_coloring_csp(
+    regions: &[&str],
+    adjacencies: &[(&str, &str)],
+    num_colors: usize,
+) -> Csp<usize, VecDomain<usize>> {
+    let mut csp = Csp::<usize, VecDomain<usize>>::new();
+
+    // One variable per region, colored 0..num_colors
+    for region in regions {
+        let domain = VecDomain::new(0..num_colors);
+        csp.add_variable(Variable::new(region), domain).unwrap();
+    }
+
+    // Neighboring regions must have different colors
+    for (a, b) in adjacencies {
+        let name = format!("{}-{}", a, b);
+        csp.add_constraint(common::diff(&name, Variable::new(a), Variable::new(b)))
+            .unwrap();
+    }
+
+    csp
+}
+
+pub fn four_color_america_csp() -> Csp<usize, VecDomain<usize>> {
+    create_map_coloring_csp(&US_STATES, &US_BORDERS, 4)
+}
+
+pub fn print_coloring_solution(regions: &[&str], assignment: &Assignment<usize>) {
+    println!("Map Coloring:");
+    for region in regions {
+        let color = assignment
+            .get(&Variable::new(region))
+            .map_or("?".to_string(), |c| c.to_string());
+        println!("  {:4} -> color {}", region, color);
+    }
+}
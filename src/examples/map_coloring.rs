@@ -0,0 +1,52 @@
+// examples/map_coloring.rs
+use crate::csp::csp::Csp;
+use crate::csp::domain::VecDomain;
+use crate::examples::coloring::create_map_coloring_csp;
+use crate::solver::utils::SeededRng;
+
+/// Map coloring problems built from region and border lists
+pub struct MapColoringCsp;
+
+/// Region names and the borders between them
+pub type PlanarMap = (Vec<String>, Vec<(String, String)>);
+
+impl MapColoringCsp {
+    /// One variable per region colored 0..num_colors, with neighbors colored differently
+    pub fn from_adjacency_list(
+        regions: &[&str],
+        borders: &[(&str, &str)],
+        num_colors: usize,
+    ) -> Csp<usize, VecDomain<usize>> {
+        create_map_coloring_csp(regions, borders, num_colors)
+    }
+
+    /// Random planar map: a stacked triangulation (each new region splits a
+    /// random triangular face into three) with each border kept with
+    /// probability `border_percent`/100. Removing edges keeps the graph planar
+    pub fn random_planar_map(num_regions: usize, border_percent: u64, seed: u64) -> PlanarMap {
+        let mut rng = SeededRng::new(seed);
+        let regions: Vec<String> = (0..num_regions).map(|i| format!("R{:02}", i)).collect();
+
+        let mut edges: Vec<(usize, usize)> = Vec::new();
+        let mut faces: Vec<[usize; 3]> = Vec::new();
+        if num_regions >= 3 {
+            edges.extend([(0, 1), (0, 2), (1, 2)]);
+            // the outer face stays uncovered, the inner face is split
+            faces.push([0, 1, 2]);
+        } else if num_regions == 2 {
+            edges.push((0, 1));
+        }
+        for new in 3..num_regions {
+            let [a, b, c] = faces.swap_remove(rng.below(faces.len()));
+            edges.extend([(a, new), (b, new), (c, new)]);
+            faces.extend([[a, b, new], [a, c, new], [b, c, new]]);
+        }
+
+        let borders = edges
+            .into_iter()
+            .filter(|_| (rng.below(100) as u64) < border_percent)
+            .map(|(a, b)| (regions[a].clone(), regions[b].clone()))
+            .collect();
+        (regions, borders)
+    }
+}
@@ -1,3 +1,9 @@
 pub mod australia;
+pub mod benchmark;
+pub mod coloring;
+pub mod map_coloring;
 pub mod queens;
+pub mod random_csp;
+pub mod scheduling;
 pub mod sudoku;
+pub mod zebra;
@@ -1,8 +1,10 @@
 use crate::csp::Assignment;
-use crate::csp::constraint::Constraint;
+use crate::csp::constraint::{Constraint, common};
 use crate::csp::csp::Csp;
-use crate::csp::domain::VecDomain;
+use crate::csp::domain::{Domain, VecDomain};
 use crate::csp::variable::Variable;
+use crate::solver::BacktrackingSolver;
+use crate::solver::symmetry::{Symmetry, count_solutions_up_to_symmetry};
 
 pub fn create_queens_csp(size: usize) -> Csp<usize, VecDomain<usize>> {
     let mut csp = Csp::<usize, VecDomain<usize>>::new();
@@ -51,6 +53,189 @@ pub fn create_queens_csp(size: usize) -> Csp<usize, VecDomain<usize>> {
     csp
 }
 
+/// Create the n-queens CSP with some queens already placed, given as (column, row)
+/// Fails if a placement is off the board, repeats a column, or attacks another placed queen
+pub fn create_queens_csp_with_preassignment(
+    size: usize,
+    preassigned: &[(usize, usize)],
+) -> Result<Csp<usize, VecDomain<usize>>, String> {
+    for (i, &(col, row)) in preassigned.iter().enumerate() {
+        if col >= size || row >= size {
+            return Err(format!(
+                "Queen at column {} row {} is off the board",
+                col, row
+            ));
+        }
+        for &(other_col, other_row) in &preassigned[..i] {
+            if other_col == col {
+                return Err(format!("Column {} has more than one queen", col));
+            }
+            if other_row == row || other_col.abs_diff(col) == other_row.abs_diff(row) {
+                return Err(format!(
+                    "Queen at column {} row {} attacks queen at column {} row {}",
+                    col, row, other_col, other_row
+                ));
+            }
+        }
+    }
+
+    let mut csp = create_queens_csp(size);
+    for &(col, row) in preassigned {
+        let var = Variable::new(&format!("Q{}", col));
+        csp.add_constraint(common::in_set(
+            &format!("Preassigned-{}", col),
+            var,
+            vec![row],
+        ))?;
+    }
+    csp.apply_unary_constraints();
+    Ok(csp)
+}
+
+/// Create the n-queens CSP on a board with blocked cells, given as (row, column).
+/// Each obstacle removes its row from that column's domain; obstacles off the
+/// board are ignored
+pub fn create_queens_with_obstacles_csp(
+    n: usize,
+    obstacles: &[(usize, usize)],
+) -> Csp<usize, VecDomain<usize>> {
+    let mut csp = create_queens_csp(n);
+    for &(row, col) in obstacles {
+        let var = Variable::new(&format!("Q{}", col));
+        if let Some(domain) = csp.get_domain(&var) {
+            let reduced = domain.remove(&row);
+            csp.set_domain(&var, reduced).unwrap();
+        }
+    }
+    csp
+}
+
+/// Create a knight's tour CSP on an n x n board: `T{i}` is the square (row * n + col)
+/// visited at step `i`, consecutive steps are a knight's move apart, and every
+/// square is visited once
+pub fn create_knights_tour_csp(n: usize) -> Csp<usize, VecDomain<usize>> {
+    let mut csp = Csp::<usize, VecDomain<usize>>::new();
+    let steps: Vec<Variable<usize>> = (0..n * n)
+        .map(|i| Variable::new(&format!("T{}", i)))
+        .collect();
+
+    for step in &steps {
+        csp.add_variable(step.clone(), VecDomain::new(0..n * n))
+            .unwrap();
+    }
+
+    // Consecutive squares must be a knight's move apart
+    for i in 0..steps.len().saturating_sub(1) {
+        let from = steps[i].clone();
+        let to = steps[i + 1].clone();
+        let name = format!("KnightMove-{}-{}", i, i + 1);
+        let vars = vec![from.clone(), to.clone()];
+        let constraint = Constraint::new(&name, vars, move |assignment| {
+            match (assignment.get(&from), assignment.get(&to)) {
+                (Some(a), Some(b)) => {
+                    let rows = (a / n).abs_diff(b / n);
+                    let cols = (a % n).abs_diff(b % n);
+                    (rows == 1 && cols == 2) || (rows == 2 && cols == 1)
+                }
+                _ => true,
+            }
+        });
+        csp.add_constraint(constraint).unwrap();
+    }
+
+    // Every square is visited exactly once
+    csp.add_constraint(common::all_different("VisitOnce", steps))
+        .unwrap();
+
+    csp
+}
+
+/// Maps a (column, row) cell to its image given the last board index
+type SquareTransform = fn(usize, usize, usize) -> (usize, usize);
+
+/// The seven non-identity symmetries of the square board (rotations and reflections)
+pub fn queens_symmetries(size: usize) -> Vec<Symmetry<usize>> {
+    let last = size - 1;
+    let transforms: Vec<SquareTransform> = vec![
+        |col, row, last| (last - row, col),        // rotate 90
+        |col, row, last| (last - col, last - row), // rotate 180
+        |col, row, last| (row, last - col),        // rotate 270
+        |col, row, last| (last - col, row),        // mirror columns
+        |col, row, last| (col, last - row),        // mirror rows
+        |col, row, _| (row, col),                  // main diagonal
+        |col, row, last| (last - row, last - col), // anti-diagonal
+    ];
+
+    transforms
+        .into_iter()
+        .map(|transform| {
+            Box::new(move |assignment: &Assignment<usize>| {
+                let mut image = Assignment::new();
+                for col in 0..size {
+                    let var = Variable::new(&format!("Q{}", col));
+                    if let Some(row) = assignment.get(&var) {
+                        let (new_col, new_row) = transform(col, *row, last);
+                        image.assign(Variable::new(&format!("Q{}", new_col)), new_row);
+                    }
+                }
+                image
+            }) as Symmetry<usize>
+        })
+        .collect()
+}
+
+/// Number of N-queens solutions for n = 1..=20 (OEIS A000170)
+pub const QUEENS_SOLUTION_COUNTS: &[u64] = &[
+    1,
+    0,
+    0,
+    2,
+    10,
+    4,
+    40,
+    92,
+    352,
+    724,
+    2680,
+    14200,
+    73712,
+    365596,
+    2279184,
+    14772512,
+    95815104,
+    666090624,
+    4968057848,
+    39029188884,
+];
+
+/// Count every N-queens solution by exhaustive backtracking. Mirroring the board
+/// top to bottom swaps solutions with the first queen in the top half for ones
+/// with it in the bottom half, so only the top half and, on an odd board, the
+/// middle row are searched
+pub fn count_queens_solutions_exactly(n: usize) -> u64 {
+    let csp = create_queens_csp(n);
+    if n == 0 {
+        return BacktrackingSolver::count_solutions(&csp);
+    }
+
+    let first = Variable::new("Q0");
+    (0..n.div_ceil(2))
+        .map(|row| {
+            let count = BacktrackingSolver::count_solutions(&csp.with_assumption(&first, row));
+            if 2 * row + 1 == n { count } else { 2 * count }
+        })
+        .sum()
+}
+
+/// Count N-queens solutions up to the 8 rotations and reflections of the board
+/// (OEIS A002562)
+pub fn count_queens_solutions_up_to_symmetry(n: usize) -> u64 {
+    if n == 0 {
+        return 1;
+    }
+    count_solutions_up_to_symmetry(&create_queens_csp(n), &queens_symmetries(n)) as u64
+}
+
 pub fn print_queens_board(size: usize, assignment: Option<&Assignment<usize>>) {
     println!("{}x{} Queens Problem:", size, size);
 
@@ -74,7 +259,7 @@ pub fn print_queens_board(size: usize, assignment: Option<&Assignment<usize>>) {
         for col in 0..size {
             let var = Variable::<usize>::new(&format!("Q{}", col));
             let has_queen = if let Some(assignment) = assignment {
-                assignment.get(&var).map_or(false, |r| *r == row)
+                assignment.get(&var).is_some_and(|r| *r == row)
             } else {
                 false
             };
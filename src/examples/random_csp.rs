@@ -0,0 +1,57 @@
+// examples/random_csp.rs
+use crate::csp::constraint::Constraint;
+use crate::csp::csp::Csp;
+use crate::csp::domain::VecDomain;
+use crate::csp::variable::Variable;
+use crate::solver::utils::SeededRng;
+use std::collections::HashSet;
+
+/// Random binary CSP (model B): `num_vars` variables over 0..domain_size, each
+/// pair constrained with probability `density`, and each value pair of a
+/// constrained pair forbidden with probability `tightness`. The same seed
+/// always builds the same problem
+pub fn create_random_binary_csp(
+    num_vars: usize,
+    domain_size: usize,
+    density: f64,
+    tightness: f64,
+    seed: u64,
+) -> Csp<usize, VecDomain<usize>> {
+    let mut rng = SeededRng::new(seed);
+    let mut csp = Csp::new();
+    let vars: Vec<Variable<usize>> = (0..num_vars)
+        .map(|i| Variable::new(&format!("X{:02}", i)))
+        .collect();
+    for var in &vars {
+        csp.add_variable(var.clone(), VecDomain::new(0..domain_size))
+            .unwrap();
+    }
+
+    for i in 0..num_vars {
+        for j in i + 1..num_vars {
+            if rng.unit() >= density {
+                continue;
+            }
+            let mut forbidden = HashSet::new();
+            for a in 0..domain_size {
+                for b in 0..domain_size {
+                    if rng.unit() < tightness {
+                        forbidden.insert((a, b));
+                    }
+                }
+            }
+
+            let (xi, xj) = (vars[i].clone(), vars[j].clone());
+            let name = format!("{}-{}", xi, xj);
+            let scope = vec![xi.clone(), xj.clone()];
+            csp.add_constraint(Constraint::new(&name, scope, move |assignment| {
+                match (assignment.get(&xi), assignment.get(&xj)) {
+                    (Some(a), Some(b)) => !forbidden.contains(&(*a, *b)),
+                    _ => true,
+                }
+            }))
+            .unwrap();
+        }
+    }
+    csp
+}
@@ -0,0 +1,133 @@
+// examples/scheduling.rs
+use crate::csp::constraint::{Constraint, common};
+use crate::csp::csp::Csp;
+use crate::csp::domain::VecDomain;
+use crate::csp::variable::Variable;
+use std::fmt;
+
+/// A course to timetable, taught by one teacher to one student group
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Course {
+    pub name: String,
+    pub teacher: String,
+    pub group: String,
+    pub enrollment: usize,
+}
+
+/// A room courses can be held in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Room {
+    pub name: String,
+    pub capacity: usize,
+}
+
+/// When and where a course is held: a (timeslot, room) pair, indexing the rooms
+/// passed to `create_timetabling_csp`. A struct rather than a tuple so it can
+/// implement `Display`, which the solvers require
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Slot {
+    pub timeslot: usize,
+    pub room: usize,
+}
+
+impl fmt::Display for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "t{}r{}", self.timeslot, self.room)
+    }
+}
+
+/// The variable holding the slot of the given course
+pub fn course_var(course: &Course) -> Variable<Slot> {
+    Variable::new(&course.name)
+}
+
+/// University timetabling: each course gets a slot in a room large enough for
+/// it, no two courses share a slot, and courses with the same teacher or
+/// student group are held at different times
+pub fn create_timetabling_csp(
+    courses: &[Course],
+    rooms: &[Room],
+    timeslots: usize,
+) -> Csp<Slot, VecDomain<Slot>> {
+    let mut csp = Csp::<Slot, VecDomain<Slot>>::new();
+    let slots: Vec<Slot> = (0..timeslots)
+        .flat_map(|timeslot| (0..rooms.len()).map(move |room| Slot { timeslot, room }))
+        .collect();
+
+    for course in courses {
+        csp.add_variable(course_var(course), VecDomain::new(slots.clone()))
+            .unwrap();
+
+        // Only rooms that seat every enrolled student
+        let fitting = slots
+            .iter()
+            .filter(|slot| rooms[slot.room].capacity >= course.enrollment)
+            .copied()
+            .collect();
+        let name = format!("{}-capacity", course.name);
+        csp.add_constraint(common::in_set(&name, course_var(course), fitting))
+            .unwrap();
+    }
+
+    for (i, first) in courses.iter().enumerate() {
+        for second in &courses[i + 1..] {
+            let (x, y) = (course_var(first), course_var(second));
+            let name = format!("{}-{}", first.name, second.name);
+
+            if first.teacher == second.teacher || first.group == second.group {
+                // A shared teacher or group can't be in two places at once
+                let constraint =
+                    Constraint::new(&name, vec![x.clone(), y.clone()], move |a| {
+                        match (a.get(&x), a.get(&y)) {
+                            (Some(s1), Some(s2)) => s1.timeslot != s2.timeslot,
+                            _ => true,
+                        }
+                    });
+                csp.add_constraint(constraint).unwrap();
+            } else {
+                // One course per room at a time
+                csp.add_constraint(common::diff(&name, x, y)).unwrap();
+            }
+        }
+    }
+
+    csp
+}
+
+/// Six courses, three rooms and four timeslots
+pub fn sample_timetabling_instance() -> (Vec<Course>, Vec<Room>, usize) {
+    let course = |name: &str, teacher: &str, group: &str, enrollment| Course {
+        name: name.to_string(),
+        teacher: teacher.to_string(),
+        group: group.to_string(),
+        enrollment,
+    };
+    let courses = vec![
+        course("Calculus", "Noether", "Math1", 110),
+        course("Algebra", "Noether", "Math2", 40),
+        course("Mechanics", "Curie", "Phys1", 90),
+        course("Optics", "Curie", "Phys2", 25),
+        course("Statistics", "Fisher", "Math1", 55),
+        course("Probability", "Fisher", "Phys1", 60),
+    ];
+    let rooms = vec![
+        Room {
+            name: "Seminar".to_string(),
+            capacity: 30,
+        },
+        Room {
+            name: "Classroom".to_string(),
+            capacity: 60,
+        },
+        Room {
+            name: "Hall".to_string(),
+            capacity: 120,
+        },
+    ];
+    (courses, rooms, 4)
+}
+
+pub fn create_sample_timetabling_csp() -> Csp<Slot, VecDomain<Slot>> {
+    let (courses, rooms, timeslots) = sample_timetabling_instance();
+    create_timetabling_csp(&courses, &rooms, timeslots)
+}
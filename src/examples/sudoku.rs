@@ -1,19 +1,144 @@
 // examples/sudoku.rs
 use crate::csp::Assignment;
-use crate::csp::constraint::common;
+use crate::csp::constraint::{Constraint, common};
 use crate::csp::csp::Csp;
-use crate::csp::domain::VecDomain;
+use crate::csp::domain::{Domain, VecDomain};
 use crate::csp::variable::Variable;
 
 // 4x4 Sudoku has values 1-4 and 2x2 boxes
 pub fn create_sudoku_csp(initial_values: &[(usize, usize, usize)]) -> Csp<usize, VecDomain<usize>> {
+    create_standard_sudoku_csp(4, initial_values).unwrap()
+}
+
+/// Which main diagonal of the board an all-different constraint covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Diagonal {
+    /// Top-left to bottom-right
+    Main,
+    /// Top-right to bottom-left
+    Anti,
+}
+
+/// Sudoku X: an n x n Sudoku whose two main diagonals must also hold 1..=n once each.
+/// Fails unless n is a perfect square
+pub fn create_sudoku_x_csp(
+    n: usize,
+    puzzle: &[(usize, usize, usize)],
+) -> Result<Csp<usize, VecDomain<usize>>, String> {
+    let mut csp = create_standard_sudoku_csp(n, puzzle)?;
+    add_diagonal_constraint(&mut csp, n, Diagonal::Main);
+    add_diagonal_constraint(&mut csp, n, Diagonal::Anti);
+    Ok(csp)
+}
+
+/// Require the cells on the given diagonal of an n x n board to be all different
+pub fn add_diagonal_constraint(
+    csp: &mut Csp<usize, VecDomain<usize>>,
+    n: usize,
+    diagonal: Diagonal,
+) {
+    let cells = (0..n)
+        .map(|row| match diagonal {
+            Diagonal::Main => cell_var(row, row),
+            Diagonal::Anti => cell_var(row, n - 1 - row),
+        })
+        .collect();
+    let name = format!("{:?}Diagonal", diagonal);
+    csp.add_constraint(common::all_different(&name, cells))
+        .unwrap();
+}
+
+/// A Killer Sudoku cage: its cells hold distinct values adding up to `sum`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KillerCage {
+    /// (row, column) of each cell in the cage
+    pub cells: Vec<(usize, usize)>,
+    pub sum: usize,
+}
+
+/// Killer Sudoku: an n x n Sudoku whose cages must each hold distinct values
+/// summing to the cage total. Cage cells start with only the values that can
+/// reach the total alongside distinct values in the other cells. Fails unless
+/// n is a perfect square and every cage cell is on the board
+pub fn create_killer_sudoku_csp(
+    n: usize,
+    initial: &[(usize, usize, usize)],
+    cages: &[KillerCage],
+) -> Result<Csp<usize, VecDomain<usize>>, String> {
+    let mut csp = create_standard_sudoku_csp(n, initial)?;
+
+    for (index, cage) in cages.iter().enumerate() {
+        let cells: Vec<Variable<usize>> = cage
+            .cells
+            .iter()
+            .map(|&(row, col)| cell_var(row, col))
+            .collect();
+
+        // The other cells add at least 1 + 2 + ... and at most n + (n - 1) + ...
+        let others = cells.len().saturating_sub(1);
+        let least_others = others * (others + 1) / 2;
+        let most_others = others * n - others.saturating_sub(1) * others / 2;
+        let lowest = cage.sum.saturating_sub(most_others);
+        let highest = cage.sum.saturating_sub(least_others);
+        for cell in &cells {
+            let domain = csp
+                .get_domain(cell)
+                .ok_or_else(|| format!("Cage {} has cell {} off the board", index, cell))?;
+            let reduced = domain.restrict_to(
+                domain
+                    .values()
+                    .into_iter()
+                    .filter(|value| (lowest..=highest).contains(value))
+                    .collect::<Vec<_>>(),
+            );
+            csp.set_domain(cell, reduced).unwrap();
+        }
+
+        let name = format!("Cage{}", index);
+        csp.add_constraint(common::all_different(&name, cells.clone()))
+            .unwrap();
+
+        // Partial sums may never exceed the total, so the search can cut a cage short
+        let target = cage.sum;
+        let summed = cells.clone();
+        let sum = Constraint::new_partial(&format!("{}Sum", name), cells, move |assignment| {
+            let values: Vec<usize> = summed
+                .iter()
+                .filter_map(|cell| assignment.get(cell).copied())
+                .collect();
+            let total: usize = values.iter().sum();
+            if values.len() == summed.len() {
+                total == target
+            } else {
+                total < target
+            }
+        });
+        csp.add_constraint(sum).unwrap();
+    }
+
+    Ok(csp)
+}
+
+/// The variable holding the value of the cell at (row, col), named `C{row}_{col}`
+pub fn cell_var(row: usize, col: usize) -> Variable<usize> {
+    Variable::new(&format!("C{}_{}", row, col))
+}
+
+/// Sudoku on an n x n board with row, column and box constraints.
+/// Fails unless n is a perfect square, which the boxes need
+pub fn create_standard_sudoku_csp(
+    n: usize,
+    initial_values: &[(usize, usize, usize)],
+) -> Result<Csp<usize, VecDomain<usize>>, String> {
+    let box_size = n.isqrt();
+    if box_size * box_size != n {
+        return Err(format!("Board size {} is not a perfect square", n));
+    }
     let mut csp = Csp::<usize, VecDomain<usize>>::new();
 
     // Create variables for each cell
-    for row in 0..4 {
-        for col in 0..4 {
-            let var = Variable::new(&format!("C{}{}", row, col));
-
+    for row in 0..n {
+        for col in 0..n {
             // Check if there's an initial value for this cell
             let initial = initial_values
                 .iter()
@@ -22,54 +147,45 @@ pub fn create_sudoku_csp(initial_values: &[(usize, usize, usize)]) -> Csp<usize,
             if let Some((_, _, value)) = initial {
                 // Set domain to just the initial value
                 let domain = VecDomain::new(vec![*value]);
-                csp.add_variable(var, domain).unwrap();
+                csp.add_variable(cell_var(row, col), domain).unwrap();
             } else {
                 // Set domain to all possible values
-                let domain = VecDomain::new(vec![1, 2, 3, 4]);
-                csp.add_variable(var, domain).unwrap();
+                let domain = VecDomain::new(1..=n);
+                csp.add_variable(cell_var(row, col), domain).unwrap();
             }
         }
     }
 
     // Add row constraints (all different in each row)
-    for row in 0..4 {
-        let mut row_vars = Vec::new();
-        for col in 0..4 {
-            row_vars.push(Variable::new(&format!("C{}{}", row, col)));
-        }
+    for row in 0..n {
+        let row_vars = (0..n).map(|col| cell_var(row, col)).collect();
         let constraint = common::all_different(&format!("Row{}", row), row_vars);
         csp.add_constraint(constraint).unwrap();
     }
 
     // Add column constraints (all different in each column)
-    for col in 0..4 {
-        let mut col_vars = Vec::new();
-        for row in 0..4 {
-            col_vars.push(Variable::new(&format!("C{}{}", row, col)));
-        }
+    for col in 0..n {
+        let col_vars = (0..n).map(|row| cell_var(row, col)).collect();
         let constraint = common::all_different(&format!("Col{}", col), col_vars);
         csp.add_constraint(constraint).unwrap();
     }
 
-    // Add box constraints (all different in each 2x2 box)
-    for box_row in 0..2 {
-        for box_col in 0..2 {
+    // Add box constraints (all different in each box)
+    for box_row in 0..box_size {
+        for box_col in 0..box_size {
             let mut box_vars = Vec::new();
-            for row in 0..2 {
-                for col in 0..2 {
-                    box_vars.push(Variable::new(&format!(
-                        "C{}{}",
-                        box_row * 2 + row,
-                        box_col * 2 + col
-                    )));
+            for row in 0..box_size {
+                for col in 0..box_size {
+                    box_vars.push(cell_var(box_row * box_size + row, box_col * box_size + col));
                 }
             }
-            let constraint = common::all_different(&format!("Box{}{}", box_row, box_col), box_vars);
+            let name = format!("Box{}_{}", box_row, box_col);
+            let constraint = common::all_different(&name, box_vars);
             csp.add_constraint(constraint).unwrap();
         }
     }
 
-    csp
+    Ok(csp)
 }
 
 pub fn print_sudoku_board(assignment: Option<&Assignment<usize>>) {
@@ -82,7 +198,7 @@ pub fn print_sudoku_board(assignment: Option<&Assignment<usize>>) {
         print!("|");
 
         for col in 0..4 {
-            let var = Variable::<usize>::new(&format!("C{}{}", row, col));
+            let var = cell_var(row, col);
 
             let value = if let Some(assignment) = assignment {
                 assignment
@@ -106,11 +222,6 @@ pub fn print_sudoku_board(assignment: Option<&Assignment<usize>>) {
             println!("+-----------+");
         }
     }
-
-    // Make sure we have a bottom border if the last row wasn't a box boundary
-    if 4 % 2 != 0 {
-        println!("+-----------+");
-    }
 }
 
 pub fn create_sample_sudoku() -> Csp<usize, VecDomain<usize>> {
@@ -127,3 +238,114 @@ pub fn create_sample_sudoku() -> Csp<usize, VecDomain<usize>> {
 
     create_sudoku_csp(&initial_values)
 }
+
+pub fn create_sample_sudoku_x() -> Csp<usize, VecDomain<usize>> {
+    // A 9x9 Sudoku X with a unique solution
+    // Format: (row, column, value)
+    let initial_values = vec![
+        (0, 2, 4),
+        (0, 5, 7),
+        (0, 7, 9),
+        (0, 8, 8),
+        (1, 1, 7),
+        (1, 2, 6),
+        (1, 3, 1),
+        (1, 5, 9),
+        (1, 7, 2),
+        (1, 8, 5),
+        (2, 0, 1),
+        (2, 3, 5),
+        (2, 7, 7),
+        (3, 0, 5),
+        (3, 1, 6),
+        (3, 5, 1),
+        (3, 6, 3),
+        (4, 1, 9),
+        (4, 5, 8),
+        (5, 1, 2),
+        (5, 3, 7),
+        (5, 4, 5),
+        (5, 5, 6),
+        (5, 8, 4),
+        (6, 4, 2),
+        (6, 7, 3),
+        (6, 8, 6),
+        (7, 1, 4),
+        (7, 3, 8),
+        (7, 4, 1),
+        (7, 5, 3),
+        (7, 7, 5),
+        (8, 0, 9),
+        (8, 2, 8),
+    ];
+
+    create_sudoku_x_csp(9, &initial_values).unwrap()
+}
+
+/// Givens and cages of a 9x9 Killer Sudoku: dominoes down columns 0, 3 and 6
+/// and along each row of every pair of rows, then three cages across the bottom row
+pub fn sample_killer_sudoku() -> (Vec<(usize, usize, usize)>, Vec<KillerCage>) {
+    // Format: (row, column, value)
+    let initial_values = vec![
+        (0, 0, 5),
+        (1, 4, 9),
+        (2, 8, 7),
+        (3, 2, 9),
+        (4, 6, 7),
+        (5, 1, 1),
+        (6, 5, 7),
+        (7, 3, 4),
+        (8, 7, 7),
+    ];
+    let cage = |cells: &[(usize, usize)], sum| KillerCage {
+        cells: cells.to_vec(),
+        sum,
+    };
+    let cages = vec![
+        cage(&[(0, 0), (1, 0)], 11),
+        cage(&[(0, 3), (1, 3)], 7),
+        cage(&[(0, 6), (1, 6)], 12),
+        cage(&[(0, 1), (0, 2)], 7),
+        cage(&[(0, 4), (0, 5)], 15),
+        cage(&[(0, 7), (0, 8)], 3),
+        cage(&[(1, 1), (1, 2)], 9),
+        cage(&[(1, 4), (1, 5)], 14),
+        cage(&[(1, 7), (1, 8)], 12),
+        cage(&[(2, 0), (3, 0)], 9),
+        cage(&[(2, 3), (3, 3)], 10),
+        cage(&[(2, 6), (3, 6)], 9),
+        cage(&[(2, 1), (2, 2)], 17),
+        cage(&[(2, 4), (2, 5)], 6),
+        cage(&[(2, 7), (2, 8)], 13),
+        cage(&[(3, 1), (3, 2)], 14),
+        cage(&[(3, 4), (3, 5)], 7),
+        cage(&[(3, 7), (3, 8)], 5),
+        cage(&[(4, 0), (5, 0)], 11),
+        cage(&[(4, 3), (5, 3)], 17),
+        cage(&[(4, 6), (5, 6)], 15),
+        cage(&[(4, 1), (4, 2)], 8),
+        cage(&[(4, 4), (4, 5)], 8),
+        cage(&[(4, 7), (4, 8)], 10),
+        cage(&[(5, 1), (5, 2)], 4),
+        cage(&[(5, 4), (5, 5)], 6),
+        cage(&[(5, 7), (5, 8)], 11),
+        cage(&[(6, 0), (7, 0)], 11),
+        cage(&[(6, 3), (7, 3)], 9),
+        cage(&[(6, 6), (7, 6)], 8),
+        cage(&[(6, 1), (6, 2)], 7),
+        cage(&[(6, 4), (6, 5)], 10),
+        cage(&[(6, 7), (6, 8)], 12),
+        cage(&[(7, 1), (7, 2)], 15),
+        cage(&[(7, 4), (7, 5)], 10),
+        cage(&[(7, 7), (7, 8)], 8),
+        cage(&[(8, 0), (8, 1), (8, 2)], 12),
+        cage(&[(8, 3), (8, 4), (8, 5)], 16),
+        cage(&[(8, 6), (8, 7), (8, 8)], 17),
+    ];
+    (initial_values, cages)
+}
+
+pub fn create_sample_killer_sudoku() -> Csp<usize, VecDomain<usize>> {
+    let (initial_values, cages) = sample_killer_sudoku();
+    create_killer_sudoku_csp(9, &initial_values, &cages).unwrap()
+}
@@ -0,0 +1,389 @@
+// examples/zebra.rs
+use crate::csp::Assignment;
+use crate::csp::constraint::{Constraint, common};
+use crate::csp::csp::Csp;
+use crate::csp::domain::HashSetDomain;
+use crate::csp::variable::Variable;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    Red,
+    Green,
+    Ivory,
+    Yellow,
+    Blue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Nationality {
+    English,
+    Spanish,
+    Ukrainian,
+    Norwegian,
+    Japanese,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Drink {
+    Coffee,
+    Tea,
+    Milk,
+    OrangeJuice,
+    Water,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Smoke {
+    OldGold,
+    Kools,
+    Chesterfield,
+    LuckyStrike,
+    Parliament,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pet {
+    Dog,
+    Snails,
+    Fox,
+    Horse,
+    Zebra,
+}
+
+/// A single domain value type so every attribute can live in one CSP
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Attribute {
+    Color(Color),
+    Nationality(Nationality),
+    Drink(Drink),
+    Smoke(Smoke),
+    Pet(Pet),
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl fmt::Display for Nationality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl fmt::Display for Drink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl fmt::Display for Smoke {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl fmt::Display for Pet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl fmt::Display for Attribute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Attribute::Color(c) => write!(f, "{}", c),
+            Attribute::Nationality(n) => write!(f, "{}", n),
+            Attribute::Drink(d) => write!(f, "{}", d),
+            Attribute::Smoke(s) => write!(f, "{}", s),
+            Attribute::Pet(p) => write!(f, "{}", p),
+        }
+    }
+}
+
+const HOUSES: usize = 5;
+const CATEGORIES: [&str; 5] = ["Color", "Nationality", "Drink", "Smoke", "Pet"];
+
+/// The variable holding the given category for the given house (0-based)
+pub fn house_var(category: &str, house: usize) -> Variable<Attribute> {
+    Variable::new(&format!("{}{}", category, house + 1))
+}
+
+fn category_values(category: &str) -> Vec<Attribute> {
+    match category {
+        "Color" => [
+            Color::Red,
+            Color::Green,
+            Color::Ivory,
+            Color::Yellow,
+            Color::Blue,
+        ]
+        .into_iter()
+        .map(Attribute::Color)
+        .collect(),
+        "Nationality" => [
+            Nationality::English,
+            Nationality::Spanish,
+            Nationality::Ukrainian,
+            Nationality::Norwegian,
+            Nationality::Japanese,
+        ]
+        .into_iter()
+        .map(Attribute::Nationality)
+        .collect(),
+        "Drink" => [
+            Drink::Coffee,
+            Drink::Tea,
+            Drink::Milk,
+            Drink::OrangeJuice,
+            Drink::Water,
+        ]
+        .into_iter()
+        .map(Attribute::Drink)
+        .collect(),
+        "Smoke" => [
+            Smoke::OldGold,
+            Smoke::Kools,
+            Smoke::Chesterfield,
+            Smoke::LuckyStrike,
+            Smoke::Parliament,
+        ]
+        .into_iter()
+        .map(Attribute::Smoke)
+        .collect(),
+        _ => [Pet::Dog, Pet::Snails, Pet::Fox, Pet::Horse, Pet::Zebra]
+            .into_iter()
+            .map(Attribute::Pet)
+            .collect(),
+    }
+}
+
+/// In every house, `a` holds `a_value` exactly when `b` holds `b_value`
+fn same_house(
+    csp: &mut Csp<Attribute, HashSetDomain<Attribute>>,
+    a: (&str, Attribute),
+    b: (&str, Attribute),
+) {
+    for house in 0..HOUSES {
+        let var_a = house_var(a.0, house);
+        let var_b = house_var(b.0, house);
+        let name = format!("{}={}@{}", a.1, b.1, house + 1);
+        let vars = vec![var_a.clone(), var_b.clone()];
+        let (a_value, b_value) = (a.1, b.1);
+        let constraint = Constraint::new(&name, vars, move |assignment| {
+            match (assignment.get(&var_a), assignment.get(&var_b)) {
+                (Some(va), Some(vb)) => (*va == a_value) == (*vb == b_value),
+                _ => true,
+            }
+        });
+        csp.add_constraint(constraint).unwrap();
+    }
+}
+
+/// The house holding `a_value` is adjacent to the house holding `b_value`
+fn next_to(
+    csp: &mut Csp<Attribute, HashSetDomain<Attribute>>,
+    a: (&str, Attribute),
+    b: (&str, Attribute),
+) {
+    for house in 0..HOUSES {
+        let var_a = house_var(a.0, house);
+        let neighbors: Vec<_> = [house.checked_sub(1), Some(house + 1)]
+            .into_iter()
+            .flatten()
+            .filter(|n| *n < HOUSES)
+            .map(|n| house_var(b.0, n))
+            .collect();
+
+        let mut vars = vec![var_a.clone()];
+        vars.extend(neighbors.iter().cloned());
+        let name = format!("{}~{}@{}", a.1, b.1, house + 1);
+        let (a_value, b_value) = (a.1, b.1);
+        let constraint = Constraint::new(&name, vars, move |assignment| {
+            if assignment.get(&var_a) != Some(&a_value) {
+                return true;
+            }
+            neighbors
+                .iter()
+                .any(|n| assignment.get(n) == Some(&b_value))
+        });
+        csp.add_constraint(constraint).unwrap();
+    }
+}
+
+pub fn create_zebra_csp() -> Csp<Attribute, HashSetDomain<Attribute>> {
+    let mut csp = Csp::<Attribute, HashSetDomain<Attribute>>::new();
+
+    // One variable per house and category
+    for category in CATEGORIES {
+        for house in 0..HOUSES {
+            let domain = HashSetDomain::new(category_values(category));
+            csp.add_variable(house_var(category, house), domain)
+                .unwrap();
+        }
+    }
+
+    // Each attribute value belongs to exactly one house
+    for category in CATEGORIES {
+        let vars = (0..HOUSES)
+            .map(|house| house_var(category, house))
+            .collect();
+        csp.add_constraint(common::all_different(
+            &format!("AllDiff-{}", category),
+            vars,
+        ))
+        .unwrap();
+    }
+
+    // The Englishman lives in the red house
+    same_house(
+        &mut csp,
+        ("Nationality", Attribute::Nationality(Nationality::English)),
+        ("Color", Attribute::Color(Color::Red)),
+    );
+    // The Spaniard owns the dog
+    same_house(
+        &mut csp,
+        ("Nationality", Attribute::Nationality(Nationality::Spanish)),
+        ("Pet", Attribute::Pet(Pet::Dog)),
+    );
+    // Coffee is drunk in the green house
+    same_house(
+        &mut csp,
+        ("Drink", Attribute::Drink(Drink::Coffee)),
+        ("Color", Attribute::Color(Color::Green)),
+    );
+    // The Ukrainian drinks tea
+    same_house(
+        &mut csp,
+        (
+            "Nationality",
+            Attribute::Nationality(Nationality::Ukrainian),
+        ),
+        ("Drink", Attribute::Drink(Drink::Tea)),
+    );
+
+    // The green house is immediately to the right of the ivory house
+    for house in 0..HOUSES - 1 {
+        let ivory = house_var("Color", house);
+        let green = house_var("Color", house + 1);
+        let vars = vec![ivory.clone(), green.clone()];
+        let name = format!("Ivory-Green@{}", house + 1);
+        let constraint = Constraint::new(&name, vars, move |assignment| {
+            match (assignment.get(&ivory), assignment.get(&green)) {
+                (Some(left), Some(right)) => {
+                    (*left == Attribute::Color(Color::Ivory))
+                        == (*right == Attribute::Color(Color::Green))
+                }
+                _ => true,
+            }
+        });
+        csp.add_constraint(constraint).unwrap();
+    }
+    csp.add_constraint(common::not_in(
+        "Green-not-first",
+        house_var("Color", 0),
+        vec![Attribute::Color(Color::Green)],
+    ))
+    .unwrap();
+    csp.add_constraint(common::not_in(
+        "Ivory-not-last",
+        house_var("Color", HOUSES - 1),
+        vec![Attribute::Color(Color::Ivory)],
+    ))
+    .unwrap();
+
+    // The Old Gold smoker owns snails
+    same_house(
+        &mut csp,
+        ("Smoke", Attribute::Smoke(Smoke::OldGold)),
+        ("Pet", Attribute::Pet(Pet::Snails)),
+    );
+    // Kools are smoked in the yellow house
+    same_house(
+        &mut csp,
+        ("Smoke", Attribute::Smoke(Smoke::Kools)),
+        ("Color", Attribute::Color(Color::Yellow)),
+    );
+
+    // Milk is drunk in the middle house
+    csp.add_constraint(common::in_set(
+        "Milk-middle",
+        house_var("Drink", 2),
+        vec![Attribute::Drink(Drink::Milk)],
+    ))
+    .unwrap();
+    // The Norwegian lives in the first house
+    csp.add_constraint(common::in_set(
+        "Norwegian-first",
+        house_var("Nationality", 0),
+        vec![Attribute::Nationality(Nationality::Norwegian)],
+    ))
+    .unwrap();
+
+    // The Chesterfield smoker lives next to the fox owner
+    next_to(
+        &mut csp,
+        ("Smoke", Attribute::Smoke(Smoke::Chesterfield)),
+        ("Pet", Attribute::Pet(Pet::Fox)),
+    );
+    // Kools are smoked next to the house with the horse
+    next_to(
+        &mut csp,
+        ("Smoke", Attribute::Smoke(Smoke::Kools)),
+        ("Pet", Attribute::Pet(Pet::Horse)),
+    );
+    // The Lucky Strike smoker drinks orange juice
+    same_house(
+        &mut csp,
+        ("Smoke", Attribute::Smoke(Smoke::LuckyStrike)),
+        ("Drink", Attribute::Drink(Drink::OrangeJuice)),
+    );
+    // The Japanese smokes Parliaments
+    same_house(
+        &mut csp,
+        ("Nationality", Attribute::Nationality(Nationality::Japanese)),
+        ("Smoke", Attribute::Smoke(Smoke::Parliament)),
+    );
+    // The Norwegian lives next to the blue house
+    next_to(
+        &mut csp,
+        (
+            "Nationality",
+            Attribute::Nationality(Nationality::Norwegian),
+        ),
+        ("Color", Attribute::Color(Color::Blue)),
+    );
+
+    csp.apply_unary_constraints();
+    csp
+}
+
+/// Find the house (0-based) holding the given attribute value
+pub fn find_house(assignment: &Assignment<Attribute>, value: Attribute) -> Option<usize> {
+    CATEGORIES.iter().find_map(|category| {
+        (0..HOUSES).find(|house| assignment.get(&house_var(category, *house)) == Some(&value))
+    })
+}
+
+pub fn print_zebra_solution(assignment: &Assignment<Attribute>) {
+    println!("Zebra Puzzle:");
+    print!("{:12}", "");
+    for house in 0..HOUSES {
+        print!("| {:12}", format!("House {}", house + 1));
+    }
+    println!();
+
+    for category in CATEGORIES {
+        print!("{:12}", category);
+        for house in 0..HOUSES {
+            let value = assignment
+                .get(&house_var(category, house))
+                .map_or("?".to_string(), |v| v.to_string());
+            print!("| {:12}", value);
+        }
+        println!();
+    }
+}
@@ -1,3 +1,4 @@
+use csp_solver::csp::SmallDomain;
 use csp_solver::solver::heuristics::mrv_degree;
 use csp_solver::solver::utils::domain_order;
 use csp_solver::solver::{ArcConsistencySolver, ForwardCheckingSolver};
@@ -27,6 +28,11 @@ fn main() {
     test_queens_problem(12);
     println!();
 
+    // test zebra puzzle with enum domain values
+    println!("--- Zebra Puzzle ---");
+    test_zebra_problem();
+    println!();
+
     // demonstrate finding multiple solutions
     println!("=== Multiple Solutions Demo ===");
     demonstrate_multiple_solutions();
@@ -91,6 +97,28 @@ fn test_australia_problem() {
             examples::australia::print_australia_map(Some(&solution));
         }
     }
+
+    // trace how much uncertainty remains at each node of the arc consistency search
+    let mut entropies = Vec::new();
+    let mut last_progress = None;
+    ArcConsistencySolver::solve_with_callback(&csp, |assignment, _, entropy| {
+        entropies.push(format!("{:.2}", entropy));
+        last_progress = Some(csp.progress(assignment));
+    });
+    println!(
+        "Remaining entropy (bits) per AC node: {}",
+        entropies.join(" -> ")
+    );
+    if let Some(progress) = last_progress {
+        println!(
+            "Final node: {}/{} variables, {}/{} checkable constraints satisfied ({:.0}%)",
+            progress.current_depth,
+            progress.total_variables,
+            progress.satisfied_constraints,
+            progress.checkable_constraints,
+            progress.satisfaction_rate * 100.0
+        );
+    }
 }
 
 fn test_sudoku_problem() {
@@ -207,14 +235,31 @@ fn test_queens_problem(n: usize) {
         println!("Fastest: {} ({:.2?})", fastest_name, fastest_time);
 
         // show solution for smaller boards
-        if n <= 8 {
-            if let Some(solution) = BacktrackingSolver::backtrack_search(&csp) {
-                examples::queens::print_queens_board(n, Some(&solution));
-            }
+        if n <= 8
+            && let Some(solution) = BacktrackingSolver::backtrack_search(&csp)
+        {
+            examples::queens::print_queens_board(n, Some(&solution));
         }
     }
 }
 
+fn test_zebra_problem() {
+    let csp = examples::zebra::create_zebra_csp();
+    println!(
+        "Variables: {}, Constraints: {}",
+        csp.num_variables(),
+        csp.num_constraints()
+    );
+
+    let start = Instant::now();
+    let solution = ForwardCheckingSolver::solve(&csp);
+    println!("Forward Checking solved in {:.2?}", start.elapsed());
+
+    if let Some(solution) = solution {
+        examples::zebra::print_zebra_solution(&solution);
+    }
+}
+
 fn demonstrate_multiple_solutions() {
     let queens_4 = examples::queens::create_queens_csp(4);
     let all_solutions = BacktrackingSolver::find_all_backtracking(&queens_4);
@@ -308,6 +353,35 @@ fn stress_test() {
     for (i, (name, time)) in times.iter().enumerate() {
         println!("{}. {} - {:?}", i + 1, name, time);
     }
+
+    // backtracking restores only the domains changed by propagation
+    let queens_12 = examples::queens::create_queens_csp(12);
+    let start = Instant::now();
+    let _ = ArcConsistencySolver::solve(&queens_12);
+    println!(
+        "\n12-Queens AC solver (snapshot undo) - {:?}",
+        start.elapsed()
+    );
+
+    // stack-allocated domains avoid a heap allocation per domain clone
+    println!("\nAustralia AC solver, domain representation (100 runs):");
+    let colors = ["red", "green", "blue"].map(String::from);
+    let hash_csp = examples::australia::create_australia_csp();
+    let small_csp = examples::australia::create_australia_csp_with_domain(
+        SmallDomain::<String, 3>::new(colors).unwrap(),
+    );
+
+    let start = Instant::now();
+    for _ in 0..100 {
+        let _ = ArcConsistencySolver::solve(&hash_csp);
+    }
+    println!("HashSetDomain       - {:?}", start.elapsed());
+
+    let start = Instant::now();
+    for _ in 0..100 {
+        let _ = ArcConsistencySolver::solve(&small_csp);
+    }
+    println!("SmallDomain<_, 3>   - {:?}", start.elapsed());
 }
 
 #[cfg(test)]
@@ -356,4 +430,4275 @@ mod tests {
             assert!(queens_4.is_solution(solution));
         }
     }
+
+    #[test]
+    fn test_table_constraint_from_diff() {
+        use csp_solver::csp::{HashSetDomain, Variable, common};
+        use std::collections::HashMap;
+
+        let x = Variable::<i32>::new("X");
+        let y = Variable::<i32>::new("Y");
+        let mut domains = HashMap::new();
+        domains.insert(x.clone(), HashSetDomain::new(vec![1, 2, 3]));
+        domains.insert(y.clone(), HashSetDomain::new(vec![1, 2, 3]));
+
+        let constraint = common::diff("X-Y", x, y);
+        assert!(constraint.is_table_compatible());
+
+        let table = constraint.to_table_constraint(&domains);
+        assert_eq!(table.tuples().len(), 6);
+        assert!(table.allows(&[1, 2]));
+        assert!(!table.allows(&[2, 2]));
+    }
+
+    #[test]
+    fn test_solve_all_mac() {
+        use csp_solver::solver::utils::first_unassigned;
+        use std::cell::Cell;
+
+        let queens_4 = examples::queens::create_queens_csp(4);
+        let solutions = ForwardCheckingSolver::solve_all_mac(&queens_4);
+        assert_eq!(solutions.len(), 2);
+
+        let queens_8 = examples::queens::create_queens_csp(8);
+        let (mac_solutions, mac_stats) = ForwardCheckingSolver::solve_all_mac_with_stats(&queens_8);
+        assert_eq!(mac_solutions.len(), 92);
+        for solution in &mac_solutions {
+            assert!(queens_8.is_solution(solution));
+        }
+
+        // every value plain backtracking tries is a node
+        let bt_nodes = Cell::new(0);
+        let bt_solutions = BacktrackingSolver::find_all_solutions(
+            &queens_8,
+            first_unassigned,
+            |var, domain, assignment, csp| {
+                let values = domain_order(var, domain, assignment, csp);
+                bt_nodes.set(bt_nodes.get() + values.len() as u64);
+                values
+            },
+        );
+        assert_eq!(mac_solutions.len(), bt_solutions.len());
+        assert!(mac_stats.nodes_explored < bt_nodes.get());
+    }
+
+    #[test]
+    fn test_adjacent_variables() {
+        use csp_solver::csp::Variable;
+
+        let australia = examples::australia::create_australia_csp();
+        let sa = Variable::new("SA");
+        let neighbors = australia.adjacent_variables(&sa);
+        assert_eq!(neighbors.len(), 5);
+        for name in ["WA", "NT", "Q", "NSW", "V"] {
+            assert!(neighbors.contains(&&Variable::new(name)));
+        }
+
+        assert!(australia.are_adjacent(&sa, &Variable::new("WA")));
+        assert!(!australia.are_adjacent(&sa, &Variable::new("T")));
+    }
+
+    #[test]
+    fn test_dom_ddeg_search() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Variable, VecDomain, common};
+        use csp_solver::solver::SolveResult;
+        use csp_solver::solver::heuristics::{dom_ddeg, minimum_remaining_values};
+
+        let queens_8 = examples::queens::create_queens_csp(8);
+        let solution = BacktrackingSolver::dom_ddeg_search(&queens_8);
+        assert!(solution.is_some());
+        assert!(queens_8.is_solution(&solution.unwrap()));
+
+        let australia = examples::australia::create_australia_csp();
+        let solution = BacktrackingSolver::dom_ddeg_search(&australia).unwrap();
+        assert!(australia.is_solution(&solution));
+
+        // Four variables that must all differ over three values, next to six
+        // pairs of two-valued variables that only constrain each other. MRV
+        // assigns the pairs first, as their domains are smaller, and refutes
+        // the core under each of their 64 combinations. A pair variable's
+        // dynamic degree drops to zero once its partner is assigned, and the
+        // core variables have the higher degree, so dom/ddeg refutes the core once
+        let mut csp: Csp<usize, VecDomain<usize>> = Csp::new();
+        let core: Vec<Variable<usize>> =
+            (0..4).map(|i| Variable::new(&format!("K{}", i))).collect();
+        for var in &core {
+            csp.add_variable(var.clone(), VecDomain::new(0..3)).unwrap();
+        }
+        for (i, a) in core.iter().enumerate() {
+            for b in &core[i + 1..] {
+                let name = format!("{}-{}", a, b);
+                csp.add_constraint(common::diff(&name, a.clone(), b.clone()))
+                    .unwrap();
+            }
+        }
+        for i in 0..6 {
+            let p = Variable::new(&format!("P{}", i));
+            let q = Variable::new(&format!("Q{}", i));
+            csp.add_variable(p.clone(), VecDomain::new(0..2)).unwrap();
+            csp.add_variable(q.clone(), VecDomain::new(0..2)).unwrap();
+            let name = format!("{}-{}", p, q);
+            csp.add_constraint(common::diff(&name, p, q)).unwrap();
+        }
+
+        let ddeg = BacktrackingSolver::solve_full(&csp, dom_ddeg, domain_order, None, None);
+        let mrv = BacktrackingSolver::solve_full(
+            &csp,
+            minimum_remaining_values,
+            domain_order,
+            None,
+            None,
+        );
+        assert!(matches!(ddeg, SolveResult::NoSolution(_)));
+        assert!(matches!(mrv, SolveResult::NoSolution(_)));
+        assert!(
+            ddeg.stats().nodes_explored * 10 < mrv.stats().nodes_explored,
+            "dom/ddeg explored {} nodes, MRV {}",
+            ddeg.stats().nodes_explored,
+            mrv.stats().nodes_explored
+        );
+    }
+
+    #[test]
+    fn test_unary_constraints_restrict_domains() {
+        use csp_solver::csp::{Domain, Variable, common};
+
+        let mut australia = examples::australia::create_australia_csp();
+        let wa = Variable::new("WA");
+        let sa = Variable::new("SA");
+        australia
+            .add_constraint(common::not_in(
+                "WA-not-red",
+                wa.clone(),
+                vec!["red".to_string()],
+            ))
+            .unwrap();
+        australia
+            .add_constraint(common::in_set(
+                "SA-blue",
+                sa.clone(),
+                vec!["blue".to_string()],
+            ))
+            .unwrap();
+
+        australia.apply_unary_constraints();
+        assert_eq!(australia.get_domain(&wa).unwrap().size(), 2);
+        assert!(
+            !australia
+                .get_domain(&wa)
+                .unwrap()
+                .contains(&"red".to_string())
+        );
+        assert_eq!(australia.get_domain(&sa).unwrap().size(), 1);
+
+        let solution = BacktrackingSolver::backtrack_search(&australia).unwrap();
+        assert_eq!(solution.get(&sa), Some(&"blue".to_string()));
+    }
+
+    #[test]
+    fn test_iterative_backtracking_deep_chain() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Variable, VecDomain, common};
+        use csp_solver::solver::utils::domain_order;
+
+        let n = 10_000;
+        let mut chain = Csp::<usize, VecDomain<usize>>::new();
+        for i in 0..n {
+            chain
+                .add_variable(Variable::new(&format!("X{}", i)), VecDomain::new(0..2))
+                .unwrap();
+        }
+        for i in 0..n - 1 {
+            let constraint = common::diff(
+                &format!("X{}-X{}", i, i + 1),
+                Variable::new(&format!("X{}", i)),
+                Variable::new(&format!("X{}", i + 1)),
+            );
+            chain.add_constraint(constraint).unwrap();
+        }
+
+        // assign variables in chain order without scanning every variable
+        let next_in_chain = |assignment: &csp_solver::csp::Assignment<usize>, _: &Csp<_, _>| {
+            (assignment.size() < n).then(|| Variable::new(&format!("X{}", assignment.size())))
+        };
+
+        let solution =
+            BacktrackingSolver::find_solution_iterative(&chain, next_in_chain, domain_order)
+                .unwrap();
+        assert_eq!(solution.size(), n);
+        assert_ne!(
+            solution.get(&Variable::new("X0")),
+            solution.get(&Variable::new("X1"))
+        );
+
+        let queens_6 = examples::queens::create_queens_csp(6);
+        let solution = BacktrackingSolver::find_solution_iterative(
+            &queens_6,
+            csp_solver::solver::utils::first_unassigned,
+            domain_order,
+        )
+        .unwrap();
+        assert!(queens_6.is_solution(&solution));
+    }
+
+    #[test]
+    fn test_variable_set_rejects_duplicates() {
+        use csp_solver::csp::{Variable, VariableBuilder, VariableSet};
+
+        let mut vars = VariableSet::<usize>::new();
+        let q0 = vars.indexed("Q", 0).unwrap();
+        assert_eq!(q0, Variable::new("Q0"));
+        assert!(vars.named("Q0").is_err());
+        assert!(vars.build(VariableBuilder::new().indexed("Q", 0)).is_err());
+        assert!(vars.named("Q1").is_ok());
+        assert_eq!(vars.len(), 2);
+
+        let wa: Variable<String> = csp_solver::var!("WA");
+        assert_eq!(wa, VariableBuilder::new().named("WA").build());
+    }
+
+    #[test]
+    fn test_forward_checking_stats() {
+        let queens_8 = examples::queens::create_queens_csp(8);
+        let (solution, stats) = ForwardCheckingSolver::solve_with_stats(&queens_8);
+        assert!(queens_8.is_solution(&solution.unwrap()));
+        assert!(stats.values_pruned > 0);
+        assert!(stats.total_domain_reductions > 0);
+
+        let (_, again) = ForwardCheckingSolver::solve_with_stats(&queens_8);
+        assert_eq!(stats, again);
+    }
+
+    #[test]
+    fn test_forward_checking_look_ahead() {
+        let queens_12 = examples::queens::create_queens_csp(12);
+        let (plain, plain_stats) = ForwardCheckingSolver::solve_with_stats(&queens_12);
+        let (ahead, ahead_stats) = ForwardCheckingSolver::solve_with_look_ahead_stats(&queens_12);
+        assert!(queens_12.is_solution(&plain.unwrap()));
+        assert!(queens_12.is_solution(&ahead.unwrap()));
+        assert!(ahead_stats.nodes_explored < plain_stats.nodes_explored);
+    }
+
+    #[test]
+    fn test_validate() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{ValidationError, Variable, VecDomain, common};
+
+        assert_eq!(
+            examples::australia::create_australia_csp().validate(),
+            Ok(())
+        );
+
+        // add_constraint already rejects unknown variables, so that check can't be hit here
+        let (x, y) = (Variable::new("X"), Variable::new("Y"));
+        let mut csp = Csp::<u8, VecDomain<u8>>::new();
+        csp.add_variable(x.clone(), VecDomain::new(vec![1, 2, 3]))
+            .unwrap();
+        csp.add_variable(y.clone(), VecDomain::new(vec![1, 2]))
+            .unwrap();
+        csp.add_variable(Variable::new("W"), VecDomain::new(vec![]))
+            .unwrap();
+        csp.add_constraint(common::diff("XY", x.clone(), y.clone()))
+            .unwrap();
+        csp.add_constraint(common::same("XY", x.clone(), y.clone()))
+            .unwrap();
+        csp.add_constraint(common::sum("total", vec![x.clone(), y.clone()], 6))
+            .unwrap();
+        csp.add_constraint(common::sum("reachable", vec![x, y], 5))
+            .unwrap();
+
+        assert_eq!(
+            csp.validate(),
+            Err(vec![
+                ValidationError::EmptyDomain("W".to_string()),
+                ValidationError::DuplicateConstraintName("XY".to_string()),
+                ValidationError::UnreachableSum {
+                    constraint: "total".to_string(),
+                    target: 6,
+                    min: 2,
+                    max: 5,
+                },
+            ])
+        );
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "malformed CSP")]
+    fn test_solvers_validate_in_debug_builds() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Variable, VecDomain, common};
+
+        let mut csp = Csp::<u8, VecDomain<u8>>::new();
+        let (x, y) = (Variable::new("X"), Variable::new("Y"));
+        csp.add_variable(x.clone(), VecDomain::new(vec![1, 2]))
+            .unwrap();
+        csp.add_variable(y.clone(), VecDomain::new(vec![1, 2]))
+            .unwrap();
+        csp.add_constraint(common::diff("XY", x.clone(), y.clone()))
+            .unwrap();
+        csp.add_constraint(common::diff("XY", y, x)).unwrap();
+        ForwardCheckingSolver::solve(&csp);
+    }
+
+    #[test]
+    fn test_solvers_report_infeasible_csps() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Variable, VecDomain, common};
+        use csp_solver::examples::queens::create_queens_with_obstacles_csp;
+        use csp_solver::solver::MacNogoodSolver;
+
+        // an empty domain is unsatisfiable, not malformed
+        let mut empty = Csp::<u8, VecDomain<u8>>::new();
+        empty
+            .add_variable(Variable::new("X"), VecDomain::new(vec![]))
+            .unwrap();
+        empty
+            .add_variable(Variable::new("Y"), VecDomain::new(vec![1, 2]))
+            .unwrap();
+        assert!(BacktrackingSolver::backtrack_search(&empty).is_none());
+        assert!(BacktrackingSolver::find_all_backtracking(&empty).is_empty());
+        assert!(ForwardCheckingSolver::solve(&empty).is_none());
+        assert!(ArcConsistencySolver::solve(&empty).is_none());
+        assert!(MacNogoodSolver::solve(&empty).is_none());
+
+        let blocked_column = create_queens_with_obstacles_csp(4, &[(0, 0), (1, 0), (2, 0), (3, 0)]);
+        assert!(BacktrackingSolver::find_all_backtracking(&blocked_column).is_empty());
+
+        // X + Y can't reach 10 when both are at most 2
+        let mut unreachable = Csp::<i32, VecDomain<i32>>::new();
+        let vars = vec![Variable::new("X"), Variable::new("Y")];
+        for var in &vars {
+            unreachable
+                .add_variable(var.clone(), VecDomain::new(vec![1, 2]))
+                .unwrap();
+        }
+        unreachable
+            .add_constraint(common::sum("Total", vars, 10))
+            .unwrap();
+        assert!(unreachable.validate().is_err());
+        assert!(BacktrackingSolver::backtrack_search(&unreachable).is_none());
+        assert!(ForwardCheckingSolver::solve(&unreachable).is_none());
+    }
+
+    #[test]
+    fn test_phase_saving() {
+        use csp_solver::csp::{Assignment, Domain, Variable, VecDomain, csp::Csp};
+        use csp_solver::solver::heuristics::{PhaseCache, phase_saving_value_order};
+        use std::cell::RefCell;
+
+        fn by_name<D: Domain<usize>>(
+            assignment: &Assignment<usize>,
+            csp: &Csp<usize, D>,
+        ) -> Option<Variable<usize>> {
+            csp.get_variables()
+                .into_iter()
+                .filter(|var| !assignment.is_assigned(var))
+                .min_by(|a, b| a.name.cmp(&b.name))
+        }
+
+        let queens_12 = examples::queens::create_queens_csp(12);
+        let cache = RefCell::new(PhaseCache::new());
+        let phase_saving = |var: &Variable<usize>,
+                            domain: &VecDomain<usize>,
+                            assignment: &Assignment<usize>,
+                            csp: &Csp<usize, VecDomain<usize>>| {
+            phase_saving_value_order(var, domain, assignment, csp, &mut cache.borrow_mut())
+        };
+
+        let plain = BacktrackingSolver::solve_full(&queens_12, by_name, domain_order, None, None);
+        let first = BacktrackingSolver::solve_full(&queens_12, by_name, phase_saving, None, None);
+        let solution = first.solution().unwrap().clone();
+        assert!(queens_12.is_solution(&solution));
+        // before any solution is found, saved phases are only values that just
+        // failed, so the first search costs about as much as plain domain order
+        assert!(first.stats().backtracks * 10 <= plain.stats().backtracks * 11);
+
+        // the cache now holds the phases the first search left behind, and
+        // nothing else, so re-solving walks back to that solution
+        let second = BacktrackingSolver::solve_full(&queens_12, by_name, phase_saving, None, None);
+        let again = second.solution().unwrap();
+        assert!(again.changed_variables(&solution).is_empty());
+        assert!(second.stats().backtracks < plain.stats().backtracks);
+        assert_eq!(second.stats().backtracks, 0);
+        assert!(second.stats().nodes_explored * 100 < first.stats().nodes_explored);
+    }
+
+    #[test]
+    fn test_fail_first_with_forced() {
+        use csp_solver::csp::{Assignment, Constraint, Domain, Variable, csp::Csp};
+        use csp_solver::examples::sudoku::cell_var;
+        use csp_solver::solver::heuristics::{fail_first_with_forced, minimum_remaining_values};
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        fn by_name<D: Domain<usize>>(
+            assignment: &Assignment<usize>,
+            csp: &Csp<usize, D>,
+        ) -> Option<Variable<usize>> {
+            csp.get_variables()
+                .into_iter()
+                .filter(|var| !assignment.is_assigned(var))
+                .min_by(|a, b| a.name.cmp(&b.name))
+        }
+
+        // with the rest of row 0 filled in, cell (0, 3) has one value left
+        let givens = [(0, 0, 1), (0, 1, 2), (0, 2, 3)];
+        let sudoku = examples::sudoku::create_sudoku_csp(&givens);
+        let mut assignment = Assignment::new();
+        for (col, value) in [(0, 1), (1, 2), (2, 3)] {
+            assignment.assign(cell_var(0, col), value);
+        }
+        let forced = fail_first_with_forced(&assignment, &sudoku).unwrap();
+        assert_eq!(forced, cell_var(0, 3));
+
+        // MRV picks the same cell, but only after scoring every other one
+        let checks = Arc::new(AtomicU64::new(0));
+        let mut counted = Csp::new();
+        for var in sudoku.get_variables() {
+            let domain = sudoku.get_domain(&var).unwrap().clone();
+            counted.add_variable(var, domain).unwrap();
+        }
+        for constraint in sudoku.get_constraints() {
+            let (inner, checks) = (constraint.clone(), checks.clone());
+            let wrapped = Constraint::new_partial(
+                constraint.name(),
+                constraint.variables().to_vec(),
+                move |assignment| {
+                    checks.fetch_add(1, Ordering::Relaxed);
+                    inner.is_satisfied(assignment)
+                },
+            );
+            counted.add_constraint(wrapped).unwrap();
+        }
+        assert_eq!(
+            minimum_remaining_values(&assignment, &counted),
+            Some(cell_var(0, 3))
+        );
+        let mrv_checks = checks.swap(0, Ordering::Relaxed);
+        fail_first_with_forced(&assignment, &counted);
+        let forced_checks = checks.load(Ordering::Relaxed);
+        assert!(forced_checks * 2 < mrv_checks);
+
+        // a dead end is reported before a later forced cell, as MRV would
+        let mut dead_end = Assignment::new();
+        for (row, col, value) in [(0, 0, 1), (0, 2, 3), (1, 0, 2), (1, 1, 4), (2, 3, 2)] {
+            dead_end.assign(cell_var(row, col), value);
+        }
+        assert_eq!(
+            fail_first_with_forced(&dead_end, &sudoku),
+            Some(cell_var(0, 1))
+        );
+
+        // forced cells are filled in before they can be contradicted further down
+        let sparse =
+            examples::sudoku::create_sudoku_csp(&[(0, 1, 3), (1, 3, 2), (2, 0, 4), (3, 2, 1)]);
+        let plain = BacktrackingSolver::solve_full(&sparse, by_name, domain_order, None, None);
+        let result = BacktrackingSolver::solve_full(
+            &sparse,
+            fail_first_with_forced,
+            domain_order,
+            None,
+            None,
+        );
+        assert!(sparse.is_solution(result.solution().unwrap()));
+        assert!(result.stats().backtracks * 5 < plain.stats().backtracks);
+        let mrv = BacktrackingSolver::mrv_search(&sparse).unwrap();
+        assert!(sparse.is_solution(&mrv));
+    }
+
+    #[test]
+    fn test_queens_with_obstacles() {
+        use csp_solver::csp::Variable;
+        use csp_solver::examples::queens::{create_queens_csp, create_queens_with_obstacles_csp};
+
+        let obstacles = [
+            (0, 0),
+            (1, 1),
+            (2, 2),
+            (3, 3),
+            (4, 4),
+            (5, 5),
+            (6, 6),
+            (7, 7),
+            (0, 7),
+            (7, 0),
+        ];
+        let blocked = create_queens_with_obstacles_csp(8, &obstacles);
+        assert_eq!(blocked.get_domain_size(&Variable::new("Q0")), Some(6));
+
+        // exactly the unblocked 8-queens solutions remain
+        let solutions = BacktrackingSolver::find_all_backtracking(&blocked);
+        let expected = BacktrackingSolver::find_all_backtracking(&create_queens_csp(8))
+            .into_iter()
+            .filter(|solution| {
+                obstacles.iter().all(|&(row, col)| {
+                    solution.get(&Variable::new(&format!("Q{}", col))) != Some(&row)
+                })
+            })
+            .count();
+        assert!(expected > 0);
+        assert_eq!(solutions.len(), expected);
+        assert!(
+            solutions
+                .iter()
+                .all(|solution| blocked.is_solution(solution))
+        );
+
+        // both 4-queens solutions put the first queen in row 1 or 2
+        let no_solutions = create_queens_with_obstacles_csp(4, &[(1, 0), (2, 0)]);
+        assert!(BacktrackingSolver::find_all_backtracking(&no_solutions).is_empty());
+    }
+
+    #[test]
+    fn test_domain_adapter() {
+        use csp_solver::csp::{
+            Constraint, Domain, DomainAdapter, HashSetDomain, Variable, csp::Csp,
+        };
+
+        const COLORS: [&str; 3] = ["red", "green", "blue"];
+        let color_domain = || {
+            let names = HashSetDomain::new(COLORS.iter().map(|c| c.to_string()));
+            DomainAdapter::new(
+                names,
+                |name: &String| COLORS.iter().position(|c| c == name).unwrap(),
+                |&index: &usize| COLORS[index].to_string(),
+            )
+        };
+
+        let domain = color_domain();
+        assert_eq!(domain.size(), 3);
+        assert!(domain.contains(&2));
+        assert_eq!(domain.to_sorted_vec(), vec![0, 1, 2]);
+        let without_green = domain.remove(&1);
+        assert!(!without_green.contains(&1));
+        assert!(!without_green.inner().contains(&"green".to_string()));
+
+        // color indices strictly increase from A to C
+        let mut csp = Csp::new();
+        let vars: Vec<Variable<usize>> = ["A", "B", "C"].iter().map(|n| Variable::new(n)).collect();
+        for var in &vars {
+            csp.add_variable(var.clone(), color_domain()).unwrap();
+        }
+        let (a, b, c) = (vars[0].clone(), vars[1].clone(), vars[2].clone());
+        let increasing = Constraint::new("Increasing", vars.clone(), move |assignment| {
+            match (assignment.get(&a), assignment.get(&b), assignment.get(&c)) {
+                (Some(a), Some(b), Some(c)) => a < b && b < c,
+                _ => true,
+            }
+        });
+        csp.add_constraint(increasing).unwrap();
+
+        let solution = BacktrackingSolver::backtrack_search(&csp).unwrap();
+        let names: Vec<&str> = vars
+            .iter()
+            .map(|var| COLORS[*solution.get(var).unwrap()])
+            .collect();
+        assert_eq!(names, vec!["red", "green", "blue"]);
+    }
+
+    #[test]
+    fn test_boolean_cardinality() {
+        use csp_solver::csp::{Variable, VecDomain, common, csp::Csp};
+
+        let vars: Vec<Variable<bool>> = (0..5).map(|i| Variable::new(&format!("B{}", i))).collect();
+        let booleans = |constraint| {
+            let mut csp = Csp::new();
+            for var in &vars {
+                csp.add_variable(var.clone(), VecDomain::new([false, true]))
+                    .unwrap();
+            }
+            csp.add_constraint(constraint).unwrap();
+            csp
+        };
+        let count =
+            |constraint| BacktrackingSolver::find_all_backtracking(&booleans(constraint)).len();
+
+        // C(5, 3) ways to pick the true variables
+        assert_eq!(count(common::exactly_n_true("Three", vars.clone(), 3)), 10);
+        assert_eq!(
+            count(common::at_most_n_true("AtMostOne", vars.clone(), 1)),
+            6
+        );
+        assert_eq!(
+            count(common::at_least_n_true("AtLeastFour", vars.clone(), 4)),
+            6
+        );
+        assert_eq!(count(common::majority_true("Majority", vars.clone())), 16);
+    }
+
+    #[test]
+    fn test_assignment_ordered_iter() {
+        use csp_solver::csp::{Assignment, Variable};
+
+        let names = ["WA", "NT", "SA", "Q", "NSW", "V", "T", "Extra1", "Extra2"];
+        let mut forward = Assignment::new();
+        for (i, name) in names.iter().enumerate() {
+            forward.assign(Variable::new(name), i);
+        }
+        let mut backward = Assignment::new();
+        for (i, name) in names.iter().enumerate().rev() {
+            backward.assign(Variable::new(name), i);
+        }
+
+        let pairs = |a: &Assignment<usize>| -> Vec<(String, usize)> {
+            a.ordered_iter()
+                .map(|(var, value)| (var.name.clone(), *value))
+                .collect()
+        };
+        assert_eq!(pairs(&forward), pairs(&backward));
+        let mut sorted: Vec<&str> = names.to_vec();
+        sorted.sort();
+        let ordered: Vec<String> = pairs(&forward).into_iter().map(|(name, _)| name).collect();
+        assert_eq!(ordered, sorted);
+
+        assert_eq!(forward.to_string(), backward.to_string());
+        assert!(
+            forward
+                .to_string()
+                .starts_with("{Extra1: 7, Extra2: 8, NSW: 4")
+        );
+        let map = backward.into_btree_map();
+        assert_eq!(map.keys().next(), Some(&Variable::new("Extra1")));
+        assert_eq!(map.len(), names.len());
+    }
+
+    #[test]
+    fn test_find_all_solutions_parallel() {
+        use csp_solver::solver::heuristics::minimum_remaining_values;
+
+        let queens = examples::queens::create_queens_csp(8);
+        let solutions = BacktrackingSolver::find_all_solutions_parallel(
+            &queens,
+            minimum_remaining_values,
+            domain_order,
+        );
+        assert_eq!(solutions.len(), 92);
+        assert!(
+            solutions
+                .iter()
+                .all(|solution| queens.is_solution(solution))
+        );
+        let distinct: std::collections::HashSet<String> = solutions
+            .iter()
+            .map(|solution| solution.to_string())
+            .collect();
+        assert_eq!(distinct.len(), 92);
+
+        let on_two_threads = BacktrackingSolver::find_all_solutions_parallel_with_threads(
+            &queens,
+            minimum_remaining_values,
+            domain_order,
+            2,
+        )
+        .unwrap();
+        assert_eq!(on_two_threads.len(), 92);
+    }
+
+    #[test]
+    fn test_killer_sudoku() {
+        use csp_solver::examples::sudoku::cell_var;
+        use std::collections::HashSet;
+
+        let (_, cages) = examples::sudoku::sample_killer_sudoku();
+        let mut killer = examples::sudoku::create_sample_killer_sudoku();
+        // AC-3 only propagates binary constraints, so expose the pairwise differences
+        killer.decompose_global_constraints();
+
+        let solution = ArcConsistencySolver::solve(&killer).expect("puzzle has a solution");
+        assert!(killer.is_solution(&solution));
+
+        let cell = |row: usize, col: usize| *solution.get(&cell_var(row, col)).unwrap();
+        let all_digits: HashSet<usize> = (1..=9).collect();
+        for i in 0..9 {
+            assert_eq!(
+                (0..9).map(|col| cell(i, col)).collect::<HashSet<_>>(),
+                all_digits
+            );
+            assert_eq!(
+                (0..9).map(|row| cell(row, i)).collect::<HashSet<_>>(),
+                all_digits
+            );
+            let (box_row, box_col) = (i / 3 * 3, i % 3 * 3);
+            let in_box = (0..9).map(|k| cell(box_row + k / 3, box_col + k % 3));
+            assert_eq!(in_box.collect::<HashSet<_>>(), all_digits);
+        }
+        for cage in &cages {
+            let values: Vec<usize> = cage
+                .cells
+                .iter()
+                .map(|&(row, col)| cell(row, col))
+                .collect();
+            assert_eq!(values.iter().sum::<usize>(), cage.sum);
+            assert_eq!(values.iter().collect::<HashSet<_>>().len(), values.len());
+        }
+    }
+
+    #[test]
+    fn test_find_solution_with_progress() {
+        use csp_solver::csp::SolveProgress;
+        use csp_solver::solver::utils::first_unassigned;
+        use std::cell::RefCell;
+
+        let queens_12 = examples::queens::create_queens_csp(12);
+        let reports: RefCell<Vec<SolveProgress<usize>>> = RefCell::new(Vec::new());
+        let solution = BacktrackingSolver::find_solution_with_progress(
+            &queens_12,
+            first_unassigned,
+            domain_order,
+            |progress| reports.borrow_mut().push(progress),
+            5,
+        )
+        .unwrap();
+        assert!(queens_12.is_solution(&solution));
+
+        let reports = reports.into_inner();
+        assert!(!reports.is_empty());
+        assert!(
+            reports
+                .windows(2)
+                .all(|pair| pair[0].nodes_explored <= pair[1].nodes_explored)
+        );
+        assert!(reports.iter().all(|report| report.nodes_explored % 5 == 0));
+        assert!(reports.iter().all(|report| report.total_variables == 12));
+        assert!(
+            reports
+                .iter()
+                .all(|report| queens_12.is_consistent(&report.best_partial))
+        );
+    }
+
+    #[test]
+    fn test_mac_nogood_solver() {
+        use csp_solver::solver::{FcCbjSolver, MacNogoodSolver};
+
+        let queens_15 = examples::queens::create_queens_csp(15);
+        let (solution, stats) = MacNogoodSolver::solve_with_stats(&queens_15);
+        assert!(queens_15.is_solution(&solution.unwrap()));
+
+        let (_, fc) = ForwardCheckingSolver::solve_with_stats(&queens_15);
+        let (_, cbj) = FcCbjSolver::solve_with_stats(&queens_15);
+        // called once per search node plus once at the root
+        let mut mac_nodes = 0;
+        ArcConsistencySolver::solve_with_callback(&queens_15, |_, _, _| mac_nodes += 1).unwrap();
+        assert!(stats.nodes_explored < fc.nodes_explored);
+        assert!(stats.nodes_explored < cbj.nodes_explored);
+        assert!(stats.nodes_explored < mac_nodes);
+
+        // the zebra puzzle fails often enough for recorded no-goods to be reused;
+        // its HashSetDomain value order differs per build and occasionally finds
+        // the solution without reuse, so count prunes over several builds
+        let mut nogood_prunes = 0;
+        for _ in 0..5 {
+            let zebra = examples::zebra::create_zebra_csp();
+            let (solution, stats) = MacNogoodSolver::solve_with_stats(&zebra);
+            assert!(zebra.is_solution(&solution.unwrap()));
+            nogood_prunes += stats.nogood_prunes;
+        }
+        assert!(nogood_prunes > 0);
+
+        let k4 = examples::coloring::create_map_coloring_csp(
+            &["A", "B", "C", "D"],
+            &[
+                ("A", "B"),
+                ("A", "C"),
+                ("A", "D"),
+                ("B", "C"),
+                ("B", "D"),
+                ("C", "D"),
+            ],
+            3,
+        );
+        assert!(MacNogoodSolver::solve(&k4).is_none());
+    }
+
+    #[test]
+    fn test_trivially_infeasible() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Variable, VecDomain, common};
+
+        let australia = examples::australia::create_australia_csp();
+        assert!(!australia.is_trivially_infeasible());
+
+        let mut conflicting = Csp::<usize, VecDomain<usize>>::new();
+        let x = Variable::new("X");
+        let y = Variable::new("Y");
+        conflicting
+            .add_variable(x.clone(), VecDomain::new(vec![1]))
+            .unwrap();
+        conflicting
+            .add_variable(y.clone(), VecDomain::new(vec![1]))
+            .unwrap();
+        conflicting
+            .add_constraint(common::diff("X-Y", x, y))
+            .unwrap();
+        assert!(conflicting.is_trivially_infeasible());
+
+        let mut empty = Csp::<usize, VecDomain<usize>>::new();
+        empty
+            .add_variable(Variable::new("Z"), VecDomain::new(vec![]))
+            .unwrap();
+        assert!(empty.is_trivially_infeasible());
+    }
+
+    #[test]
+    fn test_portfolio_falls_back() {
+        use csp_solver::solver::utils::first_unassigned;
+        use csp_solver::solver::{CancellationToken, PortfolioSolver};
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::thread;
+        use std::time::{Duration, Instant};
+
+        let australia = examples::australia::create_australia_csp();
+        let portfolio = PortfolioSolver::new()
+            .add_fn(|_| None)
+            .add_fn(BacktrackingSolver::mrv_search);
+        assert_eq!(portfolio.len(), 2);
+
+        let solution = portfolio.solve(&australia).unwrap();
+        assert!(australia.is_solution(&solution));
+
+        // forward checking wins the race on 30-queens while plain backtracking
+        // is still searching, and the budget stops backtracking's thread
+        let queens_30 = examples::queens::create_queens_csp(30);
+        let portfolio = PortfolioSolver::new()
+            .add_fn_with_deadline(|csp, deadline| {
+                BacktrackingSolver::solve_full(csp, first_unassigned, domain_order, deadline, None)
+                    .solution()
+                    .cloned()
+            })
+            .add_solver(ForwardCheckingSolver)
+            .with_time_budget(Duration::from_secs(10));
+        let solution = portfolio.solve(&queens_30).unwrap();
+        assert!(queens_30.is_solution(&solution));
+
+        // the losers are cancelled once a solver wins
+        let stopped = Arc::new(AtomicBool::new(false));
+        let observed = Arc::clone(&stopped);
+        let portfolio = PortfolioSolver::new()
+            .add_fn_cancellable(move |_, token: &CancellationToken| {
+                while !token.is_cancelled() {
+                    thread::sleep(Duration::from_millis(1));
+                }
+                observed.store(true, Ordering::SeqCst);
+                None
+            })
+            .add_fn(BacktrackingSolver::mrv_search);
+        assert!(australia.is_solution(&portfolio.solve(&australia).unwrap()));
+        let start = Instant::now();
+        while !stopped.load(Ordering::SeqCst) && start.elapsed() < Duration::from_secs(10) {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(stopped.load(Ordering::SeqCst));
+
+        // the budget holds even for a solver that never checks it
+        let portfolio = PortfolioSolver::new()
+            .add_fn(|_| {
+                thread::sleep(Duration::from_secs(5));
+                None
+            })
+            .with_time_budget(Duration::from_millis(50));
+        let start = Instant::now();
+        assert!(portfolio.solve(&australia).is_none());
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_reified_constraint() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Variable, VecDomain, common};
+
+        let build = |indicator_values: Vec<usize>| {
+            let mut csp = Csp::<usize, VecDomain<usize>>::new();
+            let b = Variable::new("B");
+            let x = Variable::new("X");
+            let y = Variable::new("Y");
+            csp.add_variable(b.clone(), VecDomain::new(indicator_values))
+                .unwrap();
+            csp.add_variable(x.clone(), VecDomain::new(vec![1, 2]))
+                .unwrap();
+            csp.add_variable(y.clone(), VecDomain::new(vec![1, 2]))
+                .unwrap();
+            for constraint in common::reify("B<=>X!=Y", b, 1, common::diff("X-Y", x, y)) {
+                csp.add_constraint(constraint).unwrap();
+            }
+            csp
+        };
+
+        let x = Variable::new("X");
+        let y = Variable::new("Y");
+
+        // fixing the indicator on forces the constraint
+        let forced = build(vec![1]);
+        let solutions = BacktrackingSolver::find_all_backtracking(&forced);
+        assert_eq!(solutions.len(), 2);
+        assert!(solutions.iter().all(|s| s.get(&x) != s.get(&y)));
+
+        // fixing the indicator off forces its negation
+        let negated = build(vec![0]);
+        let solutions = BacktrackingSolver::find_all_backtracking(&negated);
+        assert_eq!(solutions.len(), 2);
+        assert!(solutions.iter().all(|s| s.get(&x) == s.get(&y)));
+    }
+
+    #[test]
+    fn test_split_preserves_solutions() {
+        use csp_solver::csp::Variable;
+
+        let australia = examples::australia::create_australia_csp();
+        let all = BacktrackingSolver::find_all_backtracking(&australia);
+        let sa = Variable::new("SA");
+
+        let parts = australia.split(&sa);
+        assert_eq!(parts.len(), 3);
+        let split_solutions: Vec<_> = parts
+            .iter()
+            .flat_map(BacktrackingSolver::find_all_backtracking)
+            .collect();
+        assert_eq!(split_solutions.len(), all.len());
+        for solution in &split_solutions {
+            assert!(australia.is_solution(solution));
+        }
+
+        let parts = australia.split_n_way(&sa, 2);
+        assert_eq!(parts.len(), 2);
+        let count: usize = parts
+            .iter()
+            .map(|part| BacktrackingSolver::find_all_backtracking(part).len())
+            .sum();
+        assert_eq!(count, all.len());
+    }
+
+    #[test]
+    fn test_domain_complement_in() {
+        use csp_solver::csp::{
+            BTreeSetDomain, Domain, HashSetDomain, IntervalDomain, LazyDomain, SortedVecDomain,
+            VecDomain,
+        };
+
+        fn check<D: Domain<i32> + FromIterator<i32>>(domain: D, universe: D) {
+            let complement = domain.complement_in(&universe);
+            let mut union = complement.values();
+            union.extend(domain.values());
+            union.sort();
+            assert_eq!(union, universe.to_sorted_vec());
+
+            let sym_diff = domain.symmetric_difference_with(&universe);
+            assert_eq!(sym_diff.to_sorted_vec(), complement.to_sorted_vec());
+            assert_eq!(complement.to_debug_string(), "{1, 3, 5}");
+        }
+
+        check(HashSetDomain::new(vec![2, 4]), HashSetDomain::new(1..=5));
+        check(BTreeSetDomain::new(vec![2, 4]), BTreeSetDomain::new(1..=5));
+        check(VecDomain::new(vec![2, 4]), VecDomain::new(1..=5));
+        check(
+            SortedVecDomain::new(vec![2, 4]),
+            SortedVecDomain::new(1..=5),
+        );
+        // the provided implementations, collected through FromIterator
+        check(
+            IntervalDomain::new(2, 4).unwrap().remove(&3),
+            IntervalDomain::new(1, 5).unwrap(),
+        );
+        check(
+            LazyDomain::new(|| vec![2, 4]),
+            LazyDomain::new(|| (1..=5).collect()),
+        );
+    }
+
+    #[test]
+    fn test_domain_map() {
+        use csp_solver::csp::{Domain, HashSetDomain, VecDomain};
+
+        let palette = ["red", "green", "blue"];
+        let colors = HashSetDomain::new(["blue", "red"].map(String::from));
+        let indices: VecDomain<usize> =
+            colors.map(|color| palette.iter().position(|p| *p == color).unwrap());
+        assert_eq!(indices.to_sorted_vec(), vec![0, 2]);
+
+        // unknown names are dropped, and names mapping to the same index kept once
+        let names = HashSetDomain::new(["red", "RED", "purple"].map(String::from));
+        let known = names.filter_map(|name| {
+            let name = name.to_lowercase();
+            palette.iter().position(|p| *p == name)
+        });
+        assert_eq!(known.values(), vec![0]);
+    }
+
+    #[test]
+    fn test_solve_full_outcomes() {
+        use csp_solver::solver::SolveResult;
+        use csp_solver::solver::utils::first_unassigned;
+        use std::time::Duration;
+
+        let queens_6 = examples::queens::create_queens_csp(6);
+        let result =
+            BacktrackingSolver::solve_full(&queens_6, first_unassigned, domain_order, None, None);
+        match result {
+            SolveResult::Solved(solution, stats) => {
+                assert!(queens_6.is_solution(&solution));
+                assert!(stats.nodes_explored > 0);
+            }
+            _ => panic!("6-queens should be solvable"),
+        }
+
+        let queens_3 = examples::queens::create_queens_csp(3);
+        let result =
+            BacktrackingSolver::solve_full(&queens_3, first_unassigned, domain_order, None, None);
+        assert!(matches!(result, SolveResult::NoSolution(_)));
+
+        let result = BacktrackingSolver::solve_full(
+            &queens_6,
+            first_unassigned,
+            domain_order,
+            None,
+            Some(5),
+        );
+        assert!(matches!(result, SolveResult::Timeout(ref stats) if stats.nodes_explored == 5));
+
+        let result = BacktrackingSolver::solve_full(
+            &queens_6,
+            first_unassigned,
+            domain_order,
+            Some(Instant::now() - Duration::from_millis(1)),
+            None,
+        );
+        assert!(matches!(result, SolveResult::Timeout(_)));
+    }
+
+    #[test]
+    fn test_zebra_puzzle() {
+        use examples::zebra::{Attribute, Drink, Nationality, Pet, find_house, house_var};
+
+        let zebra = examples::zebra::create_zebra_csp();
+        let solution = ForwardCheckingSolver::solve(&zebra).unwrap();
+        assert!(zebra.is_solution(&solution));
+
+        let owner = find_house(&solution, Attribute::Pet(Pet::Zebra)).unwrap();
+        assert_eq!(
+            solution.get(&house_var("Nationality", owner)),
+            Some(&Attribute::Nationality(Nationality::Japanese))
+        );
+        let drinker = find_house(&solution, Attribute::Drink(Drink::Water)).unwrap();
+        assert_eq!(
+            solution.get(&house_var("Nationality", drinker)),
+            Some(&Attribute::Nationality(Nationality::Norwegian))
+        );
+    }
+
+    #[test]
+    fn test_queens_solutions_up_to_symmetry() {
+        use csp_solver::solver::symmetry::count_solutions_up_to_symmetry;
+
+        let queens_8 = examples::queens::create_queens_csp(8);
+        let symmetries = examples::queens::queens_symmetries(8);
+        assert_eq!(count_solutions_up_to_symmetry(&queens_8, &symmetries), 12);
+
+        let queens_4 = examples::queens::create_queens_csp(4);
+        let symmetries = examples::queens::queens_symmetries(4);
+        assert_eq!(count_solutions_up_to_symmetry(&queens_4, &symmetries), 1);
+    }
+
+    #[test]
+    fn test_increasing_sequence_constraints() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Variable, VecDomain, common};
+
+        let build = |ordered: bool| {
+            let mut csp = Csp::<usize, VecDomain<usize>>::new();
+            let vars: Vec<Variable<usize>> =
+                (0..4).map(|i| Variable::new(&format!("V{}", i))).collect();
+            for var in &vars {
+                csp.add_variable(var.clone(), VecDomain::new(1..=4))
+                    .unwrap();
+            }
+            let constraint = if ordered {
+                common::strictly_increasing("Increasing", vars)
+            } else {
+                common::nondecreasing("NonDecreasing", vars)
+            };
+            csp.add_constraint(constraint).unwrap();
+            csp
+        };
+
+        // 4^4 = 256 unconstrained; C(4+4-1, 4) = 35 multisets; C(4, 4) = 1 strict
+        let nondecreasing = BacktrackingSolver::find_all_backtracking(&build(false));
+        assert_eq!(nondecreasing.len(), 35);
+        let increasing = BacktrackingSolver::find_all_backtracking(&build(true));
+        assert_eq!(increasing.len(), 1);
+    }
+
+    #[test]
+    fn test_domain_shortcuts() {
+        use csp_solver::csp::{Domain, Variable};
+
+        let australia = examples::australia::create_australia_csp();
+        let wa = Variable::new("WA");
+        let domain = australia.get_domain(&wa).unwrap();
+        assert_eq!(australia.get_domain_values(&wa), Some(domain.values()));
+        assert_eq!(australia.get_domain_size(&wa), Some(domain.size()));
+        assert!(australia.domain_contains(&wa, &"red".to_string()));
+        assert!(!australia.domain_contains(&wa, &"purple".to_string()));
+
+        let missing = Variable::new("Tasmania");
+        assert_eq!(australia.get_domain_values(&missing), None);
+        assert_eq!(australia.get_domain_size(&missing), None);
+        assert!(!australia.domain_contains(&missing, &"red".to_string()));
+    }
+
+    #[test]
+    fn test_limited_discrepancy_search() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Assignment, Variable, VecDomain};
+        use csp_solver::solver::LimitedDiscrepancySolver;
+        use csp_solver::solver::heuristics::{fail_first_with_forced, minimum_remaining_values};
+
+        let queens_8 = examples::queens::create_queens_csp(8);
+        let solution =
+            LimitedDiscrepancySolver::solve(&queens_8, 8, minimum_remaining_values, domain_order)
+                .unwrap();
+        assert!(queens_8.is_solution(&solution));
+
+        // with enough discrepancies the search is complete
+        let queens_3 = examples::queens::create_queens_csp(3);
+        assert!(
+            LimitedDiscrepancySolver::solve(&queens_3, 3, minimum_remaining_values, domain_order)
+                .is_none()
+        );
+
+        // A value heuristic that knows a 12-queens solution but misplaces Q0:
+        // backtracking exhausts the subtree under the bad first choice, LDS
+        // repairs it with a single discrepancy
+        let queens_12 = examples::queens::create_queens_csp(12);
+        let known = [0, 2, 4, 7, 9, 11, 5, 10, 1, 6, 8, 3];
+        let mut expected = Assignment::new();
+        for (col, row) in known.iter().enumerate() {
+            expected.assign(Variable::new(&format!("Q{}", col)), *row);
+        }
+        assert!(queens_12.is_solution(&expected));
+        let nearly_accurate = |var: &Variable<usize>,
+                               _: &VecDomain<usize>,
+                               _: &Assignment<usize>,
+                               _: &Csp<usize, VecDomain<usize>>| {
+            let col: usize = var.name[1..].parse().unwrap();
+            let first = if col == 0 { 2 } else { known[col] };
+            let mut values = vec![first];
+            values.extend((0..12).filter(|&row| row != first));
+            values
+        };
+
+        // fail_first_with_forced is MRV with a name tie-break, so both runs are deterministic
+        let backtracking = BacktrackingSolver::solve_full(
+            &queens_12,
+            fail_first_with_forced,
+            nearly_accurate,
+            None,
+            None,
+        );
+        let (solution, lds) = LimitedDiscrepancySolver::solve_with_stats(
+            &queens_12,
+            12,
+            fail_first_with_forced,
+            nearly_accurate,
+        );
+        assert!(queens_12.is_solution(backtracking.solution().unwrap()));
+        assert!(queens_12.is_solution(&solution.unwrap()));
+        assert!(lds.nodes_explored * 10 < backtracking.stats().nodes_explored);
+    }
+
+    #[test]
+    fn test_assignment_get_or_default() {
+        use csp_solver::csp::{Assignment, Variable};
+
+        let x = Variable::new("X");
+        let y = Variable::new("Y");
+        let mut assignment = Assignment::new();
+        assignment.assign(x.clone(), 3);
+
+        assert_eq!(assignment.get_or_default(&x, 0), 3);
+        assert_eq!(assignment.get_or_default(&y, 0), 0);
+        assert_eq!(assignment.get_or_else(&x, || 7), 3);
+        assert_eq!(assignment.get_or_else(&y, || 7), 7);
+    }
+
+    #[test]
+    fn test_wdeg_search() {
+        use csp_solver::csp::{Variable, VecDomain, common};
+        use csp_solver::examples::random_csp::create_random_binary_csp;
+        use csp_solver::solver::SolveResult;
+        use csp_solver::solver::heuristics::{ConstraintWeights, minimum_remaining_values};
+        use csp_solver::solver::utils::domain_order;
+
+        let queens_8 = examples::queens::create_queens_csp(8);
+        let solution = BacktrackingSolver::wdeg_search(&queens_8).unwrap();
+        assert!(queens_8.is_solution(&solution));
+
+        let queens_3 = examples::queens::create_queens_csp(3);
+        assert!(BacktrackingSolver::wdeg_search(&queens_3).is_none());
+
+        let mut weights = ConstraintWeights::<usize>::new();
+        assert_eq!(weights.weight("NotSameRow-0-1"), 1.0);
+        weights.increment("NotSameRow-0-1");
+        assert_eq!(weights.weight("NotSameRow-0-1"), 2.0);
+
+        // Seeded random CSPs with a small unsatisfiable core: four variables
+        // that must all differ over three values. MRV starts on the random
+        // variables, whose domains are smaller, and re-proves the core
+        // infeasible under each of their assignments; dom/wdeg learns that the
+        // core's constraints keep failing and branches there
+        let (mut wdeg_nodes, mut mrv_nodes) = (0, 0);
+        for seed in 0..8 {
+            let mut csp = create_random_binary_csp(10, 2, 0.3, 0.2, seed);
+            let core: Vec<Variable<usize>> =
+                (0..4).map(|i| Variable::new(&format!("K{}", i))).collect();
+            for var in &core {
+                csp.add_variable(var.clone(), VecDomain::new(0..3)).unwrap();
+            }
+            for (i, a) in core.iter().enumerate() {
+                for b in &core[i + 1..] {
+                    let name = format!("{}-{}", a, b);
+                    csp.add_constraint(common::diff(&name, a.clone(), b.clone()))
+                        .unwrap();
+                }
+            }
+
+            let (solution, stats) = BacktrackingSolver::wdeg_search_with_stats(&csp);
+            assert!(solution.is_none());
+            let mrv = BacktrackingSolver::solve_full(
+                &csp,
+                minimum_remaining_values,
+                domain_order,
+                None,
+                None,
+            );
+            assert!(matches!(mrv, SolveResult::NoSolution(_)));
+            wdeg_nodes += stats.nodes_explored;
+            mrv_nodes += mrv.stats().nodes_explored;
+        }
+        assert!(
+            wdeg_nodes * 4 < mrv_nodes,
+            "dom/wdeg explored {} nodes, MRV {}",
+            wdeg_nodes,
+            mrv_nodes
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_is_sorted() {
+        let australia = examples::australia::create_australia_csp();
+        let pretty = australia.format_pretty();
+        assert_eq!(pretty, australia.format_pretty());
+
+        let variables = ["NSW", "NT", "Q", "SA", "T", "V", "WA"];
+        let positions: Vec<usize> = variables
+            .iter()
+            .map(|name| {
+                pretty
+                    .find(&format!("  {}: {{blue, green, red}}", name))
+                    .unwrap()
+            })
+            .collect();
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let constraints = [
+            "NSW-V", "NT-Q", "NT-SA", "Q-NSW", "SA-NSW", "SA-Q", "SA-V", "WA-NT", "WA-SA",
+        ];
+        let positions: Vec<usize> = constraints
+            .iter()
+            .map(|name| pretty.find(&format!("    {} on", name)).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_forbidden_and_allowed_pairs() {
+        use csp_solver::csp::{Assignment, Variable, common};
+        use std::collections::HashSet;
+
+        let x = Variable::new("X");
+        let y = Variable::new("Y");
+        let pairs: HashSet<(i32, i32)> = [(1, 2), (2, 3)].into_iter().collect();
+        let forbidden = common::forbidden_pairs("F", x.clone(), y.clone(), pairs.clone());
+        let allowed = common::allowed_pairs("A", x.clone(), y.clone(), pairs);
+
+        let mut assignment = Assignment::new();
+        assignment.assign(x.clone(), 1);
+        assignment.assign(y.clone(), 2);
+        assert!(!forbidden.is_satisfied(&assignment));
+        assert!(allowed.is_satisfied(&assignment));
+
+        assignment.assign(y.clone(), 1);
+        assert!(forbidden.is_satisfied(&assignment));
+        assert!(!allowed.is_satisfied(&assignment));
+    }
+
+    #[test]
+    fn test_find_unconstrained_variable_pairs() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{HashSetDomain, Variable};
+
+        let australia = examples::australia::create_australia_csp();
+        assert!(!australia.is_fully_constrained());
+
+        // rebuild Australia without the SA-NSW border
+        let mut incomplete = Csp::<String, HashSetDomain<String>>::new();
+        for var in australia.get_variables() {
+            let domain = australia.get_domain(&var).unwrap().clone();
+            incomplete.add_variable(var, domain).unwrap();
+        }
+        for constraint in australia.get_constraints() {
+            if constraint.name() != "SA-NSW" {
+                incomplete.add_constraint(constraint.clone()).unwrap();
+            }
+        }
+
+        let full_pairs = australia.find_unconstrained_variable_pairs();
+        let missing: Vec<_> = incomplete
+            .find_unconstrained_variable_pairs()
+            .into_iter()
+            .filter(|pair| !full_pairs.contains(pair))
+            .collect();
+        assert_eq!(missing, vec![(Variable::new("NSW"), Variable::new("SA"))]);
+    }
+
+    #[test]
+    fn test_find_inconsistency() {
+        use csp_solver::csp::{Variable, common};
+
+        let australia = examples::australia::create_australia_csp();
+        assert!(ArcConsistencySolver::find_inconsistency(&australia).is_none());
+
+        // two colors with SA fixed to red forces WA and NT to both be green
+        let mut two_colors = examples::australia::create_australia_csp();
+        for var in two_colors.get_variables() {
+            let name = format!("{}-no-blue", var);
+            two_colors
+                .add_constraint(common::not_in(&name, var, vec!["blue".to_string()]))
+                .unwrap();
+        }
+        two_colors
+            .add_constraint(common::in_set(
+                "SA-red",
+                Variable::new("SA"),
+                vec!["red".to_string()],
+            ))
+            .unwrap();
+        two_colors.apply_unary_constraints();
+
+        let (empty_var, other_var, constraint, value) =
+            ArcConsistencySolver::find_inconsistency(&two_colors).unwrap();
+        assert!(constraint.involves(&empty_var) && constraint.involves(&other_var));
+        assert!(value == "red" || value == "green");
+        assert!(ArcConsistencySolver::solve(&two_colors).is_none());
+    }
+
+    #[test]
+    fn test_sequence_constraint_nurse_schedule() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Variable, VecDomain, common};
+        use std::collections::HashSet;
+
+        // 0 = rest, 1 = work; never more than 3 working days in any 4-day window
+        let mut schedule = Csp::<usize, VecDomain<usize>>::new();
+        let days: Vec<Variable<usize>> = (0..7)
+            .map(|d| Variable::new(&format!("Day{}", d)))
+            .collect();
+        for day in &days {
+            schedule
+                .add_variable(day.clone(), VecDomain::new(0..2))
+                .unwrap();
+        }
+        let work: HashSet<usize> = [1].into_iter().collect();
+        schedule
+            .add_constraint(common::sequence("MaxWork", days.clone(), 4, work, 0, 3))
+            .unwrap();
+
+        let solutions = BacktrackingSolver::find_all_backtracking(&schedule);
+        // binary strings of length 7 without four consecutive ones
+        assert_eq!(solutions.len(), 108);
+        for solution in &solutions {
+            let worked: Vec<usize> = days.iter().map(|d| *solution.get(d).unwrap()).collect();
+            assert!(worked.windows(4).all(|w| w.iter().sum::<usize>() <= 3));
+        }
+    }
+
+    #[test]
+    fn test_domain_entropy() {
+        use csp_solver::csp::{Assignment, Variable, VecDomain};
+        use std::collections::HashMap;
+
+        let x = Variable::<usize>::new("X");
+        let assignment = Assignment::new();
+        assert_eq!(assignment.domain_entropy(&x, &VecDomain::new(vec![3])), 0.0);
+        assert_eq!(assignment.domain_entropy(&x, &VecDomain::new(0..8)), 3.0);
+
+        let queens_4 = examples::queens::create_queens_csp(4);
+        let domains: HashMap<_, _> = queens_4
+            .get_variables()
+            .into_iter()
+            .map(|var| (var.clone(), queens_4.get_domain(&var).unwrap().clone()))
+            .collect();
+        assert_eq!(queens_4.total_remaining_entropy(&assignment, &domains), 8.0);
+
+        let mut partial = Assignment::new();
+        partial.assign(Variable::new("Q0"), 1);
+        assert_eq!(queens_4.total_remaining_entropy(&partial, &domains), 6.0);
+
+        // the arc consistency search reports it at every node, ending at zero
+        let mut entropies = Vec::new();
+        let solution = ArcConsistencySolver::solve_with_callback(&queens_4, |_, _, entropy| {
+            entropies.push(entropy)
+        })
+        .unwrap();
+        assert!(queens_4.is_solution(&solution));
+        assert_eq!(entropies.first(), Some(&8.0));
+        assert_eq!(entropies.last(), Some(&0.0));
+    }
+
+    #[test]
+    fn test_tree_solve_chain() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Variable, VecDomain, common};
+        use csp_solver::solver::utils::topological_variable_order;
+
+        let mut chain = Csp::<usize, VecDomain<usize>>::new();
+        for i in 0..6 {
+            chain
+                .add_variable(Variable::new(&format!("X{}", i)), VecDomain::new(0..3))
+                .unwrap();
+        }
+        for i in 0..5 {
+            let constraint = common::diff(
+                &format!("X{}-X{}", i, i + 1),
+                Variable::new(&format!("X{}", i)),
+                Variable::new(&format!("X{}", i + 1)),
+            );
+            chain.add_constraint(constraint).unwrap();
+        }
+        // pin the last variable so the forward pass has to respect leaf support
+        chain
+            .add_constraint(common::in_set("X5-fixed", Variable::new("X5"), vec![0]))
+            .unwrap();
+
+        let order = topological_variable_order(&chain).unwrap();
+        assert_eq!(order.len(), 6);
+        let solution = BacktrackingSolver::tree_solve(&chain).unwrap();
+        assert!(chain.is_solution(&solution));
+
+        // the australia map has cycles
+        let australia = examples::australia::create_australia_csp();
+        assert!(topological_variable_order(&australia).is_none());
+        assert!(australia.is_solution(&BacktrackingSolver::tree_solve(&australia).unwrap()));
+    }
+
+    #[test]
+    fn test_america_is_four_colorable() {
+        use examples::coloring::{US_STATES, four_color_america_csp};
+
+        let america = four_color_america_csp();
+        assert_eq!(america.num_variables(), US_STATES.len());
+
+        let solution = ArcConsistencySolver::solve(&america).unwrap();
+        assert!(america.is_solution(&solution));
+
+        // three colors are not enough for the states around Nevada
+        let three_colors = examples::coloring::create_map_coloring_csp(
+            &US_STATES,
+            &examples::coloring::US_BORDERS,
+            3,
+        );
+        assert!(ArcConsistencySolver::solve(&three_colors).is_none());
+    }
+
+    #[test]
+    fn test_find_k_best_solutions() {
+        use csp_solver::csp::{Assignment, Variable};
+        use csp_solver::solver::utils::first_unassigned;
+        use std::collections::HashMap;
+
+        // balance = -(largest color class - smallest color class)
+        let balance = |assignment: &Assignment<String>| {
+            let mut counts: HashMap<&String, usize> = HashMap::new();
+            for (_, color) in assignment.iter() {
+                *counts.entry(color).or_insert(0) += 1;
+            }
+            let max = counts.values().max().copied().unwrap_or(0);
+            let min = if counts.len() < 3 {
+                0
+            } else {
+                counts.values().min().copied().unwrap_or(0)
+            };
+            -((max - min) as f64)
+        };
+
+        let australia = examples::australia::create_australia_csp();
+        let best = BacktrackingSolver::find_k_best_solutions(
+            &australia,
+            3,
+            balance,
+            first_unassigned,
+            domain_order,
+        );
+
+        assert_eq!(best.len(), 3);
+        for (solution, score) in &best {
+            assert!(australia.is_solution(solution));
+            // 7 regions over 3 colors split at best 3/2/2
+            assert_eq!(*score, -1.0);
+        }
+
+        // the objective reads every region, so it must only see complete colorings
+        let regions: Vec<Variable<String>> = australia.get_variables();
+        let objective = {
+            let regions = regions.clone();
+            move |assignment: &Assignment<String>| {
+                regions
+                    .iter()
+                    .filter(|var| assignment.get(var).unwrap() == "red")
+                    .count() as f64
+            }
+        };
+        let upper_bound = |assignment: &Assignment<String>| {
+            let red = assignment
+                .iter()
+                .filter(|(_, color)| *color == "red")
+                .count();
+            (red + regions.len() - assignment.size()) as f64
+        };
+        let most_red = BacktrackingSolver::find_k_best_solutions_bounded(
+            &australia,
+            2,
+            objective,
+            upper_bound,
+            first_unassigned,
+            domain_order,
+        );
+        let scores: Vec<f64> = most_red.iter().map(|(_, score)| *score).collect();
+        // WA, Q, V and T are pairwise non-adjacent, the largest such set
+        assert_eq!(scores, vec![4.0, 4.0]);
+    }
+
+    #[test]
+    fn test_constraint_algebra() {
+        use csp_solver::csp::{Assignment, Constraint, Variable, common};
+
+        let x = Variable::new("X");
+        let y = Variable::new("Y");
+        let z = Variable::new("Z");
+        let both = Constraint::and(
+            "X!=Y&Y!=Z",
+            common::diff("X-Y", x.clone(), y.clone()),
+            common::diff("Y-Z", y.clone(), z.clone()),
+        );
+        let either = Constraint::or(
+            "X!=Y|Y!=Z",
+            common::diff("X-Y", x.clone(), y.clone()),
+            common::diff("Y-Z", y.clone(), z.clone()),
+        );
+        let not_diff = Constraint::negate("!(X!=Y)", common::diff("X-Y", x.clone(), y.clone()));
+        let same = common::same("X=Y", x.clone(), y.clone());
+        assert_eq!(both.variables().len(), 3);
+
+        for a in 0..3 {
+            for b in 0..3 {
+                for c in 0..3 {
+                    let mut assignment = Assignment::new();
+                    assignment.assign(x.clone(), a);
+                    assignment.assign(y.clone(), b);
+                    assignment.assign(z.clone(), c);
+                    assert_eq!(both.is_satisfied(&assignment), a != b && b != c);
+                    assert_eq!(either.is_satisfied(&assignment), a != b || b != c);
+                    assert_eq!(
+                        not_diff.is_satisfied(&assignment),
+                        same.is_satisfied(&assignment)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_small_domain() {
+        use csp_solver::csp::{Domain, VecDomain};
+
+        assert!(SmallDomain::<i32, 3>::new(vec![1, 2, 3, 4]).is_err());
+        // duplicates do not count against capacity
+        let domain = SmallDomain::<i32, 3>::new(vec![1, 2, 2, 3]).unwrap();
+        assert_eq!(domain.size(), 3);
+        assert!(domain.contains(&2));
+
+        let removed = domain.remove(&2);
+        assert_eq!(removed.to_sorted_vec(), vec![1, 3]);
+        assert_eq!(domain.restrict_to(vec![3, 4]).to_sorted_vec(), vec![3]);
+        assert!(domain.restrict_to(vec![]).is_empty());
+
+        // results that do not fit are an error rather than a truncated domain
+        let one = SmallDomain::<i32, 3>::new(vec![1]).unwrap();
+        let universe = VecDomain::new(1..=5);
+        assert!(one.try_complement_in(&universe).is_err());
+        let complement = removed.try_complement_in(&VecDomain::new(1..=4)).unwrap();
+        assert_eq!(complement.to_sorted_vec(), vec![2, 4]);
+        assert_eq!(
+            one.try_symmetric_difference_with(&removed)
+                .unwrap()
+                .to_sorted_vec(),
+            vec![3]
+        );
+        let high = SmallDomain::<i32, 3>::new(vec![4, 5, 6]).unwrap();
+        assert!(domain.try_symmetric_difference_with(&high).is_err());
+
+        let colors = ["red", "green", "blue"].map(String::from);
+        let csp = examples::australia::create_australia_csp_with_domain(
+            SmallDomain::<String, 3>::new(colors).unwrap(),
+        );
+        let solution = ArcConsistencySolver::solve(&csp).expect("Australia is 3-colorable");
+        assert!(csp.is_solution(&solution));
+    }
+
+    #[test]
+    fn test_domain_filtering_sudoku() {
+        use csp_solver::csp::{Domain, Variable};
+        use csp_solver::examples::sudoku::{cell_var, create_standard_sudoku_csp};
+        use csp_solver::solver::preprocessing::domain_filtering;
+        use std::collections::HashMap;
+
+        let solution = [
+            "534678912",
+            "672195348",
+            "198342567",
+            "859761423",
+            "426853791",
+            "713924856",
+            "961537284",
+            "287419635",
+            "345286179",
+        ];
+        let digit = |row: usize, col: usize| solution[row].as_bytes()[col] as usize - '0' as usize;
+        // blank three cells in every row, column and box; has_support walks the
+        // Cartesian product of the blanks, so a sparser board gets slow
+        let puzzle: Vec<(usize, usize, usize)> = (0..9)
+            .flat_map(|row| (0..9).map(move |col| (row, col)))
+            .filter(|&(row, col)| (row * 3 + row / 3 + col) % 9 >= 3)
+            .map(|(row, col)| (row, col, digit(row, col)))
+            .collect();
+        let sudoku = create_standard_sudoku_csp(9, &puzzle).unwrap();
+
+        // AC-3 only revises binary arcs, so the all-different constraints prune nothing
+        let mut ac3_domains: HashMap<Variable<usize>, _> = sudoku
+            .get_variables()
+            .into_iter()
+            .map(|var| {
+                let domain = sudoku.get_domain(&var).unwrap().clone();
+                (var, domain)
+            })
+            .collect();
+        assert!(ArcConsistencySolver::ac3(&sudoku, &mut ac3_domains));
+
+        let domains = domain_filtering(&sudoku).expect("puzzle is consistent");
+        let mut narrower = 0;
+        for row in 0..9 {
+            for col in 0..9 {
+                let var = cell_var(row, col);
+                assert!(domains[&var].contains(&digit(row, col)));
+                assert!(domains[&var].size() <= ac3_domains[&var].size());
+                if domains[&var].size() < ac3_domains[&var].size() {
+                    narrower += 1;
+                }
+            }
+        }
+        // every blank cell loses the six values given in its row
+        assert_eq!(narrower, 27);
+        assert!(domains.values().all(|domain| domain.size() <= 3));
+
+        // a second 7 in row 0
+        let mut clashing = puzzle.clone();
+        clashing[0] = (0, 3, 7);
+        let conflicting = create_standard_sudoku_csp(9, &clashing).unwrap();
+        assert!(domain_filtering(&conflicting).is_none());
+    }
+
+    #[test]
+    fn test_substitute_variable() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Domain, Variable, VecDomain, common};
+
+        let x = Variable::new("X");
+        let y = Variable::new("Y");
+        let z = Variable::new("Z");
+        let mut csp = Csp::<i32, VecDomain<i32>>::new();
+        csp.add_variable(x.clone(), VecDomain::new(vec![1, 2, 3]))
+            .unwrap();
+        csp.add_variable(y.clone(), VecDomain::new(vec![2, 3, 4]))
+            .unwrap();
+        csp.add_variable(z.clone(), VecDomain::new(vec![1, 2, 3]))
+            .unwrap();
+        csp.add_constraint(common::same("X=Y", x.clone(), y.clone()))
+            .unwrap();
+        csp.add_constraint(common::diff("X-Z", x.clone(), z.clone()))
+            .unwrap();
+        let original = csp.clone();
+
+        csp.substitute_variable(&x, &y).unwrap();
+        assert_eq!(csp.num_variables(), 2);
+        assert_eq!(csp.get_domain(&y).unwrap().to_sorted_vec(), vec![2, 3]);
+        assert!(csp.get_constraints().iter().all(|c| !c.involves(&x)));
+
+        let expected = BacktrackingSolver::find_all_backtracking(&original);
+        let merged = BacktrackingSolver::find_all_backtracking(&csp);
+        assert_eq!(merged.len(), expected.len());
+        for solution in merged {
+            let mut restored = solution.clone();
+            restored.assign(x.clone(), *solution.get(&y).unwrap());
+            assert!(original.is_solution(&restored));
+        }
+
+        assert!(csp.substitute_variable(&x, &z).is_err());
+    }
+
+    #[test]
+    fn test_occurrence_constraints() {
+        use csp_solver::csp::{Assignment, Variable, common};
+
+        let vars: Vec<Variable<i32>> = ["A", "B", "C"].into_iter().map(Variable::new).collect();
+        let exactly = common::exactly_once("one-1", vars.clone(), 1);
+        let at_most = common::at_most_once("max-1", vars.clone(), 1);
+        let at_least = common::at_least_once("min-1", vars.clone(), 1);
+
+        let assign = |values: &[i32]| {
+            let mut assignment = Assignment::new();
+            for (var, value) in vars.iter().zip(values) {
+                assignment.assign(var.clone(), *value);
+            }
+            assignment
+        };
+
+        for (values, ones) in [(vec![2, 3, 2], 0), (vec![1, 3, 2], 1), (vec![1, 1, 2], 2)] {
+            let assignment = assign(&values);
+            assert_eq!(exactly.is_satisfied(&assignment), ones == 1);
+            assert_eq!(at_most.is_satisfied(&assignment), ones <= 1);
+            assert_eq!(at_least.is_satisfied(&assignment), ones >= 1);
+        }
+        // a repeated value is rejected before the last variable is assigned
+        assert!(!exactly.is_satisfied(&assign(&[1, 1])));
+        assert!(exactly.is_satisfied(&assign(&[2, 2])));
+
+        // color Australia with exactly one red region
+        let mut australia = examples::australia::create_australia_csp();
+        let regions = australia.get_variables();
+        australia
+            .add_constraint(common::exactly_once(
+                "one-red",
+                regions.clone(),
+                "red".to_string(),
+            ))
+            .unwrap();
+        let solution = BacktrackingSolver::mrv_search(&australia).expect("one red region suffices");
+        let reds = regions
+            .iter()
+            .filter(|region| solution.get(region).map(String::as_str) == Some("red"))
+            .count();
+        assert_eq!(reds, 1);
+    }
+
+    #[test]
+    fn test_revise_batch_matches_sequential() {
+        use csp_solver::csp::{Constraint, Domain, DomainsSnapshot, Variable, VecDomain};
+        use std::collections::HashMap;
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        // chain X1 < X2 < X3 < X4 < X5, counting predicate calls
+        let calls = Arc::new(AtomicU64::new(0));
+        let vars: Vec<Variable<i32>> = (1..=5).map(|i| Variable::new(&format!("X{}", i))).collect();
+        let less_than = |a: &Variable<i32>, b: &Variable<i32>| {
+            let (a, b, calls) = (a.clone(), b.clone(), calls.clone());
+            let name = format!("{}<{}", a.name, b.name);
+            Constraint::new(&name, vec![a.clone(), b.clone()], move |assignment| {
+                calls.fetch_add(1, Ordering::Relaxed);
+                assignment.get(&a) < assignment.get(&b)
+            })
+        };
+        // X4 is already down to 1..=3, so X4 refutes X3 = 3, 4, 5 in a row
+        let mut domains: HashMap<Variable<i32>, VecDomain<i32>> = vars
+            .iter()
+            .map(|var| (var.clone(), VecDomain::new(1..=5)))
+            .collect();
+        domains.insert(vars[3].clone(), VecDomain::new(1..=3));
+
+        let xi = &vars[2];
+        let arcs = vec![
+            (vars[1].clone(), less_than(&vars[1], xi)),
+            (vars[3].clone(), less_than(xi, &vars[3])),
+        ];
+
+        let mut sequential = domains.clone();
+        let mut trail = DomainsSnapshot::new();
+        calls.store(0, Ordering::Relaxed);
+        for (xj, constraint) in &arcs {
+            ArcConsistencySolver::revise(&mut sequential, xi, xj, constraint, &mut trail);
+        }
+        let sequential_calls = calls.load(Ordering::Relaxed);
+
+        let mut batched = domains.clone();
+        calls.store(0, Ordering::Relaxed);
+        assert!(ArcConsistencySolver::revise_batch(xi, &arcs, &mut batched));
+        let batched_calls = calls.load(Ordering::Relaxed);
+
+        assert_eq!(batched[xi].to_sorted_vec(), vec![2]);
+        assert_eq!(batched[xi].to_sorted_vec(), sequential[xi].to_sorted_vec());
+        // one arc at a time, then trying the refuting arc first
+        assert_eq!((sequential_calls, batched_calls), (21, 19));
+        assert!(!ArcConsistencySolver::revise_batch(xi, &arcs, &mut batched));
+    }
+
+    #[test]
+    fn test_profiler_counts_australia() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Assignment, Domain, Variable};
+        use csp_solver::solver::CspProfiler;
+        use std::time::Duration;
+
+        // WA and NSW both take red, then NT and Q leave SA no color
+        fn dead_end_order<D: Domain<String>>(
+            assignment: &Assignment<String>,
+            _csp: &Csp<String, D>,
+        ) -> Option<Variable<String>> {
+            ["WA", "NSW", "NT", "Q", "SA", "V", "T"]
+                .into_iter()
+                .map(Variable::new)
+                .find(|var| !assignment.is_assigned(var))
+        }
+
+        let csp = examples::australia::create_australia_csp();
+        let profiler = CspProfiler::new();
+        let solve = profiler.wrap_solver(dead_end_order, domain_order, |csp, vs, vo| {
+            BacktrackingSolver::solve_full(csp, vs, vo, None, None)
+        });
+
+        let solution = solve(&csp).expect("Australia is 3-colorable");
+        assert!(csp.is_solution(&solution));
+
+        let profile = profiler.profile();
+        assert!(profile.variable_selection_time > Duration::ZERO);
+        assert!(profile.value_ordering_time > Duration::ZERO);
+        assert!(profile.constraint_check_time > Duration::ZERO);
+        assert!(profile.constraint_checks > 0);
+        assert!(profile.domain_operations > 0);
+        assert!(profile.backtracks > 0);
+
+        profiler.reset();
+        assert_eq!(profiler.profile().constraint_checks, 0);
+    }
+
+    #[test]
+    fn test_induce_subproblem() {
+        use csp_solver::csp::Variable;
+        use csp_solver::csp::csp::Csp;
+
+        let australia = examples::australia::create_australia_csp();
+        let subset: Vec<Variable<String>> =
+            ["WA", "NT", "SA"].into_iter().map(Variable::new).collect();
+        let sub = australia.induce_subproblem(&subset);
+
+        assert_eq!(sub.num_variables(), 3);
+        let mut names: Vec<&str> = sub.get_constraints().iter().map(|c| c.name()).collect();
+        names.sort();
+        assert_eq!(names, vec!["NT-SA", "WA-NT", "WA-SA"]);
+
+        let sa_q = australia
+            .get_constraints()
+            .iter()
+            .find(|c| c.name() == "SA-Q")
+            .unwrap();
+        assert!(
+            Csp::<String, csp_solver::csp::HashSetDomain<String>>::project_constraint(
+                sa_q, &subset
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_queens_with_preassignment() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Assignment, Domain, Variable};
+        use csp_solver::examples::queens::create_queens_csp_with_preassignment;
+
+        // column order keeps the search deterministic
+        fn by_column<D: Domain<usize>>(
+            assignment: &Assignment<usize>,
+            csp: &Csp<usize, D>,
+        ) -> Option<Variable<usize>> {
+            csp.get_variables()
+                .into_iter()
+                .filter(|var| !assignment.is_assigned(var))
+                .min_by(|a, b| a.name.cmp(&b.name))
+        }
+
+        let plain = examples::queens::create_queens_csp(8);
+        let hinted = create_queens_csp_with_preassignment(8, &[(0, 0), (1, 4), (2, 7)]).unwrap();
+        assert_eq!(hinted.get_domain_size(&Variable::new("Q1")), Some(1));
+
+        let plain_result =
+            BacktrackingSolver::solve_full(&plain, by_column, domain_order, None, None);
+        let hinted_result =
+            BacktrackingSolver::solve_full(&hinted, by_column, domain_order, None, None);
+        let solution = hinted_result
+            .solution()
+            .expect("hints extend to a solution");
+        assert!(hinted.is_solution(solution));
+        assert_eq!(solution.get(&Variable::new("Q2")), Some(&7));
+        assert!(hinted_result.stats().nodes_explored < plain_result.stats().nodes_explored);
+
+        let err = create_queens_csp_with_preassignment(8, &[(0, 0), (2, 2)]).err();
+        assert!(err.is_some_and(|err| err.contains("attacks")));
+        assert!(create_queens_csp_with_preassignment(8, &[(0, 0), (0, 3)]).is_err());
+        assert!(create_queens_csp_with_preassignment(8, &[(8, 0)]).is_err());
+    }
+
+    #[test]
+    fn test_value_promise_ordering() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Assignment, Constraint, Variable, VecDomain};
+        use csp_solver::solver::heuristics::{least_constraining_value, value_promise};
+
+        // X=0 removes three values, all from Y (1 * 4 * 4 left); X=1 removes
+        // four spread over Y, Z and W (2 * 3 * 3 left): LCV picks X=0 first,
+        // value promise picks X=1
+        let x = Variable::new("X");
+        let mut csp = Csp::new();
+        csp.add_variable(x.clone(), VecDomain::new(vec![0, 1]))
+            .unwrap();
+        for (name, removed_by_0, removed_by_1) in [
+            ("Y", vec![0, 1, 2], vec![0, 1]),
+            ("Z", vec![], vec![0]),
+            ("W", vec![], vec![0]),
+        ] {
+            let other = Variable::new(name);
+            csp.add_variable(other.clone(), VecDomain::new(0..4))
+                .unwrap();
+            let (cx, cother) = (x.clone(), other.clone());
+            csp.add_constraint(Constraint::new(
+                &format!("X{}", name),
+                vec![x.clone(), other],
+                move |assignment| match (assignment.get(&cx), assignment.get(&cother)) {
+                    (Some(0), Some(val)) => !removed_by_0.contains(val),
+                    (Some(1), Some(val)) => !removed_by_1.contains(val),
+                    _ => true,
+                },
+            ))
+            .unwrap();
+        }
+
+        let empty = Assignment::new();
+        let domain = csp.get_domain(&x).unwrap().clone();
+        assert_eq!(
+            least_constraining_value(&x, &domain, &empty, &csp),
+            vec![0, 1]
+        );
+        assert_eq!(value_promise(&x, &domain, &empty, &csp), vec![1, 0]);
+
+        // Two copies of a tie for LCV: Xi=0 wipes out the two-valued Zi, Xi=1
+        // takes one value each from Zi and the spare Yi. Value promise sees the
+        // empty Zi as a zero product; LCV only finds out once name order reaches Zi
+        let mut squeezed = Csp::new();
+        for i in 0..2 {
+            let x = Variable::new(&format!("X{}", i));
+            let y = Variable::new(&format!("Y{}", i));
+            let z = Variable::new(&format!("Z{}", i));
+            squeezed
+                .add_variable(x.clone(), VecDomain::new(vec![0, 1]))
+                .unwrap();
+            squeezed
+                .add_variable(y.clone(), VecDomain::new(0..4))
+                .unwrap();
+            squeezed
+                .add_variable(z.clone(), VecDomain::new(vec![0, 1]))
+                .unwrap();
+            let (cx, cz) = (x.clone(), z.clone());
+            squeezed
+                .add_constraint(Constraint::new(
+                    &format!("XZ{}", i),
+                    vec![x.clone(), z],
+                    move |assignment| match (assignment.get(&cx), assignment.get(&cz)) {
+                        (Some(0), Some(_)) => false,
+                        (Some(1), Some(z)) => *z != 0,
+                        _ => true,
+                    },
+                ))
+                .unwrap();
+            let (cx, cy) = (x.clone(), y.clone());
+            squeezed
+                .add_constraint(Constraint::new(
+                    &format!("XY{}", i),
+                    vec![x, y],
+                    move |assignment| match (assignment.get(&cx), assignment.get(&cy)) {
+                        (Some(1), Some(y)) => *y != 0,
+                        _ => true,
+                    },
+                ))
+                .unwrap();
+        }
+        let by_name = |assignment: &Assignment<usize>, csp: &Csp<usize, VecDomain<usize>>| {
+            csp.get_variables()
+                .into_iter()
+                .filter(|var| !assignment.is_assigned(var))
+                .min_by(|a, b| a.name.cmp(&b.name))
+        };
+        let lcv = BacktrackingSolver::solve_full(
+            &squeezed,
+            by_name,
+            least_constraining_value,
+            None,
+            None,
+        );
+        let promise = BacktrackingSolver::solve_full(&squeezed, by_name, value_promise, None, None);
+        assert!(squeezed.is_solution(promise.solution().unwrap()));
+        assert!(promise.stats().backtracks < lcv.stats().backtracks);
+        assert!(promise.stats().nodes_explored * 10 < lcv.stats().nodes_explored);
+
+        let queens = examples::queens::create_queens_csp(10);
+        let solution = BacktrackingSolver::find_solution_value_promise(&queens).unwrap();
+        assert!(queens.is_solution(&solution));
+    }
+
+    #[test]
+    fn test_decompose_global_constraints() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Assignment, Domain, Variable, VecDomain, common};
+
+        fn by_name<D: Domain<usize>>(
+            assignment: &Assignment<usize>,
+            csp: &Csp<usize, D>,
+        ) -> Option<Variable<usize>> {
+            csp.get_variables()
+                .into_iter()
+                .filter(|var| !assignment.is_assigned(var))
+                .min_by(|a, b| a.name.cmp(&b.name))
+        }
+
+        let puzzle = [(0, 0, 1), (1, 3, 2), (2, 1, 3), (3, 2, 4)];
+        let global = examples::sudoku::create_sudoku_csp(&puzzle);
+        let mut decomposed = global.clone();
+
+        // 12 all-different constraints over 4 cells, 6 pairs each
+        assert_eq!(decomposed.decompose_global_constraints(), 72);
+        assert_eq!(decomposed.num_constraints(), global.num_constraints() + 72);
+        // already decomposed pairs are not added twice
+        assert_eq!(decomposed.add_implied_binary_not_equals(), 0);
+        assert_eq!(decomposed.num_constraints(), global.num_constraints() + 72);
+
+        // A = B and all_different(B, C, D) imply A != C and A != D
+        let mut linked: Csp<usize, VecDomain<usize>> = Csp::new();
+        let vars: Vec<Variable<usize>> = ["A", "B", "C", "D"]
+            .iter()
+            .map(|n| Variable::new(n))
+            .collect();
+        for var in &vars {
+            linked
+                .add_variable(var.clone(), VecDomain::new(vec![1, 2, 3]))
+                .unwrap();
+        }
+        linked
+            .add_constraint(common::same("A=B", vars[0].clone(), vars[1].clone()))
+            .unwrap();
+        linked
+            .add_constraint(common::all_different("BCD", vars[1..].to_vec()))
+            .unwrap();
+        assert_eq!(linked.add_implied_binary_not_equals(), 5);
+        let mut assignment = Assignment::new();
+        assignment.assign(vars[0].clone(), 1);
+        assignment.assign(vars[2].clone(), 1);
+        let implied = linked
+            .get_constraints()
+            .iter()
+            .find(|c| c.name() == "implied-A-C")
+            .unwrap();
+        assert!(!implied.is_satisfied(&assignment));
+
+        let plain = BacktrackingSolver::solve_full(&global, by_name, domain_order, None, None);
+        let pruned = BacktrackingSolver::solve_full(&decomposed, by_name, domain_order, None, None);
+        assert!(global.is_solution(pruned.solution().unwrap()));
+        assert!(pruned.stats().nodes_explored < plain.stats().nodes_explored);
+    }
+
+    #[test]
+    fn test_find_solution_smart() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Assignment, IntelligentConstraint, Variable, VecDomain, common};
+        use csp_solver::solver::heuristics::{
+            constraint_suggested_variable, minimum_remaining_values,
+        };
+        use csp_solver::solver::utils::domain_order;
+        use std::collections::HashMap;
+
+        let vars: Vec<Variable<usize>> =
+            (0..3).map(|i| Variable::new(&format!("V{}", i))).collect();
+        let all_diff = common::all_different("AllDiff", vars.clone());
+        let domains: HashMap<Variable<usize>, VecDomain<usize>> = HashMap::from([
+            (vars[0].clone(), VecDomain::new(vec![1, 2, 3])),
+            (vars[1].clone(), VecDomain::new(vec![1, 2, 3])),
+            (vars[2].clone(), VecDomain::new(vec![1, 2])),
+        ]);
+
+        let mut assignment = Assignment::new();
+        assert_eq!(
+            all_diff.suggest_next_variable(&assignment, &domains),
+            Some(vars[2].clone())
+        );
+        assignment.assign(vars[2].clone(), 1);
+        // V0 and V1 both have two values left, so the tie goes to the first name
+        assert_eq!(
+            all_diff.suggest_next_variable(&assignment, &domains),
+            Some(vars[0].clone())
+        );
+        let not_global = common::diff("V0-V1", vars[0].clone(), vars[1].clone());
+        assert_eq!(
+            not_global.suggest_next_variable(&assignment, &domains),
+            None
+        );
+
+        // queens with an explicit all-different over rows branches on its suggestions
+        let mut queens = examples::queens::create_queens_csp(12);
+        let columns = (0..12)
+            .map(|col| Variable::new(&format!("Q{}", col)))
+            .collect();
+        queens
+            .add_constraint(common::all_different("Rows", columns))
+            .unwrap();
+        let solution = BacktrackingSolver::find_solution_smart(&queens).unwrap();
+        assert!(queens.is_solution(&solution));
+
+        // Y = 0 would leave five values for six all-different variables. MRV
+        // branches on Y first, as it has the smallest domain, and then has to
+        // exhaust the pigeonhole; the all-different constraint keeps the search
+        // on its own variables, so Y = 0 is refuted by a single check
+        let n = 6;
+        let mut pigeonhole: Csp<usize, VecDomain<usize>> = Csp::new();
+        let y = Variable::new("Y");
+        pigeonhole
+            .add_variable(y.clone(), VecDomain::new(vec![0, 1]))
+            .unwrap();
+        let xs: Vec<Variable<usize>> = (0..n).map(|i| Variable::new(&format!("X{}", i))).collect();
+        for x in &xs {
+            pigeonhole
+                .add_variable(x.clone(), VecDomain::new(1..=n))
+                .unwrap();
+            let name = format!("Y0-{}", x);
+            let below = common::in_set(&name, x.clone(), (1..n).collect::<Vec<_>>());
+            pigeonhole
+                .add_constraint(common::implies(&name, y.clone(), 0, below))
+                .unwrap();
+        }
+        pigeonhole
+            .add_constraint(common::all_different("AllDiff", xs))
+            .unwrap();
+
+        let smart = BacktrackingSolver::solve_full(
+            &pigeonhole,
+            constraint_suggested_variable,
+            domain_order,
+            None,
+            None,
+        );
+        let mrv = BacktrackingSolver::solve_full(
+            &pigeonhole,
+            minimum_remaining_values,
+            domain_order,
+            None,
+            None,
+        );
+        let solution = BacktrackingSolver::find_solution_smart(&pigeonhole).unwrap();
+        assert!(pigeonhole.is_solution(&solution));
+        assert_eq!(solution.get(&y), Some(&1));
+        assert!(mrv.solution().is_some());
+        assert!(
+            smart.stats().nodes_explored * 10 < mrv.stats().nodes_explored,
+            "smart explored {} nodes, MRV {}",
+            smart.stats().nodes_explored,
+            mrv.stats().nodes_explored
+        );
+    }
+
+    #[test]
+    fn test_sudoku_x() {
+        use csp_solver::examples::sudoku::cell_var;
+        use std::collections::HashSet;
+
+        let mut sudoku_x = examples::sudoku::create_sample_sudoku_x();
+        // AC-3 only propagates binary constraints, so expose the pairwise differences
+        sudoku_x.decompose_global_constraints();
+
+        let solution = ArcConsistencySolver::solve(&sudoku_x).expect("puzzle has a solution");
+        assert!(sudoku_x.is_solution(&solution));
+
+        let cell = |row: usize, col: usize| *solution.get(&cell_var(row, col)).unwrap();
+        let all_digits: HashSet<usize> = (1..=9).collect();
+        for i in 0..9 {
+            assert_eq!(
+                (0..9).map(|col| cell(i, col)).collect::<HashSet<_>>(),
+                all_digits
+            );
+            assert_eq!(
+                (0..9).map(|row| cell(row, i)).collect::<HashSet<_>>(),
+                all_digits
+            );
+            let (box_row, box_col) = (i / 3 * 3, i % 3 * 3);
+            let in_box = (0..9).map(|k| cell(box_row + k / 3, box_col + k % 3));
+            assert_eq!(in_box.collect::<HashSet<_>>(), all_digits);
+        }
+        assert_eq!(
+            (0..9).map(|i| cell(i, i)).collect::<HashSet<_>>(),
+            all_digits
+        );
+        assert_eq!(
+            (0..9).map(|i| cell(i, 8 - i)).collect::<HashSet<_>>(),
+            all_digits
+        );
+        assert_eq!(cell(0, 0), 2);
+
+        // two-digit coordinates get distinct names, and boxes need a square size
+        let large = examples::sudoku::create_sudoku_x_csp(16, &[]).unwrap();
+        assert_eq!(large.num_variables(), 256);
+        assert_ne!(cell_var(1, 11), cell_var(11, 1));
+        assert!(examples::sudoku::create_sudoku_x_csp(6, &[]).is_err());
+    }
+
+    #[test]
+    fn test_gac3_all_different() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Domain, Variable, VecDomain, common};
+        use std::collections::HashMap;
+
+        let vars: Vec<Variable<i32>> = ["X", "Y", "Z"].into_iter().map(Variable::new).collect();
+        let mut global = Csp::<i32, VecDomain<i32>>::new();
+        global
+            .add_variable(vars[0].clone(), VecDomain::new(vec![1, 2]))
+            .unwrap();
+        global
+            .add_variable(vars[1].clone(), VecDomain::new(vec![1, 2]))
+            .unwrap();
+        global
+            .add_variable(vars[2].clone(), VecDomain::new(vec![1, 2, 3]))
+            .unwrap();
+        global
+            .add_constraint(common::all_different("XYZ", vars.clone()))
+            .unwrap();
+
+        let initial_domains =
+            |csp: &Csp<i32, VecDomain<i32>>| -> HashMap<Variable<i32>, VecDomain<i32>> {
+                vars.iter()
+                    .map(|var| (var.clone(), csp.get_domain(var).unwrap().clone()))
+                    .collect()
+            };
+
+        // X and Y use up 1 and 2, so GAC leaves Z with 3
+        let mut gac_domains = initial_domains(&global);
+        assert!(ArcConsistencySolver::gac3(&global, &mut gac_domains));
+        assert_eq!(gac_domains[&vars[2]].to_sorted_vec(), vec![3]);
+
+        // every pair of the binary decomposition has support, so AC-3 prunes nothing
+        let mut decomposed = Csp::<i32, VecDomain<i32>>::new();
+        for var in &vars {
+            decomposed
+                .add_variable(var.clone(), global.get_domain(var).unwrap().clone())
+                .unwrap();
+        }
+        decomposed
+            .add_constraint(common::all_different("XYZ", vars.clone()))
+            .unwrap();
+        decomposed.decompose_global_constraints();
+        let mut ac_domains = initial_domains(&decomposed);
+        assert!(ArcConsistencySolver::ac3(&decomposed, &mut ac_domains));
+        assert_eq!(ac_domains[&vars[2]].to_sorted_vec(), vec![1, 2, 3]);
+
+        let solution = ArcConsistencySolver::solve_gac(&global).unwrap();
+        assert!(global.is_solution(&solution));
+        assert_eq!(solution.get(&vars[2]), Some(&3));
+    }
+
+    #[test]
+    fn test_domains_snapshot() {
+        use csp_solver::csp::{Domain, Variable};
+
+        let mut queens = examples::queens::create_queens_csp(12);
+        let q0 = Variable::new("Q0");
+        let q1 = Variable::new("Q1");
+        let full = queens.get_domain(&q0).unwrap().clone();
+
+        let mut snapshot = queens.get_domains_snapshot();
+        queens
+            .update_domain(&q0, full.restrict_to(vec![0, 1]), &mut snapshot)
+            .unwrap();
+        queens
+            .update_domain(&q0, full.restrict_to(vec![0]), &mut snapshot)
+            .unwrap();
+        queens
+            .update_domain(&q1, full.restrict_to(vec![5]), &mut snapshot)
+            .unwrap();
+        // only the two changed variables are saved, each once
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(queens.get_domain_size(&q0), Some(1));
+        // changes made without the snapshot are undone too
+        let q2 = Variable::new("Q2");
+        queens.set_domain(&q2, full.restrict_to(vec![7])).unwrap();
+        assert!(
+            queens
+                .set_domain(&Variable::new("Q12"), full.clone())
+                .is_err()
+        );
+        assert!(
+            queens
+                .update_domain(&Variable::new("Q12"), full.clone(), &mut snapshot)
+                .is_err()
+        );
+
+        queens.restore_domains_snapshot(snapshot);
+        assert_eq!(queens.get_domain_size(&q0), Some(12));
+        assert_eq!(queens.get_domain_size(&q2), Some(12));
+        assert_eq!(
+            queens.get_domain(&q1).unwrap().to_sorted_vec(),
+            full.to_sorted_vec()
+        );
+
+        // the arc consistency solver undoes propagation through snapshots
+        let solution = ArcConsistencySolver::solve(&queens).expect("12-queens has a solution");
+        assert!(queens.is_solution(&solution));
+    }
+
+    #[test]
+    fn test_domains_snapshot_allocations() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Domain, VecDomain};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // counts every domain clone, the allocations a full domain-map copy makes
+        static CLONES: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Debug)]
+        struct CountingDomain(VecDomain<usize>);
+
+        impl Clone for CountingDomain {
+            fn clone(&self) -> Self {
+                CLONES.fetch_add(1, Ordering::Relaxed);
+                CountingDomain(self.0.clone())
+            }
+        }
+
+        impl Domain<usize> for CountingDomain {
+            fn contains(&self, value: &usize) -> bool {
+                self.0.contains(value)
+            }
+            fn size(&self) -> usize {
+                self.0.size()
+            }
+            fn is_empty(&self) -> bool {
+                self.0.is_empty()
+            }
+            fn values(&self) -> Vec<usize> {
+                self.0.values()
+            }
+            fn remove(&self, value: &usize) -> Self {
+                CountingDomain(self.0.remove(value))
+            }
+            fn restrict_to<I: IntoIterator<Item = usize>>(&self, values_to_keep: I) -> Self {
+                CountingDomain(self.0.restrict_to(values_to_keep))
+            }
+        }
+
+        let queens = examples::queens::create_queens_csp(12);
+        let mut counting: Csp<usize, CountingDomain> = Csp::new();
+        for var in queens.get_variables() {
+            let domain = CountingDomain(queens.get_domain(&var).unwrap().clone());
+            counting.add_variable(var, domain).unwrap();
+        }
+        for constraint in queens.get_constraints() {
+            counting.add_constraint(constraint.clone()).unwrap();
+        }
+
+        CLONES.store(0, Ordering::Relaxed);
+        let mut nodes = 0;
+        let solution = ArcConsistencySolver::solve_with_callback(&counting, |_, _, _| nodes += 1)
+            .expect("12-queens has a solution");
+        assert!(counting.is_solution(&solution));
+        let trailed = CLONES.load(Ordering::Relaxed);
+
+        // cloning the whole domain map at every node would copy all 12 domains each time
+        let full_clone = nodes * counting.num_variables();
+        assert!(
+            trailed * 4 < full_clone,
+            "{} domain clones with the trail vs {} with full copies",
+            trailed,
+            full_clone
+        );
+    }
+
+    #[test]
+    fn test_knights_tour() {
+        use csp_solver::csp::{Variable, common};
+        use std::collections::HashSet;
+
+        let n = 5;
+        let mut tour = examples::queens::create_knights_tour_csp(n);
+        assert_eq!(tour.num_variables(), 25);
+        // AC-3 only propagates binary constraints, so expose the pairwise differences
+        tour.decompose_global_constraints();
+        // open tours on the 5x5 board can start in a corner
+        tour.add_constraint(common::in_set("StartCorner", Variable::new("T0"), vec![0]))
+            .unwrap();
+        tour.apply_unary_constraints();
+
+        let solution = ArcConsistencySolver::solve(&tour).expect("a 5x5 knight's tour exists");
+        assert!(tour.is_solution(&solution));
+
+        let squares: Vec<usize> = (0..n * n)
+            .map(|i| *solution.get(&Variable::new(&format!("T{}", i))).unwrap())
+            .collect();
+        assert_eq!(squares.iter().collect::<HashSet<_>>().len(), n * n);
+        for pair in squares.windows(2) {
+            let rows = (pair[0] / n).abs_diff(pair[1] / n);
+            let cols = (pair[0] % n).abs_diff(pair[1] % n);
+            assert_eq!(rows * cols, 2);
+        }
+    }
+
+    #[test]
+    fn test_variable_construction_paths() {
+        use csp_solver::csp::Variable;
+
+        let expected: Variable<i32> = Variable::new("X1");
+        let parsed: Variable<i32> = "X1".parse().unwrap();
+        let owned: Variable<i32> = Variable::from_name(String::from("X1"));
+        let from_str: Variable<i32> = Variable::from("X1");
+        let from_string: Variable<i32> = String::from("X1").into();
+        assert_eq!(parsed, expected);
+        assert_eq!(owned, expected);
+        assert_eq!(from_str, expected);
+        assert_eq!(from_string, expected);
+
+        let vars: Vec<Variable<i32>> = ["A", "B", "C"].into_iter().map(Variable::from).collect();
+        assert_eq!(vars[2], Variable::new("C"));
+    }
+
+    #[test]
+    fn test_australia_to_xcsp3() {
+        use csp_solver::csp::Xcsp3Error;
+
+        let australia = examples::australia::create_australia_csp();
+        let xml = australia.to_xcsp3().unwrap();
+
+        // every element opened is closed in order
+        let mut open_tags = Vec::new();
+        for tag in xml
+            .split('<')
+            .skip(1)
+            .map(|part| part.split('>').next().unwrap())
+        {
+            if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(open_tags.pop(), Some(name.to_string()));
+            } else {
+                open_tags.push(tag.split_whitespace().next().unwrap().to_string());
+            }
+        }
+        assert!(open_tags.is_empty());
+
+        assert!(xml.starts_with("<instance format=\"XCSP3\" type=\"CSP\">"));
+        assert_eq!(xml.matches("<var ").count(), 7);
+        for region in ["WA", "NT", "SA", "Q", "NSW", "V", "T"] {
+            assert!(xml.contains(&format!(
+                "<var id=\"{}\" type=\"symbolic\"> blue green red </var>",
+                region
+            )));
+        }
+        assert_eq!(xml.matches("<intension> ne(").count(), 9);
+        assert!(xml.contains("ne(WA,NT)"));
+
+        let path = std::env::temp_dir().join("australia.xcsp3.xml");
+        australia.save_xcsp3(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), xml);
+        std::fs::remove_file(&path).unwrap();
+
+        let sudoku = examples::sudoku::create_sample_sudoku();
+        assert_eq!(
+            sudoku.to_xcsp3().unwrap().matches("<allDifferent>").count(),
+            12
+        );
+        let queens = examples::queens::create_queens_csp(4);
+        assert!(matches!(
+            queens.to_xcsp3(),
+            Err(Xcsp3Error::UnsupportedConstraint(_))
+        ));
+    }
+
+    #[test]
+    fn test_proximity_constraints() {
+        use csp_solver::csp::{Assignment, Variable, common};
+
+        let start_a: Variable<u32> = Variable::new("StartA");
+        let start_b: Variable<u32> = Variable::new("StartB");
+        let close = common::within_tolerance("close", start_a.clone(), start_b.clone(), 2);
+        let apart = common::differ_by_exactly("apart", start_a.clone(), start_b.clone(), 3);
+
+        let check = |a: u32, b: u32| {
+            let mut assignment = Assignment::new();
+            assignment.assign(start_a.clone(), a);
+            assignment.assign(start_b.clone(), b);
+            (
+                close.is_satisfied(&assignment),
+                apart.is_satisfied(&assignment),
+            )
+        };
+
+        assert_eq!(check(3, 5), (true, false));
+        assert_eq!(check(3, 6), (false, true));
+        // unsigned values in either order
+        assert_eq!(check(6, 3), (false, true));
+        assert_eq!(check(4, 4), (true, false));
+    }
+
+    #[test]
+    fn test_fc_cbj_queens() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Constraint, Variable, VecDomain, common};
+        use csp_solver::solver::{CbjSolver, FcCbjSolver};
+
+        let queens = examples::queens::create_queens_csp(15);
+        let (solution, fc_cbj_stats) = FcCbjSolver::solve_with_stats(&queens);
+        assert!(queens.is_solution(&solution.unwrap()));
+
+        // A bad first value for K only shows once the queens are placed and X is
+        // tried: forward checking re-searches every queen placement under it,
+        // while FC-CBJ blames K and jumps straight back to it
+        let mut trapped = examples::queens::create_queens_csp(8);
+        let (k, x, y) = (Variable::new("K"), Variable::new("X"), Variable::new("Y"));
+        trapped
+            .add_variable(k.clone(), VecDomain::new(vec![0, 1]))
+            .unwrap();
+        trapped
+            .add_variable(x.clone(), VecDomain::new(0..20))
+            .unwrap();
+        trapped
+            .add_variable(y.clone(), VecDomain::new(0..20))
+            .unwrap();
+        let culprit = k.clone();
+        trapped
+            .add_constraint(Constraint::new("KXY", vec![k, x, y], move |a| {
+                a.get(&culprit) == Some(&1)
+            }))
+            .unwrap();
+        let (solution, trapped_fc_cbj) = FcCbjSolver::solve_with_stats(&trapped);
+        assert!(trapped.is_solution(&solution.unwrap()));
+        let (solution, trapped_fc) = ForwardCheckingSolver::solve_with_stats(&trapped);
+        assert!(trapped.is_solution(&solution.unwrap()));
+        assert!(trapped_fc_cbj.nodes_explored * 10 < trapped_fc.nodes_explored);
+
+        // without forward checking the jumps come too late to make up for the missing pruning
+        let (solution, cbj_stats) = CbjSolver::solve_with_stats(&queens);
+        assert!(queens.is_solution(&solution.unwrap()));
+        assert!(fc_cbj_stats.nodes_explored < cbj_stats.nodes_explored);
+
+        // queens failures involve nearly every placed queen, so FC-CBJ rarely
+        // skips a value there; an unsatisfiable pair reached after unrelated
+        // variables lets it jump straight back to the root
+        let mut late_failure = Csp::<i32, VecDomain<i32>>::new();
+        for name in ["A", "B", "C", "D", "X", "Y"] {
+            late_failure
+                .add_variable(Variable::new(name), VecDomain::new(0..3))
+                .unwrap();
+        }
+        for (a, b) in [("A", "B"), ("C", "D")] {
+            late_failure
+                .add_constraint(common::diff(
+                    &format!("{}-{}", a, b),
+                    Variable::new(a),
+                    Variable::new(b),
+                ))
+                .unwrap();
+        }
+        let (x, y) = (Variable::new("X"), Variable::new("Y"));
+        late_failure
+            .add_constraint(Constraint::new(
+                "XY",
+                vec![x.clone(), y.clone()],
+                move |a| !(a.is_assigned(&x) && a.is_assigned(&y)),
+            ))
+            .unwrap();
+        let (solution, fc_cbj_stats) = FcCbjSolver::solve_with_stats(&late_failure);
+        assert!(solution.is_none());
+        let (solution, fc_stats) = ForwardCheckingSolver::solve_with_stats(&late_failure);
+        assert!(solution.is_none());
+        let (solution, cbj_stats) = CbjSolver::solve_with_stats(&late_failure);
+        assert!(solution.is_none());
+        assert!(fc_cbj_stats.backjumps > 0 && cbj_stats.backjumps > 0);
+        assert!(fc_cbj_stats.nodes_explored < fc_stats.nodes_explored);
+        assert!(fc_cbj_stats.nodes_explored < cbj_stats.nodes_explored);
+
+        // conflict sets must not jump past a variable that could still fix the failure
+        let queens_22 = examples::queens::create_queens_csp(22);
+        let (solution, stats) = FcCbjSolver::solve_with_stats(&queens_22);
+        assert!(queens_22.is_solution(&solution.unwrap()));
+        assert!(stats.backjumps > 0);
+        assert!(FcCbjSolver::solve(&examples::queens::create_queens_csp(3)).is_none());
+    }
+
+    #[test]
+    fn test_count_solutions_all_different() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Variable, VecDomain, common};
+
+        let vars: Vec<Variable<i32>> = ["A", "B", "C"].into_iter().map(Variable::new).collect();
+        let mut triangle = Csp::<i32, VecDomain<i32>>::new();
+        for var in &vars {
+            triangle
+                .add_variable(var.clone(), VecDomain::new(vec![1, 2, 3]))
+                .unwrap();
+        }
+        triangle
+            .add_constraint(common::diff("A-B", vars[0].clone(), vars[1].clone()))
+            .unwrap();
+        triangle
+            .add_constraint(common::diff("B-C", vars[1].clone(), vars[2].clone()))
+            .unwrap();
+        triangle
+            .add_constraint(common::diff("A-C", vars[0].clone(), vars[2].clone()))
+            .unwrap();
+
+        // K3 with 3 colors: 3!/(3-3)! = 6
+        assert_eq!(triangle.count_solutions_all_different(&vars), 6);
+        assert_eq!(
+            BacktrackingSolver::find_all_backtracking(&triangle).len(),
+            6
+        );
+        // a single edge A-B has 3 * 2 colorings
+        assert_eq!(triangle.count_solutions_all_different(&vars[..2]), 6);
+        // a path A-B-C has 3 * 2 * 2 colorings
+        let mut path = Csp::<i32, VecDomain<i32>>::new();
+        for var in &vars {
+            path.add_variable(var.clone(), VecDomain::new(vec![1, 2, 3]))
+                .unwrap();
+        }
+        path.add_constraint(common::diff("A-B", vars[0].clone(), vars[1].clone()))
+            .unwrap();
+        path.add_constraint(common::diff("B-C", vars[1].clone(), vars[2].clone()))
+            .unwrap();
+        assert_eq!(path.count_solutions_all_different(&vars), 12);
+
+        let australia = examples::australia::create_australia_csp();
+        assert_eq!(
+            australia.count_solutions_all_different(&australia.get_variables()) as usize,
+            BacktrackingSolver::find_all_backtracking(&australia).len()
+        );
+    }
+
+    #[test]
+    fn test_least_constraining_variable() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Assignment, Variable, VecDomain, common};
+        use csp_solver::solver::SolveResult;
+        use csp_solver::solver::heuristics::{
+            least_constraining_variable, minimum_remaining_values,
+        };
+
+        // random 3-coloring with a planted solution, so every instance is satisfiable
+        fn planted_coloring(
+            n: usize,
+            edge_percent: u64,
+            seed: u64,
+        ) -> Csp<usize, VecDomain<usize>> {
+            let mut state = seed;
+            let mut next = move || {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                state >> 33
+            };
+            let colors: Vec<usize> = (0..n).map(|_| next() as usize % 3).collect();
+            let vars: Vec<Variable<usize>> =
+                (0..n).map(|i| Variable::new(&format!("V{i:02}"))).collect();
+            let mut csp = Csp::new();
+            for var in &vars {
+                csp.add_variable(var.clone(), VecDomain::new(vec![0, 1, 2]))
+                    .unwrap();
+            }
+            for i in 0..n {
+                for j in i + 1..n {
+                    if colors[i] != colors[j] && next() % 100 < edge_percent {
+                        let name = format!("{i}-{j}");
+                        csp.add_constraint(common::diff(&name, vars[i].clone(), vars[j].clone()))
+                            .unwrap();
+                    }
+                }
+            }
+            csp
+        }
+
+        type Selector =
+            fn(&Assignment<usize>, &Csp<usize, VecDomain<usize>>) -> Option<Variable<usize>>;
+        fn nodes(csp: &Csp<usize, VecDomain<usize>>, selector: Selector) -> u64 {
+            match BacktrackingSolver::solve_full(csp, selector, domain_order, None, None) {
+                SolveResult::Solved(solution, stats) => {
+                    assert!(csp.is_solution(&solution));
+                    stats.nodes_explored
+                }
+                _ => panic!("planted instances are satisfiable"),
+            }
+        }
+
+        let mut differs = false;
+        for (edge_percent, seed) in [(15, 1), (15, 2), (50, 3), (50, 4)] {
+            let csp = planted_coloring(14, edge_percent, seed);
+            let lcvar = nodes(&csp, least_constraining_variable::<usize, VecDomain<usize>>);
+            let mrv = nodes(&csp, minimum_remaining_values::<usize, VecDomain<usize>>);
+            differs |= lcvar != mrv;
+            assert!(csp.is_solution(&BacktrackingSolver::lcvar_search(&csp).unwrap()));
+        }
+        assert!(differs);
+    }
+
+    #[test]
+    fn test_find_backbone() {
+        use csp_solver::csp::Variable;
+
+        // 4-queens has two mirror-image solutions that share no placement
+        let queens = examples::queens::create_queens_csp(4);
+        let backbone = queens.find_backbone_subset(10, 7);
+        assert!(backbone.is_empty());
+        assert!(queens.verify_backbone(backbone));
+
+        // pinning a queen forces the rest of the board into a single solution
+        let pinned = examples::queens::create_queens_csp_with_preassignment(4, &[(0, 1)]).unwrap();
+        let backbone = pinned.find_backbone_subset(5, 7);
+        assert_eq!(backbone.len(), 4);
+        assert!(pinned.verify_backbone(backbone.clone()));
+
+        let mut wrong = backbone;
+        wrong.insert(Variable::new("Q0"), 2);
+        assert!(!pinned.verify_backbone(wrong));
+
+        let queens_3 = examples::queens::create_queens_csp(3);
+        assert!(queens_3.find_backbone_subset(3, 1).is_empty());
+    }
+
+    #[test]
+    fn test_walksat_solver() {
+        use csp_solver::solver::WalkSatSolver;
+
+        let queens = examples::queens::create_queens_csp(20);
+        for seed in 0..5 {
+            let solution = WalkSatSolver::solve(&queens, 0.2, 20_000, seed)
+                .expect("20-queens should be solved within the step budget");
+            assert!(queens.is_solution(&solution));
+        }
+
+        // same seed, same walk
+        let australia = examples::australia::create_australia_csp();
+        let first = WalkSatSolver::solve(&australia, 0.3, 1_000, 42).unwrap();
+        let second = WalkSatSolver::solve(&australia, 0.3, 1_000, 42).unwrap();
+        assert_eq!(first.get_assignments(), second.get_assignments());
+
+        let queens_3 = examples::queens::create_queens_csp(3);
+        assert!(WalkSatSolver::solve(&queens_3, 0.2, 500, 1).is_none());
+    }
+
+    #[test]
+    fn test_assignment_project_and_compatibility() {
+        use csp_solver::csp::{Assignment, Variable};
+
+        let (a, b, c, d) = (
+            Variable::new("A"),
+            Variable::new("B"),
+            Variable::new("C"),
+            Variable::new("D"),
+        );
+        let mut full = Assignment::new();
+        full.assign(a.clone(), 1);
+        full.assign(b.clone(), 2);
+        full.assign(c.clone(), 3);
+
+        let projected = full.project(&[a.clone(), c.clone(), d.clone()]);
+        assert_eq!(projected.size(), 2);
+        assert_eq!(projected.get(&a), Some(&1));
+        assert_eq!(projected.get(&c), Some(&3));
+        assert!(!projected.is_assigned(&b));
+        assert!(!projected.is_assigned(&d));
+
+        let mut other = Assignment::new();
+        other.assign(c.clone(), 3);
+        other.assign(d.clone(), 4);
+        assert!(full.is_compatible_with(&other));
+        assert!(other.is_compatible_with(&full));
+        assert!(projected.is_compatible_with(&full));
+
+        other.assign(a.clone(), 5);
+        assert!(!full.is_compatible_with(&other));
+        assert!(!other.is_compatible_with(&full));
+        assert!(Assignment::new().is_compatible_with(&other));
+    }
+
+    #[test]
+    fn test_interval_domain() {
+        use csp_solver::csp::{Domain, IntervalDomain};
+
+        let domain = IntervalDomain::new(1, 10).unwrap();
+        assert_eq!(domain.size(), 10);
+        assert!(domain.contains(&1) && domain.contains(&10) && !domain.contains(&11));
+        assert_eq!(domain.values(), (1..=10).collect::<Vec<i32>>());
+        assert!(IntervalDomain::new(5, 4).is_err());
+
+        let (left, right) = domain.split_at(4);
+        assert_eq!((left.lo(), left.hi()), (1, 4));
+        assert_eq!((right.lo(), right.hi()), (5, 10));
+        assert_eq!(left.size() + right.size(), domain.size());
+        for v in 1..=10 {
+            assert!(left.contains(&v) != right.contains(&v));
+        }
+        let (all, none) = domain.split_at(10);
+        assert_eq!(all.size(), 10);
+        assert!(none.is_empty());
+
+        let other = IntervalDomain::new(7, 20).unwrap();
+        let both = domain.intersect_with(&other).unwrap();
+        assert_eq!(both.values(), vec![7, 8, 9, 10]);
+        for v in 0..=21 {
+            assert_eq!(both.contains(&v), domain.contains(&v) && other.contains(&v));
+        }
+        assert!(left.intersect_with(&other).is_none());
+
+        // interior removals become holes, removing an endpoint shrinks the interval
+        let holed = domain.remove(&5).remove(&1);
+        assert_eq!(holed.size(), 8);
+        assert_eq!((holed.lo(), holed.hi()), (2, 10));
+        assert!(!holed.contains(&5));
+        let (left, right) = holed.split_at(5);
+        assert_eq!(left.values(), vec![2, 3, 4]);
+        assert_eq!(right.size(), 5);
+        assert_eq!(holed.restrict_to([3, 5, 8]).values(), vec![3, 8]);
+    }
+
+    #[test]
+    fn test_lex_ordering_symmetry_breaking() {
+        use csp_solver::csp::{Variable, common};
+
+        let columns: Vec<Variable<usize>> = (0..4)
+            .map(|col| Variable::new(&format!("Q{}", col)))
+            .collect();
+        let mirrored: Vec<Variable<usize>> = columns.iter().rev().cloned().collect();
+
+        let queens = examples::queens::create_queens_csp(4);
+        assert_eq!(BacktrackingSolver::find_all_backtracking(&queens).len(), 2);
+
+        // the two 4-queens solutions are mirror images, so keep the smaller one
+        let mut strict = examples::queens::create_queens_csp(4);
+        strict
+            .add_constraint(common::lex_less_than(
+                "lex",
+                columns.clone(),
+                mirrored.clone(),
+            ))
+            .unwrap();
+        let solutions = BacktrackingSolver::find_all_backtracking(&strict);
+        assert_eq!(solutions.len(), 1);
+        let rows: Vec<usize> = columns
+            .iter()
+            .map(|q| *solutions[0].get(q).unwrap())
+            .collect();
+        assert_eq!(rows, vec![1, 3, 0, 2]);
+
+        let mut loose = examples::queens::create_queens_csp(4);
+        loose
+            .add_constraint(common::lex_less_than_or_equal(
+                "lex",
+                columns.clone(),
+                mirrored,
+            ))
+            .unwrap();
+        assert_eq!(BacktrackingSolver::find_all_backtracking(&loose).len(), 1);
+
+        // a sequence is never strictly smaller than itself
+        let mut itself = examples::queens::create_queens_csp(4);
+        itself
+            .add_constraint(common::lex_less_than(
+                "lex",
+                columns.clone(),
+                columns.clone(),
+            ))
+            .unwrap();
+        assert!(BacktrackingSolver::find_all_backtracking(&itself).is_empty());
+    }
+
+    #[test]
+    fn test_promise_value_ordering() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Assignment, Constraint, Domain, Variable, VecDomain};
+        use csp_solver::solver::heuristics::{least_constraining_value, promise_value_ordering};
+
+        fn by_column<D: Domain<usize>>(
+            assignment: &Assignment<usize>,
+            csp: &Csp<usize, D>,
+        ) -> Option<Variable<usize>> {
+            (0..csp.num_variables())
+                .map(|col| Variable::new(&format!("Q{}", col)))
+                .find(|var| !assignment.is_assigned(var))
+        }
+
+        // X=0 and X=1 both remove two neighbor values, so LCV keeps domain
+        // order; X=0 empties most of Y (1 * 3 left) while X=1 spreads the
+        // loss (2 * 2 left), so the product tie-breaker prefers X=1
+        let x = Variable::new("X");
+        let y = Variable::new("Y");
+        let z = Variable::new("Z");
+        let mut csp = Csp::new();
+        csp.add_variable(x.clone(), VecDomain::new(vec![0, 1]))
+            .unwrap();
+        csp.add_variable(y.clone(), VecDomain::new(vec![0, 1, 2]))
+            .unwrap();
+        csp.add_variable(z.clone(), VecDomain::new(vec![0, 1, 2]))
+            .unwrap();
+        let (cx, cy) = (x.clone(), y.clone());
+        csp.add_constraint(Constraint::new(
+            "XY",
+            vec![x.clone(), y.clone()],
+            move |assignment| match (assignment.get(&cx), assignment.get(&cy)) {
+                (Some(0), Some(y)) => *y == 2,
+                (Some(1), Some(y)) => *y != 1,
+                _ => true,
+            },
+        ))
+        .unwrap();
+        let (cx, cz) = (x.clone(), z.clone());
+        csp.add_constraint(Constraint::new(
+            "XZ",
+            vec![x.clone(), z.clone()],
+            move |assignment| match (assignment.get(&cx), assignment.get(&cz)) {
+                (Some(1), Some(z)) => *z != 1,
+                _ => true,
+            },
+        ))
+        .unwrap();
+
+        let empty = Assignment::new();
+        let domain = csp.get_domain(&x).unwrap().clone();
+        assert_eq!(
+            least_constraining_value(&x, &domain, &empty, &csp),
+            vec![0, 1]
+        );
+        assert_eq!(
+            promise_value_ordering(&x, &domain, &empty, &csp),
+            vec![1, 0]
+        );
+
+        let queens = examples::queens::create_queens_csp(12);
+        let promise =
+            BacktrackingSolver::solve_full(&queens, by_column, promise_value_ordering, None, None);
+        assert!(queens.is_solution(promise.solution().unwrap()));
+
+        // Three copies of a tie: Xi=0 wipes out the two-valued Zi, Xi=1 takes
+        // one value each from Zi and the spare Yi. In name order the Zi come
+        // last, so LCV's X=0 choices fail only after every Yi was tried
+        let mut squeezed = Csp::new();
+        for i in 0..3 {
+            let x = Variable::new(&format!("X{}", i));
+            let y = Variable::new(&format!("Y{}", i));
+            let z = Variable::new(&format!("Z{}", i));
+            squeezed
+                .add_variable(x.clone(), VecDomain::new(vec![0, 1]))
+                .unwrap();
+            squeezed
+                .add_variable(y.clone(), VecDomain::new(0..4))
+                .unwrap();
+            squeezed
+                .add_variable(z.clone(), VecDomain::new(vec![0, 1]))
+                .unwrap();
+            let (cx, cz) = (x.clone(), z.clone());
+            squeezed
+                .add_constraint(Constraint::new(
+                    &format!("XZ{}", i),
+                    vec![x.clone(), z],
+                    move |assignment| match (assignment.get(&cx), assignment.get(&cz)) {
+                        (Some(0), Some(_)) => false,
+                        (Some(1), Some(z)) => *z != 0,
+                        _ => true,
+                    },
+                ))
+                .unwrap();
+            let (cx, cy) = (x.clone(), y.clone());
+            squeezed
+                .add_constraint(Constraint::new(
+                    &format!("XY{}", i),
+                    vec![x, y],
+                    move |assignment| match (assignment.get(&cx), assignment.get(&cy)) {
+                        (Some(1), Some(y)) => *y != 0,
+                        _ => true,
+                    },
+                ))
+                .unwrap();
+        }
+        let by_name = |assignment: &Assignment<usize>, csp: &Csp<usize, VecDomain<usize>>| {
+            csp.get_variables()
+                .into_iter()
+                .filter(|var| !assignment.is_assigned(var))
+                .min_by(|a, b| a.name.cmp(&b.name))
+        };
+        let lcv = BacktrackingSolver::solve_full(
+            &squeezed,
+            by_name,
+            least_constraining_value,
+            None,
+            None,
+        );
+        let promise =
+            BacktrackingSolver::solve_full(&squeezed, by_name, promise_value_ordering, None, None);
+        assert!(squeezed.is_solution(promise.solution().unwrap()));
+        assert_eq!(promise.stats().backtracks, 0);
+        assert!(lcv.stats().backtracks > 0);
+        assert!(promise.stats().nodes_explored * 10 < lcv.stats().nodes_explored);
+    }
+
+    #[test]
+    fn test_constraint_satisfaction_rate() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Assignment, Domain, Variable};
+        use std::cell::RefCell;
+
+        fn by_name<D: Domain<String>>(
+            assignment: &Assignment<String>,
+            csp: &Csp<String, D>,
+        ) -> Option<Variable<String>> {
+            csp.get_variables()
+                .into_iter()
+                .filter(|var| !assignment.is_assigned(var))
+                .min()
+        }
+
+        let australia = examples::australia::create_australia_csp();
+        let empty = Assignment::new();
+        assert_eq!(australia.constraint_satisfaction_rate(&empty), 1.0);
+
+        // report every node of a real search: it only descends below nodes
+        // whose checkable constraints all hold, and ends on a full solution
+        let reports = RefCell::new(Vec::new());
+        let solution = BacktrackingSolver::find_solution_with_progress(
+            &australia,
+            by_name,
+            domain_order,
+            |progress| reports.borrow_mut().push(progress),
+            1,
+        )
+        .unwrap();
+        let reports = reports.into_inner();
+        assert!(reports.iter().any(|report| report.satisfaction_rate < 1.0));
+        for pair in reports.windows(2) {
+            assert!(pair[1].current_depth <= pair[0].current_depth + 1);
+            if pair[1].current_depth > pair[0].current_depth {
+                assert_eq!(pair[0].satisfaction_rate, 1.0);
+                assert!(pair[1].checkable_constraints >= pair[0].checkable_constraints);
+            }
+        }
+        let last = reports.last().unwrap();
+        assert_eq!(last.current_depth, australia.num_variables());
+        assert_eq!(last.checkable_constraints, australia.num_constraints());
+        assert_eq!(last.satisfaction_rate, 1.0);
+        assert_eq!(
+            australia.constraint_satisfaction_rate(&solution),
+            last.satisfaction_rate
+        );
+
+        // every region red breaks every border constraint
+        let mut all_red = Assignment::new();
+        for var in australia.get_variables() {
+            all_red.assign(var, "red".to_string());
+        }
+        let progress = australia.progress(&all_red);
+        assert_eq!(progress.satisfied_constraints, 0);
+        assert_eq!(australia.constraint_satisfaction_rate(&all_red), 0.0);
+    }
+
+    #[test]
+    fn test_forward_checking_selectors() {
+        use csp_solver::csp::{Assignment, Domain, Variable, VecDomain};
+        use std::collections::HashMap;
+
+        let queens = examples::queens::create_queens_csp(8);
+        let first = ForwardCheckingSolver::solve_first_unassigned(&queens).unwrap();
+        assert!(queens.is_solution(&first));
+        let degree = ForwardCheckingSolver::solve_degree(&queens).unwrap();
+        assert!(queens.is_solution(&degree));
+
+        let australia = examples::australia::create_australia_csp();
+        assert!(australia.is_solution(&ForwardCheckingSolver::solve_degree(&australia).unwrap()));
+
+        // a custom selector: largest remaining domain first
+        let queens_6 = examples::queens::create_queens_csp(6);
+        let widest = |assignment: &Assignment<usize>,
+                      domains: &HashMap<Variable<usize>, VecDomain<usize>>| {
+            domains
+                .iter()
+                .filter(|(var, _)| !assignment.is_assigned(var))
+                .max_by(|(a, x), (b, y)| x.size().cmp(&y.size()).then_with(|| b.name.cmp(&a.name)))
+                .map(|(var, _)| var.clone())
+        };
+        let solution = ForwardCheckingSolver::solve_with_heuristics(&queens_6, widest).unwrap();
+        assert!(queens_6.is_solution(&solution));
+
+        let queens_3 = examples::queens::create_queens_csp(3);
+        assert!(ForwardCheckingSolver::solve_first_unassigned(&queens_3).is_none());
+    }
+
+    #[test]
+    fn test_with_assumption() {
+        use csp_solver::csp::Variable;
+
+        let queens = examples::queens::create_queens_csp(6);
+        let q0 = Variable::new("Q0");
+        let q1 = Variable::new("Q1");
+
+        let assumed = queens.with_assumption(&q0, 1);
+        assert_eq!(assumed.get_domain_values(&q0), Some(vec![1]));
+        assert_eq!(assumed.get_domain_size(&q1), queens.get_domain_size(&q1));
+        assert_eq!(assumed.num_constraints(), queens.num_constraints());
+        let names = |csp: &csp_solver::Csp<usize, _>| -> Vec<String> {
+            csp.get_constraints()
+                .iter()
+                .map(|c| c.name().to_string())
+                .collect()
+        };
+        assert_eq!(names(&assumed), names(&queens));
+        // the original is untouched
+        assert_eq!(queens.get_domain_size(&q0), Some(6));
+
+        let solution = BacktrackingSolver::mrv_search(&assumed).unwrap();
+        assert_eq!(solution.get(&q0), Some(&1));
+        assert!(queens.is_solution(&solution));
+
+        let both = queens.with_assumptions(&[(q0.clone(), 1), (q1.clone(), 3)]);
+        assert_eq!(both.get_domain_values(&q0), Some(vec![1]));
+        assert_eq!(both.get_domain_values(&q1), Some(vec![3]));
+        // the only 6-queens solution with Q0 = 1 has Q1 = 3
+        assert!(BacktrackingSolver::mrv_search(&both).is_some());
+
+        // adjacent columns on neighboring rows attack each other
+        let conflicting = queens.with_assumptions(&[(q0.clone(), 1), (q1, 2)]);
+        assert!(BacktrackingSolver::mrv_search(&conflicting).is_none());
+        assert_eq!(queens.with_assumption(&q0, 9).get_domain_size(&q0), Some(0));
+    }
+
+    #[test]
+    fn test_mac3_propagate() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Constraint, Domain, Variable, VecDomain};
+        use std::collections::HashMap;
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        // count every constraint check made while propagating
+        let checks = Arc::new(AtomicU64::new(0));
+        let queens = examples::queens::create_queens_csp(12);
+        let mut counted = Csp::<usize, VecDomain<usize>>::new();
+        for var in queens.get_variables() {
+            let domain = queens.get_domain(&var).unwrap().clone();
+            counted.add_variable(var, domain).unwrap();
+        }
+        for constraint in queens.get_constraints() {
+            let (inner, checks) = (constraint.clone(), checks.clone());
+            let wrapped = Constraint::new(
+                constraint.name(),
+                constraint.variables().to_vec(),
+                move |assignment| {
+                    checks.fetch_add(1, Ordering::Relaxed);
+                    inner.is_satisfied(assignment)
+                },
+            );
+            counted.add_constraint(wrapped).unwrap();
+        }
+
+        let solution = ArcConsistencySolver::solve(&queens).unwrap();
+        let mut domains: HashMap<Variable<usize>, VecDomain<usize>> = counted
+            .get_variables()
+            .into_iter()
+            .map(|var| (var.clone(), counted.get_domain(&var).unwrap().clone()))
+            .collect();
+        assert!(ArcConsistencySolver::ac3(&counted, &mut domains));
+
+        // replay the solution, propagating each assignment both ways from the same domains
+        let (mut full_checks, mut mac3_checks) = (0, 0);
+        for col in 0..12 {
+            let var = Variable::new(&format!("Q{}", col));
+            let value = *solution.get(&var).unwrap();
+
+            let mut full = domains.clone();
+            let single = full[&var].restrict_to(vec![value]);
+            full.insert(var.clone(), single);
+            checks.store(0, Ordering::Relaxed);
+            assert!(ArcConsistencySolver::ac3(&counted, &mut full));
+            full_checks += checks.load(Ordering::Relaxed);
+
+            checks.store(0, Ordering::Relaxed);
+            assert!(ArcConsistencySolver::mac3_propagate(
+                &var,
+                &value,
+                &counted,
+                &mut domains
+            ));
+            mac3_checks += checks.load(Ordering::Relaxed);
+
+            // both reach the same arc consistent closure
+            for (v, domain) in &domains {
+                assert_eq!(domain.to_sorted_vec(), full[v].to_sorted_vec());
+            }
+        }
+        assert!(mac3_checks < full_checks);
+    }
+
+    #[test]
+    fn test_four_color_theorem_on_random_planar_maps() {
+        use csp_solver::examples::map_coloring::MapColoringCsp;
+
+        let mut needs_four = 0;
+        for seed in 0..100 {
+            let (regions, borders) = MapColoringCsp::random_planar_map(16, 60, seed);
+            let regions: Vec<&str> = regions.iter().map(String::as_str).collect();
+            let borders: Vec<(&str, &str)> = borders
+                .iter()
+                .map(|(a, b)| (a.as_str(), b.as_str()))
+                .collect();
+
+            let four = MapColoringCsp::from_adjacency_list(&regions, &borders, 4);
+            let coloring = ForwardCheckingSolver::solve(&four)
+                .unwrap_or_else(|| panic!("planar map {} is not 4-colorable", seed));
+            assert!(four.is_solution(&coloring));
+
+            let three = MapColoringCsp::from_adjacency_list(&regions, &borders, 3);
+            match ForwardCheckingSolver::solve(&three) {
+                Some(coloring) => assert!(three.is_solution(&coloring)),
+                None => needs_four += 1,
+            }
+        }
+        // the maps are seeded, so the fraction needing a fourth color is fixed;
+        // with 60% of borders kept both outcomes occur
+        let fraction_needing_four = needs_four as f64 / 100.0;
+        assert_eq!(fraction_needing_four, 0.43);
+
+        // a full stacked triangulation contains K4, so it always needs 4 colors
+        let (regions, borders) = MapColoringCsp::random_planar_map(10, 100, 1);
+        assert_eq!(borders.len(), 3 * 10 - 6);
+        let regions: Vec<&str> = regions.iter().map(String::as_str).collect();
+        let borders: Vec<(&str, &str)> = borders
+            .iter()
+            .map(|(a, b)| (a.as_str(), b.as_str()))
+            .collect();
+        let three = MapColoringCsp::from_adjacency_list(&regions, &borders, 3);
+        assert!(ForwardCheckingSolver::solve(&three).is_none());
+    }
+
+    #[test]
+    fn test_value_symmetry_breaking() {
+        use csp_solver::examples::map_coloring::MapColoringCsp;
+
+        // every coloring of Australia uses all 3 colors (WA, NT and SA form a triangle),
+        // so each canonical solution stands for 3! colorings
+        let mut australia = examples::australia::create_australia_csp();
+        let all = BacktrackingSolver::find_all_backtracking(&australia).len();
+        assert!(australia.apply_value_symmetry_breaking());
+        let canonical = BacktrackingSolver::find_all_backtracking(&australia);
+        assert_eq!(all, 6 * canonical.len());
+        assert!(!canonical.is_empty());
+
+        // a 4-cycle colored with 3 colors: the 6 two-color and 12 three-color
+        // colorings collapse to 1 and 2 canonical ones
+        let regions = ["A", "B", "C", "D"];
+        let borders = [("A", "B"), ("B", "C"), ("C", "D"), ("A", "D")];
+        let mut cycle = MapColoringCsp::from_adjacency_list(&regions, &borders, 3);
+        assert_eq!(BacktrackingSolver::find_all_backtracking(&cycle).len(), 18);
+        assert!(cycle.apply_value_symmetry_breaking());
+        assert_eq!(BacktrackingSolver::find_all_backtracking(&cycle).len(), 3);
+
+        // queens values are rows, which are not interchangeable
+        let mut queens = examples::queens::create_queens_csp(4);
+        assert!(!queens.apply_value_symmetry_breaking());
+        assert_eq!(BacktrackingSolver::find_all_backtracking(&queens).len(), 2);
+    }
+
+    #[test]
+    fn test_solver_trait() {
+        use csp_solver::csp::HashSetDomain;
+        use csp_solver::solver::{BoxedSolver, FcCbjSolver, Solver};
+
+        let solvers: Vec<(&str, BoxedSolver<String, HashSetDomain<String>>)> = vec![
+            ("backtracking", BoxedSolver::new(BacktrackingSolver)),
+            ("forward checking", BoxedSolver::new(ForwardCheckingSolver)),
+            ("arc consistency", BoxedSolver::new(ArcConsistencySolver)),
+            ("fc-cbj", BoxedSolver::new(FcCbjSolver)),
+        ];
+
+        let australia = examples::australia::create_australia_csp();
+        for (name, solver) in &solvers {
+            let solution = solver
+                .solve(&australia)
+                .unwrap_or_else(|| panic!("{} found no solution", name));
+            assert!(
+                australia.is_solution(&solution),
+                "{} returned an invalid solution",
+                name
+            );
+        }
+
+        let portfolio = csp_solver::solver::PortfolioSolver::new()
+            .add_solver(BoxedSolver::new(ArcConsistencySolver))
+            .add_solver(FcCbjSolver);
+        assert!(australia.is_solution(&portfolio.solve(&australia).unwrap()));
+
+        // the trait can also be used through generics
+        fn solve_with<S: Solver<usize, csp_solver::csp::VecDomain<usize>>>(solver: S) -> bool {
+            let queens_3 = examples::queens::create_queens_csp(3);
+            solver.solve(&queens_3).is_none()
+        }
+        assert!(solve_with(BacktrackingSolver));
+        assert!(solve_with(ForwardCheckingSolver));
+    }
+
+    #[test]
+    fn test_possible_completions() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Assignment, Variable, VecDomain, common};
+
+        let (a, b, c, d) = (
+            Variable::new("A"),
+            Variable::new("B"),
+            Variable::new("C"),
+            Variable::new("D"),
+        );
+        let mut csp = Csp::<i32, VecDomain<i32>>::new();
+        for var in [&a, &b, &c, &d] {
+            csp.add_variable(var.clone(), VecDomain::new(0..4)).unwrap();
+        }
+        csp.add_constraint(common::diff("A-B", a.clone(), b.clone()))
+            .unwrap();
+        csp.add_constraint(common::diff("B-C", b.clone(), c.clone()))
+            .unwrap();
+        csp.add_constraint(common::diff("C-D", c.clone(), d.clone()))
+            .unwrap();
+
+        // weighted cost of the assigned values, never decreasing as more are assigned
+        let cost = |assignment: &Assignment<i32>| {
+            assignment
+                .iter()
+                .map(|(var, value)| match var.name.as_str() {
+                    "A" => 3.0 * *value as f64,
+                    "C" => 2.0 * *value as f64,
+                    _ => *value as f64,
+                })
+                .sum::<f64>()
+        };
+
+        let mut partial = Assignment::new();
+        partial.assign(b.clone(), 0);
+        let completions: Vec<(Assignment<i32>, f64)> =
+            Assignment::possible_completions(&partial, &csp, cost).collect();
+
+        // A, C in 1..4 and D != C: 3 * 3 * 3 completions
+        assert_eq!(completions.len(), 27);
+        for window in completions.windows(2) {
+            assert!(window[0].1 <= window[1].1);
+        }
+        for (completion, score) in &completions {
+            assert!(csp.is_solution(completion));
+            assert!(completion.is_compatible_with(&partial));
+            assert_eq!(*score, cost(completion));
+        }
+        // cheapest: A = 1, C = 1, D = 0
+        assert_eq!(completions[0].1, 3.0 + 2.0);
+
+        // lazily taking only the best few: A = 0, B = 1, C = 0, D = 1 costs 2
+        let best: Vec<f64> = Assignment::possible_completions(&Assignment::new(), &csp, cost)
+            .take(3)
+            .map(|(_, score)| score)
+            .collect();
+        assert_eq!(best, vec![2.0, 3.0, 3.0]);
+    }
+
+    #[test]
+    fn test_constraint_activity() {
+        use csp_solver::csp::{Constraint, ConstraintActivityTracker, Variable, common};
+
+        let (a, b, c) = (Variable::new("A"), Variable::new("B"), Variable::new("C"));
+        let mut constraints: Vec<Constraint<i32>> = vec![
+            common::diff("A-B", a.clone(), b.clone()),
+            common::diff("B-C", b.clone(), c.clone()),
+        ];
+
+        constraints[0].bump_activity();
+        constraints[0].bump_activity();
+        assert_eq!(constraints[0].activity(), 2.0);
+        assert_eq!(constraints[1].activity(), 0.0);
+
+        // clones share the counter
+        let shared = constraints[1].clone();
+        shared.bump_activity();
+        assert_eq!(constraints[1].activity(), 1.0);
+
+        Constraint::decay_all(&constraints, 0.5);
+        Constraint::decay_all(&constraints, 0.5);
+        assert_eq!(constraints[0].activity(), 0.5);
+        assert_eq!(constraints[1].activity(), 0.25);
+
+        let mut tracker = ConstraintActivityTracker::new(&mut constraints, 0.9);
+        assert!(tracker.bump("B-C"));
+        assert!(!tracker.bump("missing"));
+        assert_eq!(tracker.activity("B-C"), Some(1.25));
+        assert_eq!(tracker.most_active().unwrap().name(), "B-C");
+        for _ in 0..3 {
+            tracker.decay();
+        }
+        let expected = 1.25 * 0.9f64.powi(3);
+        assert!((tracker.activity("B-C").unwrap() - expected).abs() < 1e-12);
+        assert!((tracker.activity("A-B").unwrap() - 0.5 * 0.9f64.powi(3)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_queens_solution_counts() {
+        use csp_solver::examples::queens::{
+            QUEENS_SOLUTION_COUNTS, count_queens_solutions_exactly,
+            count_queens_solutions_up_to_symmetry,
+        };
+
+        for n in 1..=12 {
+            assert_eq!(
+                count_queens_solutions_exactly(n),
+                QUEENS_SOLUTION_COUNTS[n - 1],
+                "{}-queens",
+                n
+            );
+        }
+
+        // OEIS A002562
+        let distinct = [1, 0, 0, 1, 2, 1, 6, 12];
+        for (n, expected) in (1..=8).zip(distinct) {
+            assert_eq!(count_queens_solutions_up_to_symmetry(n), expected);
+        }
+    }
+
+    #[test]
+    fn test_arc_consistency_solution_counting() {
+        let queens = examples::queens::create_queens_csp(8);
+        assert_eq!(BacktrackingSolver::count_solutions(&queens), 92);
+        assert_eq!(ArcConsistencySolver::count_solutions(&queens), 92);
+        assert_eq!(
+            ArcConsistencySolver::count_solutions_bounded(&queens, 10),
+            10
+        );
+        assert_eq!(
+            BacktrackingSolver::count_solutions_bounded(&queens, 200),
+            92
+        );
+
+        // 3 queens has no solutions
+        assert_eq!(
+            ArcConsistencySolver::count_solutions(&examples::queens::create_queens_csp(3)),
+            0
+        );
+    }
+
+    #[test]
+    fn test_find_redundant_constraints() {
+        use csp_solver::csp::{Variable, VecDomain, common, csp::Csp};
+
+        let vars: Vec<Variable<usize>> = ["A", "B", "C"].iter().map(|n| Variable::new(n)).collect();
+        let mut chain = Csp::<usize, VecDomain<usize>>::new();
+        for var in &vars {
+            chain
+                .add_variable(var.clone(), VecDomain::new(0..4))
+                .unwrap();
+        }
+        chain
+            .add_constraint(common::strictly_increasing("A<B", vars[..2].to_vec()))
+            .unwrap();
+        chain
+            .add_constraint(common::strictly_increasing("B<C", vars[1..].to_vec()))
+            .unwrap();
+        assert!(chain.find_redundant_constraints().is_empty());
+
+        // both follow from A < B < C
+        chain
+            .add_constraint(common::strictly_increasing(
+                "A<C",
+                vec![vars[0].clone(), vars[2].clone()],
+            ))
+            .unwrap();
+        chain
+            .add_constraint(common::diff("A!=C", vars[0].clone(), vars[2].clone()))
+            .unwrap();
+        let redundant: Vec<&str> = chain
+            .find_redundant_constraints()
+            .iter()
+            .map(|constraint| constraint.name())
+            .collect();
+        assert_eq!(redundant, vec!["A<C", "A!=C"]);
+    }
+
+    #[test]
+    fn test_find_minimal_correction_set() {
+        use csp_solver::csp::{Variable, VecDomain, common, csp::Csp};
+
+        let australia = examples::australia::create_australia_csp();
+        assert!(australia.find_minimal_correction_set().is_empty());
+
+        // no value satisfies both X != Y and X = Y, and dropping the first works
+        let x = Variable::<usize>::new("X");
+        let y = Variable::<usize>::new("Y");
+        let mut overconstrained = Csp::<usize, VecDomain<usize>>::new();
+        overconstrained
+            .add_variable(x.clone(), VecDomain::new(0..3))
+            .unwrap();
+        overconstrained
+            .add_variable(y.clone(), VecDomain::new(0..3))
+            .unwrap();
+        overconstrained
+            .add_constraint(common::diff("X!=Y", x.clone(), y.clone()))
+            .unwrap();
+        overconstrained
+            .add_constraint(common::same("X=Y", x.clone(), y.clone()))
+            .unwrap();
+        overconstrained
+            .add_constraint(common::in_set("X<1", x.clone(), vec![0]))
+            .unwrap();
+        assert_eq!(overconstrained.find_minimal_correction_set(), vec!["X!=Y"]);
+
+        // 2-coloring K4 needs more than one border dropped
+        let regions = ["A", "B", "C", "D"];
+        let borders = [
+            ("A", "B"),
+            ("A", "C"),
+            ("A", "D"),
+            ("B", "C"),
+            ("B", "D"),
+            ("C", "D"),
+        ];
+        let k4 = examples::coloring::create_map_coloring_csp(&regions, &borders, 2);
+        // what is left is the 4-cycle A-B-D-C
+        assert_eq!(k4.find_minimal_correction_set(), vec!["A-D", "B-C"]);
+    }
+
+    #[test]
+    fn test_find_mus() {
+        use csp_solver::csp::{Variable, VecDomain, common, csp::Csp};
+
+        assert!(
+            examples::australia::create_australia_csp()
+                .find_mus()
+                .is_empty()
+        );
+
+        // a triangle can't be 2-colored, though any two of its borders can
+        let vars: Vec<Variable<usize>> = ["X", "Y", "Z", "U", "V"]
+            .iter()
+            .map(|name| Variable::new(name))
+            .collect();
+        let mut csp = Csp::<usize, VecDomain<usize>>::new();
+        for var in &vars {
+            csp.add_variable(var.clone(), VecDomain::new(0..2)).unwrap();
+        }
+        let (x, y, z, u, v) = (&vars[0], &vars[1], &vars[2], &vars[3], &vars[4]);
+        csp.add_constraint(common::diff("X!=Y", x.clone(), y.clone()))
+            .unwrap();
+        csp.add_constraint(common::diff("Y!=Z", y.clone(), z.clone()))
+            .unwrap();
+        csp.add_constraint(common::diff("X!=Z", x.clone(), z.clone()))
+            .unwrap();
+        csp.add_constraint(common::diff("X!=U", x.clone(), u.clone()))
+            .unwrap();
+        assert!(csp.constraint_conflict_graph()["X!=Y"].is_empty());
+        assert_eq!(csp.find_mus(), vec![vec!["X!=Y", "Y!=Z", "X!=Z"]]);
+
+        // a directly conflicting pair is a second, separate core
+        csp.add_constraint(common::diff("U!=V", u.clone(), v.clone()))
+            .unwrap();
+        csp.add_constraint(common::same("U=V", u.clone(), v.clone()))
+            .unwrap();
+        let conflicts = csp.constraint_conflict_graph();
+        assert_eq!(conflicts["U!=V"], vec!["U=V"]);
+        assert_eq!(conflicts["U=V"], vec!["U!=V"]);
+        assert!(conflicts["X!=U"].is_empty());
+        assert_eq!(
+            csp.find_mus(),
+            vec![vec!["X!=Y", "Y!=Z", "X!=Z"], vec!["U!=V", "U=V"]]
+        );
+    }
+
+    #[test]
+    fn test_sum_bounded() {
+        use csp_solver::csp::{Assignment, ConstraintState, Variable, VecDomain, common, csp::Csp};
+
+        let vars: Vec<Variable<u8>> = ["A", "B", "C", "D"]
+            .iter()
+            .map(|name| Variable::new(name))
+            .collect();
+        let build = |sum, values: std::ops::RangeInclusive<u8>| {
+            let mut csp = Csp::<u8, VecDomain<u8>>::new();
+            for var in &vars {
+                csp.add_variable(var.clone(), VecDomain::new(values.clone()))
+                    .unwrap();
+            }
+            csp.add_constraint(sum).unwrap();
+            csp
+        };
+
+        // outside a CSP the domain bounds are unknown, so only full assignments are judged
+        let bounded = common::sum_bounded("sum", vars.clone(), 30, 32);
+        let mut partial = Assignment::new();
+        partial.assign(vars[0].clone(), 2);
+        assert_eq!(bounded.eval_partial(&partial), ConstraintState::Unknown);
+
+        // 2 + 9 + 9 + 9 can't reach 30
+        let digits = build(bounded, 0..=9);
+        let bounded = &digits.get_constraints()[0];
+        assert_eq!(bounded.eval_partial(&partial), ConstraintState::Violated);
+        partial.assign(vars[0].clone(), 5);
+        assert_eq!(bounded.eval_partial(&partial), ConstraintState::Unknown);
+
+        // 5 + 8 + 8 + 8 can't either once the domains stop at 8
+        let narrow = build(common::sum_bounded("sum", vars.clone(), 30, 32), 0..=8);
+        assert_eq!(
+            narrow.get_constraints()[0].eval_partial(&partial),
+            ConstraintState::Violated
+        );
+
+        // the bounds are summed in i64, so values near i32::MAX don't overflow
+        let wide: Vec<Variable<i32>> = (0..3).map(|i| Variable::new(&format!("W{}", i))).collect();
+        let mut large = Csp::<i32, VecDomain<i32>>::new();
+        for var in &wide {
+            large
+                .add_variable(var.clone(), VecDomain::new(vec![i32::MAX - 1, i32::MAX]))
+                .unwrap();
+        }
+        large
+            .add_constraint(common::sum_bounded("sum", wide.clone(), 0, i32::MAX))
+            .unwrap();
+        let mut first = Assignment::new();
+        first.assign(wide[0].clone(), i32::MAX);
+        assert_eq!(
+            large.get_constraints()[0].eval_partial(&first),
+            ConstraintState::Violated
+        );
+
+        let exact = build(common::sum("sum", vars.clone(), 34), 0..=9);
+        let pruning = build(common::sum_bounded("sum", vars.clone(), 34, 34), 0..=9);
+
+        let (solution, exact_stats) = ForwardCheckingSolver::solve_with_stats(&exact);
+        assert!(exact.is_solution(&solution.unwrap()));
+        let (solution, pruning_stats) = ForwardCheckingSolver::solve_with_stats(&pruning);
+        assert!(exact.is_solution(&solution.unwrap()));
+        assert!(pruning_stats.nodes_explored < exact_stats.nodes_explored);
+    }
+
+    #[test]
+    fn test_decompose_and_solve() {
+        use csp_solver::csp::{Variable, common};
+
+        // a tree of borders, 2-colorable, with one leaf pinned
+        let regions = ["A", "B", "C", "D", "E", "F"];
+        let borders = [("A", "B"), ("A", "C"), ("B", "D"), ("B", "E"), ("C", "F")];
+        let mut tree = examples::coloring::create_map_coloring_csp(&regions, &borders, 2);
+        tree.add_constraint(common::in_set("F-fixed", Variable::new("F"), vec![0]))
+            .unwrap();
+
+        let decomposition = ArcConsistencySolver::tree_decomposition(&tree);
+        assert_eq!(decomposition.width(), 1);
+        assert_eq!(decomposition.bags.len(), regions.len());
+        assert_eq!(decomposition.edges.len(), regions.len() - 1);
+        let solution = ArcConsistencySolver::decompose_and_solve(&tree).unwrap();
+        assert!(tree.is_solution(&solution));
+        assert_eq!(solution.get(&Variable::new("A")), Some(&0));
+
+        let australia = examples::australia::create_australia_csp();
+        assert_eq!(
+            ArcConsistencySolver::tree_decomposition(&australia).width(),
+            2
+        );
+        let solution = ArcConsistencySolver::decompose_and_solve(&australia).unwrap();
+        assert!(australia.is_solution(&solution));
+
+        let triangle = examples::coloring::create_map_coloring_csp(
+            &["A", "B", "C"],
+            &[("A", "B"), ("B", "C"), ("A", "C")],
+            2,
+        );
+        assert!(ArcConsistencySolver::decompose_and_solve(&triangle).is_none());
+    }
+
+    #[test]
+    fn test_constraint_eval_partial() {
+        use csp_solver::csp::{Assignment, ConstraintState, Variable, common};
+
+        let vars: Vec<Variable<usize>> = ["A", "B", "C"].iter().map(|n| Variable::new(n)).collect();
+        let not_equal = common::diff("A!=B", vars[0].clone(), vars[1].clone());
+        let increasing = common::strictly_increasing("A<B<C", vars.clone());
+
+        let mut assignment = Assignment::new();
+        assert_eq!(
+            not_equal.eval_partial(&assignment),
+            ConstraintState::Unknown
+        );
+
+        assignment.assign(vars[0].clone(), 2);
+        assert_eq!(
+            not_equal.eval_partial(&assignment),
+            ConstraintState::Unknown
+        );
+        assert!(not_equal.is_satisfied(&assignment));
+
+        // strictly_increasing is checked on partial assignments, so a bad pair is final
+        assignment.assign(vars[1].clone(), 2);
+        assert_eq!(
+            not_equal.eval_partial(&assignment),
+            ConstraintState::Violated
+        );
+        assert_eq!(
+            increasing.eval_partial(&assignment),
+            ConstraintState::Violated
+        );
+        assert!(!not_equal.is_satisfied(&assignment));
+
+        assignment.assign(vars[1].clone(), 3);
+        assert_eq!(
+            not_equal.eval_partial(&assignment),
+            ConstraintState::Satisfied
+        );
+        assert_eq!(
+            increasing.eval_partial(&assignment),
+            ConstraintState::Unknown
+        );
+
+        assignment.assign(vars[2].clone(), 4);
+        assert_eq!(
+            increasing.eval_partial(&assignment),
+            ConstraintState::Satisfied
+        );
+    }
+
+    #[test]
+    fn test_eval_partial_early_exit() {
+        use csp_solver::csp::{
+            Assignment, ConstraintState, HashSetDomain, Variable, common, constraint::Constraint,
+            csp::Csp,
+        };
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let vars: Vec<Variable<i32>> = ["A", "B", "C", "D"]
+            .iter()
+            .map(|n| Variable::new(n))
+            .collect();
+        let (a, b, c, d) = (
+            vars[0].clone(),
+            vars[1].clone(),
+            vars[2].clone(),
+            vars[3].clone(),
+        );
+        let guarded = common::implies(
+            "A=0 -> B!=C",
+            a.clone(),
+            0,
+            common::diff("B!=C", b.clone(), c.clone()),
+        );
+        let bounded = common::sum_bounded("B+C<=6", vec![b.clone(), c.clone()], 0, 6);
+        // adding it to a CSP gives the sum its domain bounds
+        let mut digits = Csp::new();
+        for var in &vars {
+            digits
+                .add_variable(var.clone(), HashSetDomain::new(0..4))
+                .unwrap();
+        }
+        digits.add_constraint(bounded.clone()).unwrap();
+
+        // Both are decided before B and C are assigned
+        let mut assignment = Assignment::new();
+        assert_eq!(
+            bounded.eval_partial(&assignment),
+            ConstraintState::Satisfied
+        );
+        assert_eq!(guarded.eval_partial(&assignment), ConstraintState::Unknown);
+        assignment.assign(a.clone(), 0);
+        assert_eq!(guarded.eval_partial(&assignment), ConstraintState::Unknown);
+        assignment.assign(a.clone(), 1);
+        assert_eq!(
+            guarded.eval_partial(&assignment),
+            ConstraintState::Satisfied
+        );
+
+        // is_consistent stops at the first violated constraint
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let mut csp = Csp::new();
+        for var in &vars {
+            csp.add_variable(var.clone(), HashSetDomain::new(0..4))
+                .unwrap();
+        }
+        csp.add_constraint(guarded).unwrap();
+        csp.add_constraint(common::diff("A!=D", a.clone(), d.clone()))
+            .unwrap();
+        csp.add_constraint(Constraint::new("Counted", vec![a.clone()], move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            true
+        }))
+        .unwrap();
+
+        assignment.assign(d.clone(), 1);
+        assert!(!csp.is_consistent(&assignment));
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        assignment.assign(d.clone(), 2);
+        assert!(csp.is_consistent(&assignment));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_neighborhood_inverse_consistency() {
+        use csp_solver::csp::{Assignment, Domain};
+        use csp_solver::solver::preprocessing::{
+            neighborhood_inverse_consistency, singleton_arc_consistency,
+        };
+
+        // AC-3 only sees binary constraints, so spell out the all-different pairs
+        let mut sudoku = examples::sudoku::create_sample_sudoku_x();
+        sudoku.decompose_global_constraints();
+
+        let mut sac = sudoku.clone();
+        assert!(singleton_arc_consistency(&mut sac));
+        let mut nic = sudoku.clone();
+        assert!(neighborhood_inverse_consistency(&mut nic));
+
+        // NIC never keeps a value SAC removed here, and both settle every cell
+        let mut solution = Assignment::new();
+        for var in sudoku.get_variables() {
+            let nic_values = nic.get_domain(&var).unwrap();
+            let sac_values = sac.get_domain(&var).unwrap();
+            assert!(
+                nic_values
+                    .values()
+                    .iter()
+                    .all(|value| sac_values.contains(value))
+            );
+            assert_eq!(nic_values.size(), 1);
+            solution.assign(var.clone(), nic_values.values()[0]);
+        }
+        assert!(sudoku.is_solution(&solution));
+    }
+
+    #[test]
+    fn test_assignment_diff() {
+        use csp_solver::csp::{Assignment, Variable};
+
+        let [a, b, c, d]: [Variable<usize>; 4] = ["A", "B", "C", "D"].map(Variable::new);
+        let mut before = Assignment::new();
+        before.assign(a.clone(), 1);
+        before.assign(b.clone(), 2);
+        before.assign(c.clone(), 3);
+        assert!(before.diff(&before.clone()).is_empty());
+        assert!(before.changed_variables(&before).is_empty());
+
+        let mut after = before.clone();
+        after.assign(b.clone(), 5);
+        after.unassign(&c);
+        after.assign(d.clone(), 4);
+        assert_eq!(
+            before.diff(&after),
+            vec![
+                (b.clone(), Some(2), Some(5)),
+                (c.clone(), Some(3), None),
+                (d.clone(), None, Some(4)),
+            ]
+        );
+
+        let mut changed = after.changed_variables(&before);
+        changed.sort_by(|x, y| x.name.cmp(&y.name));
+        assert_eq!(changed, vec![&b, &c, &d]);
+    }
+
+    #[test]
+    fn test_dimacs_sat_encodings() {
+        use csp_solver::csp::{Variable, VecDomain, common, csp::Csp};
+
+        /// Parse DIMACS CNF and list every model by brute force
+        fn models(dimacs: &str) -> Vec<Vec<bool>> {
+            let mut lines = dimacs.lines().filter(|line| !line.starts_with('c'));
+            let header: Vec<usize> = lines.next().unwrap()[6..]
+                .split_whitespace()
+                .map(|n| n.parse().unwrap())
+                .collect();
+            let clauses: Vec<Vec<i64>> = lines
+                .map(|line| {
+                    let lits: Vec<i64> = line
+                        .split_whitespace()
+                        .map(|n| n.parse().unwrap())
+                        .collect();
+                    assert_eq!(lits.last(), Some(&0));
+                    lits[..lits.len() - 1].to_vec()
+                })
+                .collect();
+            assert_eq!(clauses.len(), header[1]);
+
+            (0..1u32 << header[0])
+                .map(|bits| {
+                    (0..header[0])
+                        .map(|i| bits >> i & 1 == 1)
+                        .collect::<Vec<_>>()
+                })
+                .filter(|model| {
+                    clauses.iter().all(|clause| {
+                        clause
+                            .iter()
+                            .any(|&lit| model[lit.unsigned_abs() as usize - 1] == (lit > 0))
+                    })
+                })
+                .collect()
+        }
+
+        let [a, b, c]: [Variable<bool>; 3] = ["A", "B", "C"].map(Variable::new);
+        let mut chain = Csp::<bool, VecDomain<bool>>::new();
+        for var in [&a, &b, &c] {
+            chain
+                .add_variable(var.clone(), VecDomain::new([false, true]))
+                .unwrap();
+        }
+        chain
+            .add_constraint(common::diff("A!=B", a.clone(), b.clone()))
+            .unwrap();
+        chain
+            .add_constraint(common::diff("B!=C", b.clone(), c.clone()))
+            .unwrap();
+
+        let dimacs = chain.to_dimacs_sat().unwrap();
+        assert!(dimacs.contains("p cnf 3 4\n1 2 0\n-1 -2 0\n"));
+        let mut expected: Vec<Vec<bool>> = BacktrackingSolver::find_all_backtracking(&chain)
+            .iter()
+            .map(|solution| [&a, &b, &c].map(|var| *solution.get(var).unwrap()).to_vec())
+            .collect();
+        expected.sort();
+        assert_eq!(models(&dimacs), expected);
+
+        // a 3-colored triangle has one literal per region and color
+        let triangle = examples::coloring::create_map_coloring_csp(
+            &["X", "Y", "Z"],
+            &[("X", "Y"), ("Y", "Z"), ("X", "Z")],
+            3,
+        );
+        let dimacs = triangle.to_dimacs_sat_with_support_encoding().unwrap();
+        assert!(dimacs.starts_with("c 1 X=0\n"));
+        let colorings: Vec<Vec<usize>> = models(&dimacs)
+            .iter()
+            .map(|model| {
+                (0..3)
+                    .map(|region| (0..3).find(|&color| model[region * 3 + color]).unwrap())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(colorings.len(), 6);
+        assert_eq!(
+            BacktrackingSolver::find_all_backtracking(&triangle).len(),
+            colorings.len()
+        );
+        assert!(colorings.iter().all(|colors| colors[0] != colors[1]
+            && colors[1] != colors[2]
+            && colors[0] != colors[2]));
+    }
+
+    #[test]
+    fn test_timetabling() {
+        use csp_solver::examples::scheduling::{
+            course_var, create_timetabling_csp, sample_timetabling_instance,
+        };
+        use csp_solver::solver::ForwardCheckingSolver;
+
+        let (courses, rooms, timeslots) = sample_timetabling_instance();
+        let timetable = create_timetabling_csp(&courses, &rooms, timeslots);
+        let solution = ForwardCheckingSolver::solve(&timetable).unwrap();
+        assert!(timetable.is_solution(&solution));
+
+        let slots: Vec<_> = courses
+            .iter()
+            .map(|c| *solution.get(&course_var(c)).unwrap())
+            .collect();
+        for (i, (course, slot)) in courses.iter().zip(&slots).enumerate() {
+            assert!(slot.timeslot < timeslots);
+            assert!(rooms[slot.room].capacity >= course.enrollment);
+            for (other, other_slot) in courses.iter().zip(&slots).skip(i + 1) {
+                assert_ne!(slot, other_slot);
+                if course.teacher == other.teacher || course.group == other.group {
+                    assert_ne!(slot.timeslot, other_slot.timeslot);
+                }
+            }
+        }
+
+        // five courses need the classroom or the hall, more than two timeslots hold
+        let squeezed = create_timetabling_csp(&courses, &rooms, 2);
+        assert!(ForwardCheckingSolver::solve(&squeezed).is_none());
+    }
+
+    #[test]
+    fn test_lazy_domain() {
+        use csp_solver::csp::{Domain, LazyDomain};
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::thread;
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        // squares below 50
+        let squares = LazyDomain::new(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            (0..50).filter(|n: &u32| n.isqrt().pow(2) == *n).collect()
+        });
+        let copy = squares.clone();
+        assert!(!squares.is_materialized());
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        // clones on other threads share the one generated domain
+        let sizes: Vec<usize> = (0..4)
+            .map(|_| {
+                let copy = copy.clone();
+                thread::spawn(move || copy.size())
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect();
+        assert_eq!(sizes, vec![8; 4]);
+
+        assert_eq!(squares.values(), vec![0, 1, 4, 9, 16, 25, 36, 49]);
+        assert_eq!(squares.values(), copy.values());
+        assert_eq!(squares.size(), 8);
+        assert!(copy.is_materialized());
+
+        let odd = squares.remove(&0).restrict_to([1, 9, 25, 49]);
+        assert_eq!(odd.values(), vec![1, 9, 25, 49]);
+        assert!(!odd.contains(&4));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_find_solution_with_proof() {
+        use csp_solver::csp::{Assignment, Domain, Variable, csp::Csp};
+        use csp_solver::solver::ProofTree;
+
+        fn by_name<D: Domain<usize>>(
+            assignment: &Assignment<usize>,
+            csp: &Csp<usize, D>,
+        ) -> Option<Variable<usize>> {
+            csp.get_variables()
+                .into_iter()
+                .filter(|var| !assignment.is_assigned(var))
+                .min_by(|a, b| a.name.cmp(&b.name))
+        }
+
+        // an odd cycle can't be 2-colored
+        let triangle = examples::coloring::create_map_coloring_csp(
+            &["A", "B", "C"],
+            &[("A", "B"), ("B", "C"), ("A", "C")],
+            2,
+        );
+        let (solution, proof) =
+            BacktrackingSolver::find_solution_with_proof(&triangle, by_name, domain_order);
+        assert!(solution.is_none());
+
+        // A=0, B=0 clashes at once and A=0, B=1 leaves no color for C;
+        // under A=1 the order flips, B=0 failing on C before B=1 clashes
+        let ProofTree::Branch(var, children) = &proof else {
+            panic!("expected a branch at the root, got {:?}", proof);
+        };
+        assert_eq!(var.name, "A");
+        assert_eq!(children.len(), 2);
+        let (first, value, culprits) = proof.contradictions()[0];
+        assert_eq!(
+            (first.name.as_str(), *value, culprits),
+            ("B", 0, &["A-B".to_string()][..])
+        );
+        let leaves: Vec<(&str, usize)> = proof
+            .contradictions()
+            .iter()
+            .map(|(var, value, _)| (var.name.as_str(), **value))
+            .collect();
+        assert_eq!(
+            leaves,
+            vec![("B", 0), ("C", 0), ("C", 1), ("C", 0), ("C", 1), ("B", 1)]
+        );
+
+        // with three colors the recorded path ends in a solution
+        let colorable = examples::coloring::create_map_coloring_csp(
+            &["A", "B", "C"],
+            &[("A", "B"), ("B", "C"), ("A", "C")],
+            3,
+        );
+        let (solution, proof) =
+            BacktrackingSolver::find_solution_with_proof(&colorable, by_name, domain_order);
+        assert!(colorable.is_solution(&solution.unwrap()));
+        assert_eq!(proof.contradictions().len(), 3);
+
+        // past the depth limit the search runs unrecorded
+        let (solution, shallow) = BacktrackingSolver::find_solution_with_proof_depth(
+            &colorable,
+            by_name,
+            domain_order,
+            1,
+        );
+        assert!(solution.is_some());
+        let ProofTree::Branch(_, children) = shallow else {
+            panic!("expected a branch at the root");
+        };
+        assert_eq!(children, vec![(0, ProofTree::Unrecorded)]);
+    }
+
+    #[test]
+    fn test_connected_components() {
+        use csp_solver::csp::csp::Csp;
+        use csp_solver::csp::{Variable, VecDomain, common};
+
+        // the components are the mainland and Tasmania
+        let australia = examples::australia::create_australia_csp();
+        let names: Vec<Vec<String>> = australia
+            .connected_components()
+            .iter()
+            .map(|component| component.iter().map(|var| var.name.clone()).collect())
+            .collect();
+        assert_eq!(
+            names,
+            vec![vec!["NSW", "NT", "Q", "SA", "V", "WA"], vec!["T"]]
+        );
+
+        let order: Vec<String> = australia
+            .component_order()
+            .into_iter()
+            .map(|var| var.name)
+            .collect();
+        assert_eq!(order, vec!["NSW", "NT", "Q", "SA", "V", "WA", "T"]);
+
+        // without constraints every variable is its own component
+        let islands = examples::coloring::create_map_coloring_csp(&["A", "B", "C"], &[], 3);
+        assert_eq!(islands.connected_components().len(), 3);
+
+        // a long chain is one component and does not overflow the stack
+        let mut chain: Csp<i32, VecDomain<i32>> = Csp::new();
+        let vars: Vec<Variable<i32>> = (0..100_000)
+            .map(|i| Variable::new(&format!("X{}", i)))
+            .collect();
+        for var in &vars {
+            chain
+                .add_variable(var.clone(), VecDomain::new(vec![0, 1]))
+                .unwrap();
+        }
+        for (i, pair) in vars.windows(2).enumerate() {
+            let name = format!("D{}", i);
+            chain
+                .add_constraint(common::diff(&name, pair[0].clone(), pair[1].clone()))
+                .unwrap();
+        }
+        let components = chain.connected_components();
+        assert_eq!(components.len(), 1);
+        assert_eq!(components[0].len(), vars.len());
+    }
+
+    #[test]
+    fn test_constraint_scope_complement() {
+        use csp_solver::csp::Variable;
+
+        let australia = examples::australia::create_australia_csp();
+        let border = &australia.get_constraints()[0];
+        let scope = border.scope_as_set();
+        let outside = border.scope_complement(&australia);
+        assert_eq!(outside.len(), 5);
+        assert!(outside.iter().all(|var| !scope.contains(var)));
+        assert_eq!(outside.len() + scope.len(), australia.num_variables());
+
+        let sa = Variable::new("SA");
+        let tasmania = Variable::new("T");
+        assert_eq!(
+            australia.constraints_not_involving(&sa).len()
+                + australia.get_constraints_for_variable(&sa).len(),
+            australia.num_constraints()
+        );
+        assert_eq!(
+            australia.constraints_not_involving(&tasmania).len(),
+            australia.num_constraints()
+        );
+    }
+
+    #[test]
+    fn test_benchmark_suite_csv() {
+        use csp_solver::examples::benchmark::{BenchmarkConfig, BenchmarkSuite, CSV_COLUMNS};
+        use std::time::Duration;
+
+        let config = BenchmarkConfig {
+            problems: vec!["australia".to_string(), "planar-map".to_string()],
+            repetitions: 2,
+            seeds: vec![4, 5],
+            time_limit: Duration::from_secs(5),
+        };
+        let path = std::env::temp_dir().join(format!("benchmark-{}.csv", std::process::id()));
+        let records = BenchmarkSuite::run_with_config(&path, &config).unwrap();
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // 3 problems (Australia and a map per seed), 7 algorithms, 2 repetitions
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(records.len(), 3 * 7 * 2);
+        assert_eq!(lines.len(), records.len() + 1);
+        assert_eq!(lines[0].split(',').collect::<Vec<_>>(), CSV_COLUMNS);
+        for line in &lines[1..] {
+            let fields: Vec<&str> = line.split(',').collect();
+            assert_eq!(fields.len(), CSV_COLUMNS.len());
+            assert_eq!(fields[3], "true", "{}", line);
+            assert!(fields[5].parse::<f64>().is_ok());
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_to_json_schema() {
+        use jsonschema::JSONSchema;
+        use serde_json::{Map, Value};
+
+        let australia = examples::australia::create_australia_csp();
+        let schema = australia.to_json_schema();
+        assert_eq!(
+            schema["properties"]["WA"]["enum"],
+            serde_json::json!(["blue", "green", "red"])
+        );
+        assert_eq!(schema["constraints"][0]["arity"], 2);
+        let validator = JSONSchema::compile(&schema).unwrap();
+
+        let solution = ArcConsistencySolver::solve(&australia).unwrap();
+        let mut instance: Map<String, Value> = solution
+            .iter()
+            .map(|(var, color)| (var.name.clone(), Value::from(color.as_str())))
+            .collect();
+        assert!(validator.is_valid(&Value::Object(instance.clone())));
+
+        instance.insert("WA".to_string(), Value::from("purple"));
+        assert!(!validator.is_valid(&Value::Object(instance.clone())));
+        instance.remove("WA");
+        assert!(!validator.is_valid(&Value::Object(instance)));
+    }
+
+    #[cfg(feature = "enum-domain")]
+    #[test]
+    fn test_enum_domain() {
+        use csp_solver::csp::{Domain, EnumDomain};
+        use csp_solver::examples::australia::{Color, create_australia_enum_csp};
+
+        // `bool` can't derive `EnumIter` (foreign trait on a foreign type), so use a local enum
+        let colors = EnumDomain::<Color>::new();
+        assert_eq!(colors.size(), 3);
+        assert_eq!(colors.values(), vec![Color::Red, Color::Green, Color::Blue]);
+        assert!(!colors.remove(&Color::Green).contains(&Color::Green));
+        assert_eq!(colors.to_debug_string(), "{red, green, blue}");
+
+        let australia = create_australia_enum_csp();
+        let solution = BacktrackingSolver::mrv_search(&australia).unwrap();
+        assert!(australia.is_solution(&solution));
+        assert_eq!(
+            BacktrackingSolver::find_all_backtracking(&australia).len(),
+            18
+        );
+    }
 }
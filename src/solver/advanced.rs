@@ -0,0 +1,287 @@
+use super::Solver;
+use super::arc_consistency::ArcConsistencySolver;
+use crate::csp::{Assignment, Domain, Variable, csp::Csp};
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+
+/// A no-good: variable-value pairs that no solution can contain all of
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClauseSet<T: Clone + Eq + Hash + Debug> {
+    pairs: Vec<(Variable<T>, T)>,
+}
+
+impl<T: Clone + Eq + Hash + Debug> ClauseSet<T> {
+    /// Create a no-good from the pairs that fail together
+    pub fn new(pairs: Vec<(Variable<T>, T)>) -> Self {
+        ClauseSet { pairs }
+    }
+
+    /// The variable-value pairs of the no-good
+    pub fn pairs(&self) -> &[(Variable<T>, T)] {
+        &self.pairs
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Returns true if the assignment contains every pair, so it can't be extended to a solution
+    pub fn excludes(&self, assignment: &Assignment<T>) -> bool {
+        self.pairs
+            .iter()
+            .all(|(var, value)| assignment.get(var) == Some(value))
+    }
+}
+
+/// Statistics describing a `MacNogoodSolver` search
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MacNogoodStats {
+    /// Number of values tried
+    pub nodes_explored: u64,
+    /// Number of no-goods learned
+    pub nogoods_recorded: u64,
+    /// Number of values skipped because a no-good excluded them
+    pub nogood_prunes: u64,
+}
+
+/// Maintains arc consistency on top of forward checking and learns no-goods:
+/// after AC-3 preprocessing, each assignment is forward checked and then made
+/// arc consistent again. Every failure is explained by the smallest set of
+/// current assignments that still fails under propagation, recorded as a
+/// no-good and checked before later assignments. Failures that don't involve
+/// the current variable jump straight back to a variable that they do
+pub struct MacNogoodSolver;
+
+impl MacNogoodSolver {
+    pub fn solve<T, D>(csp: &Csp<T, D>) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        Self::solve_with_stats(csp).0
+    }
+
+    /// Find a single solution and report node and no-good counts
+    pub fn solve_with_stats<T, D>(csp: &Csp<T, D>) -> (Option<Assignment<T>>, MacNogoodStats)
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        #[cfg(debug_assertions)]
+        csp.debug_validate();
+
+        let mut domains: HashMap<Variable<T>, D> = csp
+            .get_variables()
+            .into_iter()
+            .filter_map(|var| csp.get_domain(&var).map(|domain| (var, domain.clone())))
+            .collect();
+        if !ArcConsistencySolver::ac3(csp, &mut domains) {
+            return (None, MacNogoodStats::default());
+        }
+
+        let mut search = NogoodSearch {
+            csp,
+            base: domains.clone(),
+            nogoods: Vec::new(),
+            stats: MacNogoodStats::default(),
+        };
+        let mut assignment = Assignment::new();
+        let solution = search
+            .backtrack(&mut assignment, &mut domains)
+            .ok()
+            .map(|_| assignment);
+        (solution, search.stats)
+    }
+}
+
+/// State shared across a `MacNogoodSolver` search
+struct NogoodSearch<'a, T: Clone + Eq + Hash + Debug, D: Domain<T>> {
+    csp: &'a Csp<T, D>,
+    /// Domains after AC-3 preprocessing, which every explanation is checked against
+    base: HashMap<Variable<T>, D>,
+    nogoods: Vec<ClauseSet<T>>,
+    stats: MacNogoodStats,
+}
+
+impl<T, D> NogoodSearch<'_, T, D>
+where
+    T: Clone + Eq + Hash + Debug + Display,
+    D: Domain<T>,
+{
+    /// Extend the assignment to a solution, or return the assignments that
+    /// explain why it can't be
+    fn backtrack(
+        &mut self,
+        assignment: &mut Assignment<T>,
+        domains: &mut HashMap<Variable<T>, D>,
+    ) -> Result<(), Vec<(Variable<T>, T)>> {
+        if assignment.is_complete(self.csp.num_variables()) {
+            return Ok(());
+        }
+
+        // MRV, ties broken by name so the search order is reproducible
+        let Some(var) = domains
+            .iter()
+            .filter(|(var, _)| !assignment.is_assigned(var))
+            .min_by(|(a, x), (b, y)| x.size().cmp(&y.size()).then_with(|| a.name.cmp(&b.name)))
+            .map(|(var, _)| var.clone())
+        else {
+            return Ok(());
+        };
+
+        // Values pruned since preprocessing go last: they are only explained,
+        // which is needed once every remaining value has failed
+        let (mut values, pruned): (Vec<T>, Vec<T>) = self.base[&var]
+            .values()
+            .into_iter()
+            .partition(|value| domains[&var].contains(value));
+        values.extend(pruned);
+
+        let mut conflict: Vec<(Variable<T>, T)> = Vec::new();
+        for value in values {
+            assignment.assign(var.clone(), value.clone());
+
+            let explanation = if let Some(nogood) = self
+                .nogoods
+                .iter()
+                .find(|nogood| nogood.excludes(assignment))
+            {
+                self.stats.nogood_prunes += 1;
+                nogood.pairs().to_vec()
+            } else if !domains[&var].contains(&value) {
+                self.learn(assignment)
+            } else {
+                self.stats.nodes_explored += 1;
+                let saved_domains = domains.clone();
+                let outcome = if self.csp.is_consistent(assignment)
+                    && self.forward_check(&var, assignment, domains)
+                    && ArcConsistencySolver::maintain_arc_consistency(
+                        &var, &value, self.csp, domains,
+                    ) {
+                    self.backtrack(assignment, domains)
+                } else {
+                    Err(self.learn(assignment))
+                };
+                if outcome.is_ok() {
+                    return Ok(());
+                }
+                *domains = saved_domains;
+                outcome.unwrap_err()
+            };
+
+            assignment.unassign(&var);
+            if !explanation.iter().any(|(v, _)| v == &var) {
+                // the failure doesn't depend on this variable, so no other value can help
+                return Err(explanation);
+            }
+            for pair in explanation {
+                if pair.0 != var && !conflict.contains(&pair) {
+                    conflict.push(pair);
+                }
+            }
+        }
+
+        self.record(conflict.clone());
+        Err(conflict)
+    }
+
+    /// Prune the domains of variables sharing a constraint with `var`
+    fn forward_check(
+        &self,
+        var: &Variable<T>,
+        assignment: &Assignment<T>,
+        domains: &mut HashMap<Variable<T>, D>,
+    ) -> bool {
+        for constraint in self.csp.get_constraints_for_variable(var) {
+            for other in constraint.variables() {
+                if assignment.is_assigned(other) {
+                    continue;
+                }
+                let domain = &domains[other];
+                let supported: Vec<T> = domain
+                    .values()
+                    .into_iter()
+                    .filter(|value| {
+                        let mut extended = assignment.clone();
+                        extended.assign(other.clone(), value.clone());
+                        constraint.is_satisfied(&extended)
+                    })
+                    .collect();
+                if supported.is_empty() {
+                    return false;
+                }
+                let pruned = domain.restrict_to(supported);
+                domains.insert(other.clone(), pruned);
+            }
+        }
+        true
+    }
+
+    /// Shrink the failed assignment to a minimal subset that still fails
+    /// under propagation, and record it
+    fn learn(&mut self, assignment: &Assignment<T>) -> Vec<(Variable<T>, T)> {
+        let mut pairs: Vec<(Variable<T>, T)> = assignment
+            .iter()
+            .map(|(var, value)| (var.clone(), value.clone()))
+            .collect();
+        pairs.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name));
+
+        // the search proved the whole assignment fails, which is always a no-good
+        if self.fails(&pairs) {
+            let mut position = 0;
+            while position < pairs.len() {
+                let dropped = pairs.remove(position);
+                if !self.fails(&pairs) {
+                    pairs.insert(position, dropped);
+                    position += 1;
+                }
+            }
+        }
+
+        self.record(pairs.clone());
+        pairs
+    }
+
+    fn record(&mut self, pairs: Vec<(Variable<T>, T)>) {
+        let nogood = ClauseSet::new(pairs);
+        if !self.nogoods.contains(&nogood) {
+            self.stats.nogoods_recorded += 1;
+            self.nogoods.push(nogood);
+        }
+    }
+
+    /// Whether fixing the pairs and propagating from the preprocessed domains fails
+    fn fails(&self, pairs: &[(Variable<T>, T)]) -> bool {
+        let mut assignment = Assignment::new();
+        let mut domains = self.base.clone();
+        for (var, value) in pairs {
+            if !domains[var].contains(value) {
+                return true;
+            }
+            assignment.assign(var.clone(), value.clone());
+            let fixed = domains[var].restrict_to(vec![value.clone()]);
+            domains.insert(var.clone(), fixed);
+        }
+
+        !self.csp.is_consistent(&assignment)
+            || !pairs
+                .iter()
+                .all(|(var, _)| self.forward_check(var, &assignment, &mut domains))
+            || !ArcConsistencySolver::ac3(self.csp, &mut domains)
+    }
+}
+
+impl<T, D> Solver<T, D> for MacNogoodSolver
+where
+    T: Clone + Eq + Hash + Debug + Display,
+    D: Domain<T>,
+{
+    fn solve(&self, csp: &Csp<T, D>) -> Option<Assignment<T>> {
+        MacNogoodSolver::solve(csp)
+    }
+}
@@ -0,0 +1,307 @@
+use super::utils::{SeededRng, first_unassigned};
+use super::{ArcConsistencySolver, BacktrackingSolver};
+use crate::csp::{Assignment, Constraint, Domain, Variable, csp::Csp};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+
+/// Largest solution count `find_redundant_constraints` will compare; past it
+/// the counts can no longer show that a constraint is redundant
+pub const REDUNDANCY_SAMPLE_LIMIT: u64 = 10_000;
+
+impl<T, D> Csp<T, D>
+where
+    T: Clone + Eq + Hash + Debug + Display,
+    D: Domain<T>,
+{
+    /// Approximate the backbone (the variable-value pairs shared by every
+    /// solution) from `sample_size` solutions found with shuffled value orders.
+    /// The result is a superset of the true backbone; empty if there is no solution
+    pub fn find_backbone_subset(&self, sample_size: usize, seed: u64) -> HashMap<Variable<T>, T> {
+        let rng = RefCell::new(SeededRng::new(seed));
+        let shuffled = |_var: &Variable<T>, domain: &D, _: &Assignment<T>, _: &Csp<T, D>| {
+            let mut values = domain.values();
+            rng.borrow_mut().shuffle(&mut values);
+            values
+        };
+
+        let mut backbone: Option<HashMap<Variable<T>, T>> = None;
+        for _ in 0..sample_size.max(1) {
+            let Some(solution) =
+                BacktrackingSolver::find_solution(self, first_unassigned, shuffled)
+            else {
+                return HashMap::new();
+            };
+            match backbone.as_mut() {
+                None => backbone = Some(solution.get_assignments()),
+                Some(common) => common.retain(|var, value| solution.get(var) == Some(value)),
+            }
+        }
+        backbone.unwrap_or_default()
+    }
+
+    /// Check a candidate backbone against every solution by exhaustive search,
+    /// so only suitable for small problems. Unsatisfiable problems return false
+    pub fn verify_backbone(&self, candidate: HashMap<Variable<T>, T>) -> bool {
+        let solutions = BacktrackingSolver::find_all_backtracking(self);
+        !solutions.is_empty()
+            && solutions.iter().all(|solution| {
+                candidate
+                    .iter()
+                    .all(|(var, value)| solution.get(var) == Some(value))
+            })
+    }
+
+    /// Find the constraints whose removal leaves the solution set unchanged.
+    /// Each constraint is judged with all the others in place, so two copies of
+    /// the same constraint are both reported. Counting is exponential, and
+    /// nothing is reported if the problem has `REDUNDANCY_SAMPLE_LIMIT` or more solutions
+    pub fn find_redundant_constraints(&self) -> Vec<&Constraint<T>> {
+        let count = BacktrackingSolver::count_solutions_bounded(self, REDUNDANCY_SAMPLE_LIMIT);
+        if count >= REDUNDANCY_SAMPLE_LIMIT {
+            return Vec::new();
+        }
+
+        // dropping a constraint can only add solutions, so one extra is enough to tell
+        (0..self.num_constraints())
+            .filter(|&index| {
+                let relaxed = self.without_constraints(&[index]);
+                BacktrackingSolver::count_solutions_bounded(&relaxed, count + 1) == count
+            })
+            .map(|index| &self.get_constraints()[index])
+            .collect()
+    }
+
+    /// Find a minimal correction set: the names of constraints whose removal
+    /// makes an infeasible problem satisfiable, empty if it already is.
+    /// Single constraints are tried first; otherwise constraints are dropped in
+    /// order until the problem is feasible, then each is put back if it can be.
+    /// The result is minimal (no constraint can be kept) but not necessarily smallest
+    pub fn find_minimal_correction_set(&self) -> Vec<String> {
+        let feasible = |removed: &[usize]| {
+            ArcConsistencySolver::solve(&self.without_constraints(removed)).is_some()
+        };
+        if feasible(&[]) {
+            return Vec::new();
+        }
+
+        let mut removed = match (0..self.num_constraints()).find(|&index| feasible(&[index])) {
+            Some(index) => vec![index],
+            None => {
+                let mut removed = Vec::new();
+                for index in 0..self.num_constraints() {
+                    removed.push(index);
+                    if feasible(&removed) {
+                        break;
+                    }
+                }
+                let mut position = 0;
+                while position < removed.len() {
+                    let restored = removed.remove(position);
+                    if !feasible(&removed) {
+                        removed.insert(position, restored);
+                        position += 1;
+                    }
+                }
+                removed
+            }
+        };
+
+        removed.sort_unstable();
+        removed
+            .into_iter()
+            .map(|index| self.get_constraints()[index].name().to_string())
+            .collect()
+    }
+
+    /// Map each binary constraint's name to the binary constraints it shares a
+    /// variable with but can never be satisfied together with. Every binary
+    /// constraint is a key, with an empty list if it conflicts with nothing
+    pub fn constraint_conflict_graph(&self) -> HashMap<String, Vec<String>> {
+        let binary: Vec<&Constraint<T>> = self
+            .get_constraints()
+            .iter()
+            .filter(|constraint| constraint.variables().len() == 2)
+            .collect();
+
+        let mut graph: HashMap<String, Vec<String>> = binary
+            .iter()
+            .map(|constraint| (constraint.name().to_string(), Vec::new()))
+            .collect();
+        for (i, first) in binary.iter().enumerate() {
+            for second in &binary[i + 1..] {
+                let shares_variable = first.variables().iter().any(|var| second.involves(var));
+                if shares_variable && !self.jointly_satisfiable(&[first, second]) {
+                    let (a, b) = (first.name().to_string(), second.name().to_string());
+                    graph.get_mut(&a).unwrap().push(b.clone());
+                    graph.get_mut(&b).unwrap().push(a);
+                }
+            }
+        }
+        for neighbors in graph.values_mut() {
+            neighbors.sort();
+        }
+        graph
+    }
+
+    /// Find every minimal unsatisfiable subset of the constraints, as lists of
+    /// names in constraint order; empty if the problem is satisfiable.
+    /// Conflicting pairs from `constraint_conflict_graph` seed the search. Then,
+    /// for each minimal set of constraints hitting every subset found so far,
+    /// the rest of the constraints (the grow step) are checked; if they are
+    /// still unsatisfiable, constraints are dropped one at a time while that
+    /// holds (the shrink step), giving a new subset. Exponential in the worst case
+    pub fn find_mus(&self) -> Vec<Vec<String>> {
+        let n = self.num_constraints();
+        let unsatisfiable = |kept: &[usize]| {
+            let removed: Vec<usize> = (0..n).filter(|index| !kept.contains(index)).collect();
+            ArcConsistencySolver::solve(&self.without_constraints(&removed)).is_none()
+        };
+        let all: Vec<usize> = (0..n).collect();
+        if !unsatisfiable(&all) {
+            return Vec::new();
+        }
+
+        let mut found: Vec<Vec<usize>> = all
+            .iter()
+            .filter(|&&index| unsatisfiable(&[index]))
+            .map(|&index| vec![index])
+            .collect();
+        let conflicts = self.constraint_conflict_graph();
+        for (i, first) in self.get_constraints().iter().enumerate() {
+            for (j, second) in self.get_constraints().iter().enumerate().skip(i + 1) {
+                let conflicting = conflicts
+                    .get(first.name())
+                    .is_some_and(|neighbors| neighbors.iter().any(|name| name == second.name()));
+                let pair = vec![i, j];
+                if conflicting
+                    && !found
+                        .iter()
+                        .any(|mus| mus.iter().all(|index| pair.contains(index)))
+                    && unsatisfiable(&pair)
+                {
+                    found.push(pair);
+                }
+            }
+        }
+
+        'search: loop {
+            for hitting in Self::minimal_hitting_sets(&found) {
+                let mut seed: Vec<usize> = all
+                    .iter()
+                    .copied()
+                    .filter(|index| !hitting.contains(index))
+                    .collect();
+                if !unsatisfiable(&seed) {
+                    continue;
+                }
+
+                let mut position = 0;
+                while position < seed.len() {
+                    let dropped = seed.remove(position);
+                    if !unsatisfiable(&seed) {
+                        seed.insert(position, dropped);
+                        position += 1;
+                    }
+                }
+                found.push(seed);
+                continue 'search;
+            }
+            break;
+        }
+
+        found.sort();
+        found
+            .into_iter()
+            .map(|mus| {
+                mus.into_iter()
+                    .map(|index| self.get_constraints()[index].name().to_string())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The minimal sets of indices sharing at least one index with every set in `sets`
+    fn minimal_hitting_sets(sets: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        let mut hitting: Vec<Vec<usize>> = vec![Vec::new()];
+        for set in sets {
+            let mut extended = Vec::new();
+            for partial in &hitting {
+                if partial.iter().any(|index| set.contains(index)) {
+                    extended.push(partial.clone());
+                    continue;
+                }
+                for &index in set {
+                    let mut candidate = partial.clone();
+                    candidate.push(index);
+                    candidate.sort_unstable();
+                    extended.push(candidate);
+                }
+            }
+            extended.sort();
+            extended.dedup();
+            hitting = extended
+                .iter()
+                .filter(|candidate| {
+                    !extended.iter().any(|other| {
+                        other.len() < candidate.len()
+                            && other.iter().all(|index| candidate.contains(index))
+                    })
+                })
+                .cloned()
+                .collect();
+        }
+        hitting
+    }
+
+    /// Whether some assignment of the constraints' variables satisfies all of them
+    fn jointly_satisfiable(&self, constraints: &[&Constraint<T>]) -> bool {
+        let mut variables: Vec<&Variable<T>> = Vec::new();
+        for constraint in constraints {
+            for var in constraint.variables() {
+                if !variables.contains(&var) {
+                    variables.push(var);
+                }
+            }
+        }
+
+        fn extend<T: Clone + Eq + Hash + Debug, D: Domain<T>>(
+            csp: &Csp<T, D>,
+            variables: &[&Variable<T>],
+            assignment: &mut Assignment<T>,
+            constraints: &[&Constraint<T>],
+        ) -> bool {
+            let Some((var, rest)) = variables.split_first() else {
+                return constraints
+                    .iter()
+                    .all(|constraint| constraint.is_satisfied(assignment));
+            };
+            for value in csp.get_domain_values(var).unwrap_or_default() {
+                assignment.assign((*var).clone(), value);
+                if extend(csp, rest, assignment, constraints) {
+                    return true;
+                }
+            }
+            assignment.unassign(var);
+            false
+        }
+
+        extend(self, &variables, &mut Assignment::new(), constraints)
+    }
+
+    /// A copy of the problem with the constraints at the `removed` indices left out
+    fn without_constraints(&self, removed: &[usize]) -> Csp<T, D> {
+        let mut relaxed = Csp::new();
+        for var in self.get_variables() {
+            let domain = self.get_domain(&var).unwrap().clone();
+            relaxed.add_variable(var, domain).unwrap();
+        }
+        for (i, constraint) in self.get_constraints().iter().enumerate() {
+            if !removed.contains(&i) {
+                relaxed.add_constraint(constraint.clone()).unwrap();
+            }
+        }
+        relaxed
+    }
+}
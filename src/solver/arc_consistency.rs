@@ -1,8 +1,40 @@
-use crate::csp::{Assignment, Domain, Variable, csp::Csp};
+use super::Solver;
+use super::backtracking::BacktrackingSolver;
+use super::preprocessing::has_support;
+use crate::csp::{Assignment, Constraint, Domain, DomainsSnapshot, Variable, csp::Csp};
 use std::collections::{HashMap, VecDeque};
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
 
+/// An arc that wiped out a domain: the emptied variable, the other variable of
+/// the arc, the constraint on the arc, and the last value removed
+pub type Inconsistency<T> = (Variable<T>, Variable<T>, Constraint<T>, T);
+
+/// Borrowed form of `Inconsistency` produced while running AC-3
+type ArcConflict<'a, T> = (Variable<T>, Variable<T>, &'a Constraint<T>, T);
+
+/// A tree decomposition of the constraint graph: every constraint's variables
+/// share a bag, and the bags holding any one variable form a connected subtree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeDecomposition<T> {
+    /// The variables of each bag
+    pub bags: Vec<Vec<Variable<T>>>,
+    /// (parent, child) bag indices; a disconnected problem gives a forest
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl<T> TreeDecomposition<T> {
+    /// Size of the largest bag minus one, so 1 for a tree-structured problem
+    pub fn width(&self) -> usize {
+        self.bags
+            .iter()
+            .map(|bag| bag.len())
+            .max()
+            .unwrap_or(0)
+            .saturating_sub(1)
+    }
+}
+
 pub struct ArcConsistencySolver;
 
 impl ArcConsistencySolver {
@@ -11,26 +43,283 @@ impl ArcConsistencySolver {
         T: Clone + Eq + Hash + Debug + Display,
         D: Domain<T>,
     {
+        Self::search_with_callback(csp, &mut |_, _| {})
+    }
+
+    /// Count all solutions, backtracking over the domains left after AC-3
+    pub fn count_solutions<T, D>(csp: &Csp<T, D>) -> u64
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        Self::count_solutions_bounded(csp, u64::MAX)
+    }
+
+    /// Count solutions like `count_solutions`, stopping once `max` have been found
+    pub fn count_solutions_bounded<T, D>(csp: &Csp<T, D>, max: u64) -> u64
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        #[cfg(debug_assertions)]
+        csp.debug_validate();
+
         let mut domains: HashMap<Variable<T>, D> = csp
             .get_variables()
             .into_iter()
             .filter_map(|var| csp.get_domain(&var).map(|domain| (var, domain.clone())))
             .collect();
 
-        // apply ac-3 preprocessing
         if !Self::ac3(csp, &mut domains) {
-            return None; // inconsistent
+            return 0;
+        }
+
+        // count over a copy whose starting domains are the filtered ones
+        let filtered = csp.with_domains(domains);
+
+        BacktrackingSolver::count_solutions_bounded(&filtered, max)
+    }
+
+    /// Run AC-3 and then search, calling `callback` at every search node with
+    /// the current assignment, the arc-consistent domains and, as a diagnostic
+    /// of how much is left to decide, the total remaining entropy in bits
+    pub fn solve_with_callback<T, D, F>(csp: &Csp<T, D>, mut callback: F) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+        F: FnMut(&Assignment<T>, &HashMap<Variable<T>, D>, f64),
+    {
+        Self::search_with_callback(csp, &mut |assignment, domains| {
+            callback(
+                assignment,
+                domains,
+                csp.total_remaining_entropy(assignment, domains),
+            )
+        })
+    }
+
+    fn search_with_callback<T, D, F>(csp: &Csp<T, D>, callback: &mut F) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+        F: FnMut(&Assignment<T>, &HashMap<Variable<T>, D>),
+    {
+        #[cfg(debug_assertions)]
+        csp.debug_validate();
+
+        let mut domains: HashMap<Variable<T>, D> = csp
+            .get_variables()
+            .into_iter()
+            .filter_map(|var| csp.get_domain(&var).map(|domain| (var, domain.clone())))
+            .collect();
+
+        if !Self::ac3(csp, &mut domains) {
+            return None;
+        }
+
+        let mut assignment = Assignment::new();
+        if Self::backtrack_ac(&mut assignment, csp, &mut domains, false, callback) {
+            Some(assignment)
+        } else {
+            None
+        }
+    }
+
+    /// Solve with generalized arc consistency, which also propagates n-ary constraints
+    pub fn solve_gac<T, D>(csp: &Csp<T, D>) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        #[cfg(debug_assertions)]
+        csp.debug_validate();
+
+        let mut domains: HashMap<Variable<T>, D> = csp
+            .get_variables()
+            .into_iter()
+            .filter_map(|var| csp.get_domain(&var).map(|domain| (var, domain.clone())))
+            .collect();
+
+        if !Self::gac3(csp, &mut domains) {
+            return None;
         }
 
         let mut assignment = Assignment::new();
-        if Self::backtrack_ac(&mut assignment, csp, &mut domains) {
+        if Self::backtrack_ac(&mut assignment, csp, &mut domains, true, &mut |_, _| {}) {
             Some(assignment)
         } else {
             None
         }
     }
 
-    fn ac3<T, D>(csp: &Csp<T, D>, domains: &mut HashMap<Variable<T>, D>) -> bool
+    /// Run AC-3 on the initial domains and explain the first wipe-out, if any
+    pub fn find_inconsistency<T, D>(csp: &Csp<T, D>) -> Option<Inconsistency<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        let mut domains: HashMap<Variable<T>, D> = csp
+            .get_variables()
+            .into_iter()
+            .filter_map(|var| csp.get_domain(&var).map(|domain| (var, domain.clone())))
+            .collect();
+
+        Self::ac3_explained(csp, &mut domains, &mut DomainsSnapshot::new())
+            .map(|(xi, xj, constraint, value)| (xi, xj, constraint.clone(), value))
+    }
+
+    /// Build a tree decomposition by eliminating variables in min-degree order
+    /// (ties broken by name): each eliminated variable forms a bag with its
+    /// remaining neighbors, which are then joined, and the bag hangs off the bag
+    /// of the next of those neighbors to be eliminated
+    pub fn tree_decomposition<T, D>(csp: &Csp<T, D>) -> TreeDecomposition<T>
+    where
+        T: Clone + Eq + Hash + Debug,
+        D: Domain<T>,
+    {
+        let mut neighbors: HashMap<Variable<T>, Vec<Variable<T>>> = csp
+            .get_variables()
+            .into_iter()
+            .map(|var| (var, Vec::new()))
+            .collect();
+        let connect = |neighbors: &mut HashMap<Variable<T>, Vec<Variable<T>>>,
+                       a: &Variable<T>,
+                       b: &Variable<T>| {
+            if a != b && !neighbors[a].contains(b) {
+                neighbors.get_mut(a).unwrap().push(b.clone());
+                neighbors.get_mut(b).unwrap().push(a.clone());
+            }
+        };
+        for constraint in csp.get_constraints() {
+            for a in constraint.variables() {
+                for b in constraint.variables() {
+                    connect(&mut neighbors, a, b);
+                }
+            }
+        }
+
+        let mut bags = Vec::new();
+        let mut eliminated_in: HashMap<Variable<T>, usize> = HashMap::new();
+        while let Some(var) = neighbors
+            .iter()
+            .min_by(|(a, x), (b, y)| x.len().cmp(&y.len()).then_with(|| a.name.cmp(&b.name)))
+            .map(|(var, _)| var.clone())
+        {
+            let mut rest = neighbors.remove(&var).unwrap();
+            rest.sort_by(|a, b| a.name.cmp(&b.name));
+            for other in &rest {
+                neighbors.get_mut(other).unwrap().retain(|v| v != &var);
+            }
+            for a in &rest {
+                for b in &rest {
+                    connect(&mut neighbors, a, b);
+                }
+            }
+
+            eliminated_in.insert(var.clone(), bags.len());
+            let mut bag = vec![var];
+            bag.extend(rest);
+            bags.push(bag);
+        }
+
+        // the rest of each bag is eliminated later, so the earliest of them holds them all
+        let edges = bags
+            .iter()
+            .enumerate()
+            .filter_map(|(child, bag)| {
+                bag[1..]
+                    .iter()
+                    .map(|var| eliminated_in[var])
+                    .min()
+                    .map(|parent| (parent, child))
+            })
+            .collect();
+
+        TreeDecomposition { bags, edges }
+    }
+
+    /// Solve by dynamic programming over a tree decomposition: after AC-3, every
+    /// bag's sub-problem is solved outright, bags keep only the solutions that
+    /// agree with some solution of each child (leaves first), and a solution is
+    /// then read off from the roots down without backtracking. Polynomial for
+    /// bounded treewidth; returns None if a bag has no solution left
+    pub fn decompose_and_solve<T, D>(csp: &Csp<T, D>) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        #[cfg(debug_assertions)]
+        csp.debug_validate();
+
+        let mut domains: HashMap<Variable<T>, D> = csp
+            .get_variables()
+            .into_iter()
+            .filter_map(|var| csp.get_domain(&var).map(|domain| (var, domain.clone())))
+            .collect();
+        if !Self::ac3(csp, &mut domains) {
+            return None;
+        }
+        let filtered = csp.with_domains(domains);
+
+        let decomposition = Self::tree_decomposition(&filtered);
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); decomposition.bags.len()];
+        let mut has_parent = vec![false; decomposition.bags.len()];
+        for &(parent, child) in &decomposition.edges {
+            children[parent].push(child);
+            has_parent[child] = true;
+        }
+
+        // children are eliminated before their parents, so index order is bottom-up
+        let mut tables: Vec<Vec<Assignment<T>>> = Vec::new();
+        for (index, bag) in decomposition.bags.iter().enumerate() {
+            let sub = filtered.induce_subproblem(bag);
+            let table: Vec<Assignment<T>> = BacktrackingSolver::find_all_backtracking(&sub)
+                .into_iter()
+                .filter(|row| {
+                    children[index].iter().all(|&child| {
+                        tables[child]
+                            .iter()
+                            .any(|child_row| child_row.is_compatible_with(row))
+                    })
+                })
+                .collect();
+            if table.is_empty() {
+                return None;
+            }
+            tables.push(table);
+        }
+
+        let mut solution = Assignment::new();
+        let mut pending: Vec<usize> = (0..tables.len()).filter(|&i| !has_parent[i]).collect();
+        while let Some(index) = pending.pop() {
+            let row = tables[index]
+                .iter()
+                .find(|row| row.is_compatible_with(&solution))?;
+            for (var, value) in row.iter() {
+                solution.assign(var.clone(), value.clone());
+            }
+            pending.extend(&children[index]);
+        }
+        Some(solution)
+    }
+
+    /// Make every binary constraint arc consistent, returning false if a domain empties
+    /// Constraints of other arities are skipped; see `gac3`
+    pub fn ac3<T, D>(csp: &Csp<T, D>, domains: &mut HashMap<Variable<T>, D>) -> bool
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        Self::ac3_explained(csp, domains, &mut DomainsSnapshot::new()).is_none()
+    }
+
+    /// AC-3 that reports the arc which wiped out a domain, saving each
+    /// domain it changes in `trail`
+    fn ac3_explained<'a, T, D>(
+        csp: &'a Csp<T, D>,
+        domains: &mut HashMap<Variable<T>, D>,
+        trail: &mut DomainsSnapshot<T, D>,
+    ) -> Option<ArcConflict<'a, T>>
     where
         T: Clone + Eq + Hash + Debug + Display,
         D: Domain<T>,
@@ -46,10 +335,26 @@ impl ArcConsistencySolver {
             }
         }
 
+        Self::ac3_worklist(csp, domains, queue, trail)
+    }
+
+    /// The AC-3 main loop: revise arcs from `queue` until it drains, re-queueing
+    /// the arcs into every variable whose domain shrinks
+    fn ac3_worklist<'a, T, D>(
+        csp: &'a Csp<T, D>,
+        domains: &mut HashMap<Variable<T>, D>,
+        mut queue: VecDeque<(Variable<T>, Variable<T>, &'a Constraint<T>)>,
+        trail: &mut DomainsSnapshot<T, D>,
+    ) -> Option<ArcConflict<'a, T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
         while let Some((xi, xj, constraint)) = queue.pop_front() {
-            if Self::revise(domains, &xi, &xj, constraint) {
+            let mut removed = Self::revise(domains, &xi, &xj, constraint, trail);
+            if let Some(last_removed) = removed.pop() {
                 if domains.get(&xi).unwrap().is_empty() {
-                    return false;
+                    return Some((xi, xj, constraint, last_removed));
                 }
 
                 // add all arcs (xk, xi) for each neighbor xk of xi
@@ -63,21 +368,86 @@ impl ArcConsistencySolver {
             }
         }
 
+        None
+    }
+
+    /// GAC-3: make every constraint of any arity generalized arc consistent,
+    /// returning false if a domain empties. Each value of each variable must
+    /// have a supporting tuple over the rest of the constraint's scope
+    pub fn gac3<T, D>(csp: &Csp<T, D>, domains: &mut HashMap<Variable<T>, D>) -> bool
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        Self::gac3_trailed(csp, domains, &mut DomainsSnapshot::new())
+    }
+
+    fn gac3_trailed<T, D>(
+        csp: &Csp<T, D>,
+        domains: &mut HashMap<Variable<T>, D>,
+        trail: &mut DomainsSnapshot<T, D>,
+    ) -> bool
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        let mut queue: VecDeque<(Variable<T>, &Constraint<T>)> = VecDeque::new();
+        for constraint in csp.get_constraints() {
+            for var in constraint.variables() {
+                queue.push_back((var.clone(), constraint));
+            }
+        }
+
+        while let Some((xi, constraint)) = queue.pop_front() {
+            let Some(domain) = domains.get(&xi) else {
+                continue;
+            };
+            let supported: Vec<T> = domain
+                .values()
+                .into_iter()
+                .filter(|value| {
+                    let mut assignment = Assignment::new();
+                    assignment.assign(xi.clone(), value.clone());
+                    has_support(constraint, domains, &mut assignment, 0)
+                })
+                .collect();
+
+            if supported.len() < domain.size() {
+                let revised = domain.restrict_to(supported);
+                if revised.is_empty() {
+                    return false;
+                }
+                trail.replace(domains, &xi, revised);
+
+                // recheck the other variables of every constraint on xi
+                for other_constraint in csp.get_constraints_for_variable(&xi) {
+                    for var in other_constraint.variables() {
+                        if var != &xi {
+                            queue.push_back((var.clone(), other_constraint));
+                        }
+                    }
+                }
+            }
+        }
+
         true
     }
 
-    fn revise<T, D>(
+    /// Remove values of `xi` with no support in `xj`, saving the old domain in
+    /// `trail`, and return the removed values
+    pub fn revise<T, D>(
         domains: &mut HashMap<Variable<T>, D>,
         xi: &Variable<T>,
         xj: &Variable<T>,
-        constraint: &crate::csp::Constraint<T>,
-    ) -> bool
+        constraint: &Constraint<T>,
+        trail: &mut DomainsSnapshot<T, D>,
+    ) -> Vec<T>
     where
         T: Clone + Eq + Hash + Debug + Display,
         D: Domain<T>,
     {
-        let mut revised = false;
-        let xi_domain = domains.get(xi).unwrap().clone();
+        let mut removed = Vec::new();
+        let xi_domain = domains.get(xi).unwrap();
         let xj_domain = domains.get(xj).unwrap();
 
         let mut valid_values = Vec::new();
@@ -99,27 +469,79 @@ impl ArcConsistencySolver {
             if satisfiable {
                 valid_values.push(xi_value);
             } else {
-                revised = true;
+                removed.push(xi_value);
             }
         }
 
-        if revised {
+        if !removed.is_empty() {
             let new_domain = xi_domain.restrict_to(valid_values);
-            domains.insert(xi.clone(), new_domain);
+            trail.replace(domains, xi, new_domain);
         }
 
-        revised
+        removed
     }
 
-    fn backtrack_ac<T, D>(
+    /// Revise `xi` against several arcs at once, keeping only the values that
+    /// have support on every arc. Each value is checked first against the arc
+    /// that refuted the previous one, since neighboring values tend to fail on
+    /// the same arc. Returns true if the domain of `xi` changed
+    pub fn revise_batch<T, D>(
+        xi: &Variable<T>,
+        arcs: &[(Variable<T>, Constraint<T>)],
+        domains: &mut HashMap<Variable<T>, D>,
+    ) -> bool
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        let Some(xi_domain) = domains.get(xi) else {
+            return false;
+        };
+
+        let mut order: Vec<&(Variable<T>, Constraint<T>)> = arcs.iter().collect();
+        let supported: Vec<T> = xi_domain
+            .values()
+            .into_iter()
+            .filter(|xi_value| {
+                let refuted = order.iter().position(|(xj, constraint)| {
+                    !domains.get(xj).is_some_and(|xj_domain| {
+                        xj_domain.values().into_iter().any(|xj_value| {
+                            let mut test_assignment = Assignment::new();
+                            test_assignment.assign(xi.clone(), xi_value.clone());
+                            test_assignment.assign(xj.clone(), xj_value);
+                            constraint.is_satisfied(&test_assignment)
+                        })
+                    })
+                });
+                if let Some(position) = refuted {
+                    order[..=position].rotate_right(1);
+                }
+                refuted.is_none()
+            })
+            .collect();
+
+        if supported.len() == xi_domain.size() {
+            return false;
+        }
+        let new_domain = xi_domain.restrict_to(supported);
+        domains.insert(xi.clone(), new_domain);
+        true
+    }
+
+    fn backtrack_ac<T, D, F>(
         assignment: &mut Assignment<T>,
         csp: &Csp<T, D>,
         domains: &mut HashMap<Variable<T>, D>,
+        generalized: bool,
+        callback: &mut F,
     ) -> bool
     where
         T: Clone + Eq + Hash + Debug + Display,
         D: Domain<T>,
+        F: FnMut(&Assignment<T>, &HashMap<Variable<T>, D>),
     {
+        callback(assignment, domains);
+
         if assignment.is_complete(csp.num_variables()) {
             return true;
         }
@@ -137,16 +559,17 @@ impl ArcConsistencySolver {
                 assignment.assign(var.clone(), value.clone());
 
                 if csp.is_consistent(assignment) {
-                    let saved_domains = domains.clone();
+                    // only the domains changed by propagation are saved for undo
+                    let mut trail = DomainsSnapshot::new();
 
-                    // maintain arc consistency after assignment
-                    if Self::maintain_arc_consistency(&var, &value, csp, domains) {
-                        if Self::backtrack_ac(assignment, csp, domains) {
-                            return true;
-                        }
+                    // maintain (generalized) arc consistency after assignment
+                    if Self::propagate(&var, &value, csp, domains, generalized, &mut trail)
+                        && Self::backtrack_ac(assignment, csp, domains, generalized, callback)
+                    {
+                        return true;
                     }
 
-                    *domains = saved_domains;
+                    trail.restore(domains);
                 }
 
                 assignment.unassign(&var);
@@ -156,12 +579,36 @@ impl ArcConsistencySolver {
         false
     }
 
-    fn maintain_arc_consistency<T, D>(
+    pub(crate) fn maintain_arc_consistency<T, D>(
         assigned_var: &Variable<T>,
         assigned_value: &T,
         csp: &Csp<T, D>,
         domains: &mut HashMap<Variable<T>, D>,
     ) -> bool
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        // forward checking does not keep the domains arc consistent between
+        // assignments, so run full AC-3 rather than MAC-3 from the assigned variable
+        let single_value_domain = domains
+            .get(assigned_var)
+            .unwrap()
+            .restrict_to(vec![assigned_value.clone()]);
+        domains.insert(assigned_var.clone(), single_value_domain);
+        Self::ac3(csp, domains)
+    }
+
+    /// Fix the assigned variable's domain and re-establish (generalized) arc
+    /// consistency, saving every changed domain in `trail`
+    fn propagate<T, D>(
+        assigned_var: &Variable<T>,
+        assigned_value: &T,
+        csp: &Csp<T, D>,
+        domains: &mut HashMap<Variable<T>, D>,
+        generalized: bool,
+        trail: &mut DomainsSnapshot<T, D>,
+    ) -> bool
     where
         T: Clone + Eq + Hash + Debug + Display,
         D: Domain<T>,
@@ -171,9 +618,72 @@ impl ArcConsistencySolver {
             .get(assigned_var)
             .unwrap()
             .restrict_to(vec![assigned_value.clone()]);
+        trail.replace(domains, assigned_var, single_value_domain);
+
+        // run (g)ac-3 with reduced domains
+        if generalized {
+            Self::gac3_trailed(csp, domains, trail)
+        } else {
+            Self::mac3_trailed(assigned_var, csp, domains, trail)
+        }
+    }
+
+    /// MAC-3 step: fix `assigned_var` to `assigned_value` and restore arc
+    /// consistency starting only from the arcs (neighbor, assigned_var).
+    /// Assumes `domains` were arc consistent before the assignment
+    pub fn mac3_propagate<T, D>(
+        assigned_var: &Variable<T>,
+        assigned_value: &T,
+        csp: &Csp<T, D>,
+        domains: &mut HashMap<Variable<T>, D>,
+    ) -> bool
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        let Some(domain) = domains.get(assigned_var) else {
+            return false;
+        };
+        let single_value_domain = domain.restrict_to(vec![assigned_value.clone()]);
+        if single_value_domain.is_empty() {
+            return false;
+        }
         domains.insert(assigned_var.clone(), single_value_domain);
 
-        // run ac-3 with reduced domains
-        Self::ac3(csp, domains)
+        Self::mac3_trailed(assigned_var, csp, domains, &mut DomainsSnapshot::new())
+    }
+
+    fn mac3_trailed<T, D>(
+        assigned_var: &Variable<T>,
+        csp: &Csp<T, D>,
+        domains: &mut HashMap<Variable<T>, D>,
+        trail: &mut DomainsSnapshot<T, D>,
+    ) -> bool
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        // only the neighbors of the assigned variable can lose support
+        let mut queue = VecDeque::new();
+        for constraint in csp.get_constraints_for_variable(assigned_var) {
+            if let [x, y] = constraint.variables() {
+                let neighbor = if x == assigned_var { y } else { x };
+                if neighbor != assigned_var {
+                    queue.push_back((neighbor.clone(), assigned_var.clone(), constraint));
+                }
+            }
+        }
+
+        Self::ac3_worklist(csp, domains, queue, trail).is_none()
+    }
+}
+
+impl<T, D> Solver<T, D> for ArcConsistencySolver
+where
+    T: Clone + Eq + Hash + Debug + Display,
+    D: Domain<T>,
+{
+    fn solve(&self, csp: &Csp<T, D>) -> Option<Assignment<T>> {
+        ArcConsistencySolver::solve(csp)
     }
 }
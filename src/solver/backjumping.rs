@@ -0,0 +1,294 @@
+use super::Solver;
+use crate::csp::{Assignment, Domain, DomainsSnapshot, Variable, csp::Csp};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+
+/// Past variables blamed for a failure
+type ConflictSet<T> = HashSet<Variable<T>>;
+
+/// Forward checking with conflict-directed backjumping (FC-CBJ)
+pub struct FcCbjSolver;
+
+/// Conflict-directed backjumping (CBJ) without forward checking
+pub struct CbjSolver;
+
+/// Statistics describing an FC-CBJ search
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FcCbjStats {
+    /// Number of values tried
+    pub nodes_explored: u64,
+    /// Number of times search jumped back over a variable not in the conflict set
+    pub backjumps: u64,
+}
+
+impl CbjSolver {
+    /// Find a single solution, checking each assignment only against the past
+    pub fn solve<T, D>(csp: &Csp<T, D>) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        Self::solve_with_stats(csp).0
+    }
+
+    /// Find a single solution and report node and backjump counts
+    pub fn solve_with_stats<T, D>(csp: &Csp<T, D>) -> (Option<Assignment<T>>, FcCbjStats)
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        FcCbjSolver::run(csp, false)
+    }
+}
+
+impl FcCbjSolver {
+    /// Find a single solution, pruning future domains after each assignment
+    /// and jumping back to the deepest culprit of a dead end
+    pub fn solve<T, D>(csp: &Csp<T, D>) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        Self::solve_with_stats(csp).0
+    }
+
+    /// Find a single solution and report node and backjump counts
+    pub fn solve_with_stats<T, D>(csp: &Csp<T, D>) -> (Option<Assignment<T>>, FcCbjStats)
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        Self::run(csp, true)
+    }
+
+    fn run<T, D>(csp: &Csp<T, D>, look_ahead: bool) -> (Option<Assignment<T>>, FcCbjStats)
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        #[cfg(debug_assertions)]
+        csp.debug_validate();
+
+        let mut stats = FcCbjStats::default();
+        let mut domains: HashMap<Variable<T>, D> = csp
+            .get_variables()
+            .into_iter()
+            .filter_map(|var| csp.get_domain(&var).map(|domain| (var, domain.clone())))
+            .collect();
+        let mut pruned_by = HashMap::new();
+        let mut assignment = Assignment::new();
+
+        let solution = Self::search(
+            &mut assignment,
+            csp,
+            &mut domains,
+            &mut pruned_by,
+            &mut stats,
+            look_ahead,
+        )
+        .ok()
+        .map(|_| assignment);
+        (solution, stats)
+    }
+
+    /// Returns the conflict set of the failure if no solution extends the assignment
+    fn search<T, D>(
+        assignment: &mut Assignment<T>,
+        csp: &Csp<T, D>,
+        domains: &mut HashMap<Variable<T>, D>,
+        pruned_by: &mut HashMap<Variable<T>, Vec<Variable<T>>>,
+        stats: &mut FcCbjStats,
+        look_ahead: bool,
+    ) -> Result<(), ConflictSet<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        if assignment.is_complete(csp.num_variables()) {
+            return Ok(());
+        }
+
+        let Some(var) = Self::select_variable(assignment, domains) else {
+            return Err(ConflictSet::new());
+        };
+        let mut conflicts = ConflictSet::new();
+
+        for value in domains.get(&var).unwrap().values() {
+            stats.nodes_explored += 1;
+            assignment.assign(var.clone(), value);
+
+            let mut trail = DomainsSnapshot::new();
+            let mut touched = Vec::new();
+            let mut from_deeper = false;
+
+            let outcome = if !csp.is_consistent(assignment) {
+                Err(Self::violated_by(&var, assignment, csp))
+            } else if !look_ahead {
+                from_deeper = true;
+                Self::search(assignment, csp, domains, pruned_by, stats, look_ahead)
+            } else {
+                match Self::forward_check(
+                    &var,
+                    assignment,
+                    csp,
+                    domains,
+                    pruned_by,
+                    &mut trail,
+                    &mut touched,
+                ) {
+                    // blame everything that pruned the wiped-out domain
+                    Err(wiped) => Err(pruned_by[&wiped].iter().cloned().collect()),
+                    Ok(()) => {
+                        from_deeper = true;
+                        Self::search(assignment, csp, domains, pruned_by, stats, look_ahead)
+                    }
+                }
+            };
+
+            let Err(mut conflict) = outcome else {
+                return Ok(());
+            };
+
+            // undo this assignment's pruning
+            trail.restore(domains);
+            for (pruned, count) in touched {
+                let culprits = pruned_by.get_mut(&pruned).unwrap();
+                culprits.truncate(culprits.len() - count);
+            }
+            assignment.unassign(&var);
+
+            // the failure below does not depend on this variable, so skip it
+            if from_deeper && !conflict.contains(&var) {
+                stats.backjumps += 1;
+                return Err(conflict);
+            }
+            conflict.remove(&var);
+            conflicts.extend(conflict);
+        }
+
+        // dead end: also blame the variables that pruned this domain
+        if let Some(culprits) = pruned_by.get(&var) {
+            conflicts.extend(culprits.iter().cloned());
+        }
+        conflicts.remove(&var);
+        Err(conflicts)
+    }
+
+    fn select_variable<T, D>(
+        assignment: &Assignment<T>,
+        domains: &HashMap<Variable<T>, D>,
+    ) -> Option<Variable<T>>
+    where
+        T: Clone + Eq + Hash + Debug,
+        D: Domain<T>,
+    {
+        domains
+            .keys()
+            .filter(|var| !assignment.is_assigned(var))
+            // break ties by name so the search order is reproducible
+            .min_by(|a, b| {
+                let a_size = domains.get(a).unwrap().size();
+                let b_size = domains.get(b).unwrap().size();
+                a_size.cmp(&b_size).then_with(|| a.name.cmp(&b.name))
+            })
+            .cloned()
+    }
+
+    /// The other assigned variables of the constraints on `var` that are violated
+    fn violated_by<T, D>(
+        var: &Variable<T>,
+        assignment: &Assignment<T>,
+        csp: &Csp<T, D>,
+    ) -> ConflictSet<T>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        csp.get_constraints_for_variable(var)
+            .into_iter()
+            .filter(|constraint| !constraint.is_satisfied(assignment))
+            .flat_map(|constraint| constraint.variables().iter())
+            .filter(|other| *other != var && assignment.is_assigned(other))
+            .cloned()
+            .collect()
+    }
+
+    /// Prune the future neighbors of `assigned_var`, recording the assigned
+    /// variables of each pruning constraint as culprits. Returns the variable
+    /// whose domain was wiped out, if any
+    fn forward_check<T, D>(
+        assigned_var: &Variable<T>,
+        assignment: &Assignment<T>,
+        csp: &Csp<T, D>,
+        domains: &mut HashMap<Variable<T>, D>,
+        pruned_by: &mut HashMap<Variable<T>, Vec<Variable<T>>>,
+        trail: &mut DomainsSnapshot<T, D>,
+        touched: &mut Vec<(Variable<T>, usize)>,
+    ) -> Result<(), Variable<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        for constraint in csp.get_constraints_for_variable(assigned_var) {
+            for var in constraint.variables() {
+                if assignment.is_assigned(var) {
+                    continue;
+                }
+
+                let current_domain = domains.get(var).unwrap();
+                let valid_values: Vec<T> = current_domain
+                    .values()
+                    .into_iter()
+                    .filter(|value| {
+                        let mut test_assignment = assignment.clone();
+                        test_assignment.assign(var.clone(), value.clone());
+                        constraint.is_satisfied(&test_assignment)
+                    })
+                    .collect();
+
+                if valid_values.len() == current_domain.size() {
+                    continue;
+                }
+
+                let culprits: Vec<Variable<T>> = constraint
+                    .variables()
+                    .iter()
+                    .filter(|other| assignment.is_assigned(other))
+                    .cloned()
+                    .collect();
+                touched.push((var.clone(), culprits.len()));
+                pruned_by.entry(var.clone()).or_default().extend(culprits);
+
+                let new_domain = current_domain.restrict_to(valid_values);
+                let wiped = new_domain.is_empty();
+                trail.replace(domains, var, new_domain);
+                if wiped {
+                    return Err(var.clone());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<T, D> Solver<T, D> for FcCbjSolver
+where
+    T: Clone + Eq + Hash + Debug + Display,
+    D: Domain<T>,
+{
+    fn solve(&self, csp: &Csp<T, D>) -> Option<Assignment<T>> {
+        FcCbjSolver::solve(csp)
+    }
+}
+
+impl<T, D> Solver<T, D> for CbjSolver
+where
+    T: Clone + Eq + Hash + Debug + Display,
+    D: Domain<T>,
+{
+    fn solve(&self, csp: &Csp<T, D>) -> Option<Assignment<T>> {
+        CbjSolver::solve(csp)
+    }
+}
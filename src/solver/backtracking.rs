@@ -1,12 +1,116 @@
-use super::heuristics::{least_constraining_value, minimum_remaining_values};
-use super::utils::{domain_order, first_unassigned};
-use crate::csp::{Assignment, Domain, Variable, csp::Csp};
+use super::Solver;
+use super::heuristics::{
+    ConstraintWeights, constraint_suggested_variable, dom_ddeg, least_constraining_value,
+    least_constraining_variable, minimum_remaining_values, value_promise, wdeg_selector,
+};
+use super::utils::{domain_order, first_unassigned, topological_variable_order};
+use crate::csp::{Assignment, Domain, SolveProgress, Variable, csp::Csp};
+use rayon::ThreadPoolBuilder;
+use rayon::prelude::*;
+use std::collections::HashMap;
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 /// Base Backtracking solver implementation that other solvers build upon
 pub struct BacktrackingSolver;
 
+/// Statistics collected during a search
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SolverStats {
+    /// Number of value assignments tried
+    pub nodes_explored: u64,
+    /// Number of times a variable ran out of values and the search backed up
+    pub backtracks: u64,
+    /// Wall-clock time spent searching
+    pub elapsed: Duration,
+}
+
+/// Outcome of a bounded search
+#[derive(Debug, Clone)]
+pub enum SolveResult<T: Clone + Eq + Hash + Debug> {
+    /// A solution was found
+    Solved(Assignment<T>, SolverStats),
+    /// The search space was exhausted without finding a solution
+    NoSolution(SolverStats),
+    /// The deadline or node limit was reached before the search finished
+    Timeout(SolverStats),
+}
+
+impl<T: Clone + Eq + Hash + Debug> SolveResult<T> {
+    /// Returns the statistics regardless of the outcome
+    pub fn stats(&self) -> &SolverStats {
+        match self {
+            SolveResult::Solved(_, stats) => stats,
+            SolveResult::NoSolution(stats) => stats,
+            SolveResult::Timeout(stats) => stats,
+        }
+    }
+
+    /// Returns the solution, if one was found
+    pub fn solution(&self) -> Option<&Assignment<T>> {
+        match self {
+            SolveResult::Solved(assignment, _) => Some(assignment),
+            _ => None,
+        }
+    }
+}
+
+/// Callback, reporting interval and deepest assignment for progress reports
+struct ProgressReporter<'a, T: Clone + Eq + Hash + Debug> {
+    callback: &'a dyn Fn(SolveProgress<T>),
+    interval: u64,
+    best_partial: Assignment<T>,
+}
+
+/// Depth below which `find_solution_with_proof` stops recording the search
+pub const DEFAULT_PROOF_DEPTH: usize = 50;
+
+/// Record of a backtracking search explaining why values were rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProofTree<T> {
+    /// Assigning the value to the variable violated the named constraints
+    Contradiction(Variable<T>, T, Vec<String>),
+    /// The values tried for a variable, each with the subtree that followed
+    Branch(Variable<T>, Vec<(T, ProofTree<T>)>),
+    /// The assignment was complete
+    Solved,
+    /// The search below this point was not recorded, because of the depth limit
+    Unrecorded,
+}
+
+impl<T> ProofTree<T> {
+    /// The contradiction leaves of the tree, left to right
+    pub fn contradictions(&self) -> Vec<(&Variable<T>, &T, &[String])> {
+        match self {
+            ProofTree::Contradiction(var, value, constraints) => vec![(var, value, constraints)],
+            ProofTree::Branch(_, children) => children
+                .iter()
+                .flat_map(|(_, child)| child.contradictions())
+                .collect(),
+            ProofTree::Solved | ProofTree::Unrecorded => Vec::new(),
+        }
+    }
+}
+
+/// Internal result of a bounded backtracking step
+enum SearchOutcome {
+    Found,
+    Exhausted,
+    Aborted,
+}
+
+/// A frame of the explicit search stack used by iterative backtracking
+struct FrameState<T> {
+    /// The variable being assigned at this depth
+    variable: Variable<T>,
+    /// The ordered candidate values for the variable
+    values: Vec<T>,
+    /// Index of the next value to try
+    cursor: usize,
+}
+
 impl BacktrackingSolver {
     /// Generic solve method that uses backtracking to find solutions
     /// Takes variable selection and value ordering strategies
@@ -24,6 +128,9 @@ impl BacktrackingSolver {
         VS: Fn(&Assignment<T>, &Csp<T, D>) -> Option<Variable<T>>,
         VO: Fn(&Variable<T>, &D, &Assignment<T>, &Csp<T, D>) -> Vec<T>,
     {
+        #[cfg(debug_assertions)]
+        csp.debug_validate();
+
         let mut solutions = Vec::new();
         Self::backtrack(
             &mut Assignment::new(),
@@ -109,6 +216,125 @@ impl BacktrackingSolver {
         solutions.into_iter().next()
     }
 
+    /// Find a single solution, also returning the search tree: every value
+    /// tried, with the constraints that rejected it. Without a solution the
+    /// tree is a complete refutation. Recorded to `DEFAULT_PROOF_DEPTH` levels
+    pub fn find_solution_with_proof<T, D, VS, VO>(
+        csp: &Csp<T, D>,
+        select_variable: VS,
+        order_values: VO,
+    ) -> (Option<Assignment<T>>, ProofTree<T>)
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+        VS: Fn(&Assignment<T>, &Csp<T, D>) -> Option<Variable<T>>,
+        VO: Fn(&Variable<T>, &D, &Assignment<T>, &Csp<T, D>) -> Vec<T>,
+    {
+        Self::find_solution_with_proof_depth(
+            csp,
+            select_variable,
+            order_values,
+            DEFAULT_PROOF_DEPTH,
+        )
+    }
+
+    /// Like `find_solution_with_proof`, recording the tree to `max_depth`
+    /// levels; deeper search still runs but shows up as `ProofTree::Unrecorded`
+    pub fn find_solution_with_proof_depth<T, D, VS, VO>(
+        csp: &Csp<T, D>,
+        select_variable: VS,
+        order_values: VO,
+        max_depth: usize,
+    ) -> (Option<Assignment<T>>, ProofTree<T>)
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+        VS: Fn(&Assignment<T>, &Csp<T, D>) -> Option<Variable<T>>,
+        VO: Fn(&Variable<T>, &D, &Assignment<T>, &Csp<T, D>) -> Vec<T>,
+    {
+        #[cfg(debug_assertions)]
+        csp.debug_validate();
+
+        let mut assignment = Assignment::new();
+        let (found, proof) = Self::backtrack_proof(
+            &mut assignment,
+            csp,
+            &select_variable,
+            &order_values,
+            max_depth,
+        );
+        (found.then_some(assignment), proof)
+    }
+
+    fn backtrack_proof<T, D, VS, VO>(
+        assignment: &mut Assignment<T>,
+        csp: &Csp<T, D>,
+        select_variable: &VS,
+        order_values: &VO,
+        depth_left: usize,
+    ) -> (bool, ProofTree<T>)
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+        VS: Fn(&Assignment<T>, &Csp<T, D>) -> Option<Variable<T>>,
+        VO: Fn(&Variable<T>, &D, &Assignment<T>, &Csp<T, D>) -> Vec<T>,
+    {
+        if assignment.is_complete(csp.num_variables()) {
+            return (true, ProofTree::Solved);
+        }
+        if depth_left == 0 {
+            let mut solutions = Vec::new();
+            let found = Self::backtrack(
+                assignment,
+                csp,
+                select_variable,
+                order_values,
+                &mut solutions,
+                false,
+            );
+            return (found, ProofTree::Unrecorded);
+        }
+
+        let Some(var) = select_variable(assignment, csp) else {
+            return (false, ProofTree::Unrecorded);
+        };
+        let mut children = Vec::new();
+        if let Some(domain) = csp.get_domain(&var) {
+            for value in order_values(&var, domain, assignment, csp) {
+                assignment.assign(var.clone(), value.clone());
+
+                let violated: Vec<String> = csp
+                    .get_constraints()
+                    .iter()
+                    .filter(|constraint| !constraint.is_satisfied(assignment))
+                    .map(|constraint| constraint.name().to_string())
+                    .collect();
+                if !violated.is_empty() {
+                    children.push((
+                        value.clone(),
+                        ProofTree::Contradiction(var.clone(), value, violated),
+                    ));
+                } else {
+                    let (found, subtree) = Self::backtrack_proof(
+                        assignment,
+                        csp,
+                        select_variable,
+                        order_values,
+                        depth_left - 1,
+                    );
+                    children.push((value, subtree));
+                    if found {
+                        return (true, ProofTree::Branch(var, children));
+                    }
+                }
+
+                assignment.unassign(&var);
+            }
+        }
+
+        (false, ProofTree::Branch(var, children))
+    }
+
     /// Find all solutions using the provided heuristics
     pub fn find_all_solutions<T, D, VS, VO>(
         csp: &Csp<T, D>,
@@ -124,6 +350,65 @@ impl BacktrackingSolver {
         Self::solve_internal(csp, select_variable, order_values, true)
     }
 
+    /// Find all solutions, searching the subtrees under each value of the first
+    /// selected variable in parallel on rayon's global thread pool. Solutions
+    /// come back grouped by that value, in value order
+    pub fn find_all_solutions_parallel<T, D, VS, VO>(
+        csp: &Csp<T, D>,
+        select_variable: VS,
+        order_values: VO,
+    ) -> Vec<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display + Send + Sync,
+        D: Domain<T> + Send + Sync,
+        VS: Fn(&Assignment<T>, &Csp<T, D>) -> Option<Variable<T>> + Sync,
+        VO: Fn(&Variable<T>, &D, &Assignment<T>, &Csp<T, D>) -> Vec<T> + Sync,
+    {
+        #[cfg(debug_assertions)]
+        csp.debug_validate();
+
+        let empty = Assignment::new();
+        let Some(var) = select_variable(&empty, csp) else {
+            return Self::find_all_solutions(csp, select_variable, order_values);
+        };
+        let Some(domain) = csp.get_domain(&var) else {
+            return Vec::new();
+        };
+
+        // each worker searches its own copy with the first variable fixed
+        order_values(&var, domain, &empty, csp)
+            .into_par_iter()
+            .map(|value| {
+                let sub = csp.with_assumption(&var, value);
+                Self::find_all_solutions(&sub, &select_variable, &order_values)
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// `find_all_solutions_parallel` on a dedicated pool of `num_threads`
+    /// threads; 0 lets rayon choose
+    pub fn find_all_solutions_parallel_with_threads<T, D, VS, VO>(
+        csp: &Csp<T, D>,
+        select_variable: VS,
+        order_values: VO,
+        num_threads: usize,
+    ) -> Result<Vec<Assignment<T>>, String>
+    where
+        T: Clone + Eq + Hash + Debug + Display + Send + Sync,
+        D: Domain<T> + Send + Sync,
+        VS: Fn(&Assignment<T>, &Csp<T, D>) -> Option<Variable<T>> + Send + Sync,
+        VO: Fn(&Variable<T>, &D, &Assignment<T>, &Csp<T, D>) -> Vec<T> + Send + Sync,
+    {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .map_err(|e| format!("Failed to build thread pool: {}", e))?;
+        Ok(pool.install(|| Self::find_all_solutions_parallel(csp, select_variable, order_values)))
+    }
+
     /// Find a limited number of solutions
     pub fn find_limited_solutions<T, D, VS, VO>(
         csp: &Csp<T, D>,
@@ -137,6 +422,9 @@ impl BacktrackingSolver {
         VS: Fn(&Assignment<T>, &Csp<T, D>) -> Option<Variable<T>>,
         VO: Fn(&Variable<T>, &D, &Assignment<T>, &Csp<T, D>) -> Vec<T>,
     {
+        #[cfg(debug_assertions)]
+        csp.debug_validate();
+
         if limit == 0 {
             return Vec::new();
         }
@@ -213,6 +501,360 @@ impl BacktrackingSolver {
         false
     }
 
+    /// Find a single solution using an explicit stack instead of recursion
+    /// Suitable for CSPs whose search depth would overflow the call stack
+    pub fn find_solution_iterative<T, D, VS, VO>(
+        csp: &Csp<T, D>,
+        select_variable: VS,
+        order_values: VO,
+    ) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+        VS: Fn(&Assignment<T>, &Csp<T, D>) -> Option<Variable<T>>,
+        VO: Fn(&Variable<T>, &D, &Assignment<T>, &Csp<T, D>) -> Vec<T>,
+    {
+        #[cfg(debug_assertions)]
+        csp.debug_validate();
+
+        let mut assignment = Assignment::new();
+        let mut stack: Vec<FrameState<T>> = Vec::new();
+
+        loop {
+            if assignment.is_complete(csp.num_variables()) {
+                return Some(assignment);
+            }
+
+            // Descend: open a frame for the next variable
+            if let Some(var) = select_variable(&assignment, csp) {
+                let values = csp
+                    .get_domain(&var)
+                    .map(|domain| order_values(&var, domain, &assignment, csp))
+                    .unwrap_or_default();
+                stack.push(FrameState {
+                    variable: var,
+                    values,
+                    cursor: 0,
+                });
+            }
+
+            // Advance the deepest frame to its next consistent value,
+            // popping exhausted frames to backtrack
+            loop {
+                let frame = stack.last_mut()?;
+                assignment.unassign(&frame.variable);
+
+                if frame.cursor < frame.values.len() {
+                    let value = frame.values[frame.cursor].clone();
+                    frame.cursor += 1;
+                    assignment.assign(frame.variable.clone(), value);
+
+                    // Earlier assignments were consistent, so only constraints
+                    // on the new variable need checking
+                    if csp
+                        .get_constraints_for_variable(&frame.variable)
+                        .iter()
+                        .all(|constraint| constraint.is_satisfied(&assignment))
+                    {
+                        break;
+                    }
+                } else {
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    /// Find a single solution while collecting statistics, stopping early
+    /// when the deadline passes or the node limit is reached
+    pub fn solve_full<T, D, VS, VO>(
+        csp: &Csp<T, D>,
+        select_variable: VS,
+        order_values: VO,
+        deadline: Option<Instant>,
+        node_limit: Option<u64>,
+    ) -> SolveResult<T>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+        VS: Fn(&Assignment<T>, &Csp<T, D>) -> Option<Variable<T>>,
+        VO: Fn(&Variable<T>, &D, &Assignment<T>, &Csp<T, D>) -> Vec<T>,
+    {
+        #[cfg(debug_assertions)]
+        csp.debug_validate();
+
+        let start = Instant::now();
+        let mut stats = SolverStats::default();
+        let mut assignment = Assignment::new();
+
+        let outcome = Self::backtrack_bounded(
+            &mut assignment,
+            csp,
+            &select_variable,
+            &order_values,
+            &mut stats,
+            deadline,
+            node_limit,
+            &mut None,
+        );
+        stats.elapsed = start.elapsed();
+
+        match outcome {
+            SearchOutcome::Found => SolveResult::Solved(assignment, stats),
+            SearchOutcome::Exhausted => SolveResult::NoSolution(stats),
+            SearchOutcome::Aborted => SolveResult::Timeout(stats),
+        }
+    }
+
+    /// Find a single solution, calling `callback` every `report_interval` nodes
+    /// with the search statistics, the progress of the current assignment and
+    /// the deepest partial assignment
+    pub fn find_solution_with_progress<T, D, VS, VO, F>(
+        csp: &Csp<T, D>,
+        select_variable: VS,
+        order_values: VO,
+        callback: F,
+        report_interval: u64,
+    ) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+        VS: Fn(&Assignment<T>, &Csp<T, D>) -> Option<Variable<T>>,
+        VO: Fn(&Variable<T>, &D, &Assignment<T>, &Csp<T, D>) -> Vec<T>,
+        F: Fn(SolveProgress<T>),
+    {
+        #[cfg(debug_assertions)]
+        csp.debug_validate();
+
+        let mut assignment = Assignment::new();
+        let mut reporter = Some(ProgressReporter {
+            callback: &callback,
+            interval: report_interval.max(1),
+            best_partial: Assignment::new(),
+        });
+
+        match Self::backtrack_bounded(
+            &mut assignment,
+            csp,
+            &select_variable,
+            &order_values,
+            &mut SolverStats::default(),
+            None,
+            None,
+            &mut reporter,
+        ) {
+            SearchOutcome::Found => Some(assignment),
+            _ => None,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn backtrack_bounded<T, D, VS, VO>(
+        assignment: &mut Assignment<T>,
+        csp: &Csp<T, D>,
+        select_variable: &VS,
+        order_values: &VO,
+        stats: &mut SolverStats,
+        deadline: Option<Instant>,
+        node_limit: Option<u64>,
+        reporter: &mut Option<ProgressReporter<'_, T>>,
+    ) -> SearchOutcome
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+        VS: Fn(&Assignment<T>, &Csp<T, D>) -> Option<Variable<T>>,
+        VO: Fn(&Variable<T>, &D, &Assignment<T>, &Csp<T, D>) -> Vec<T>,
+    {
+        if assignment.is_complete(csp.num_variables()) {
+            return SearchOutcome::Found;
+        }
+
+        if let Some(var) = select_variable(assignment, csp)
+            && let Some(domain) = csp.get_domain(&var)
+        {
+            let ordered_values = order_values(&var, domain, assignment, csp);
+
+            for value in ordered_values {
+                // Check the bounds before exploring another node
+                if node_limit.is_some_and(|limit| stats.nodes_explored >= limit)
+                    || deadline.is_some_and(|deadline| Instant::now() >= deadline)
+                {
+                    return SearchOutcome::Aborted;
+                }
+                stats.nodes_explored += 1;
+
+                assignment.assign(var.clone(), value);
+                let consistent = csp.is_consistent(assignment);
+
+                if let Some(reporter) = reporter.as_mut() {
+                    if consistent && assignment.size() > reporter.best_partial.size() {
+                        reporter.best_partial = assignment.clone();
+                    }
+                    if stats.nodes_explored.is_multiple_of(reporter.interval) {
+                        (reporter.callback)(SolveProgress {
+                            nodes_explored: stats.nodes_explored,
+                            backtracks: stats.backtracks,
+                            best_partial: reporter.best_partial.clone(),
+                            ..csp.progress(assignment)
+                        });
+                    }
+                }
+
+                if consistent {
+                    match Self::backtrack_bounded(
+                        assignment,
+                        csp,
+                        select_variable,
+                        order_values,
+                        stats,
+                        deadline,
+                        node_limit,
+                        reporter,
+                    ) {
+                        SearchOutcome::Exhausted => {}
+                        outcome => return outcome,
+                    }
+                }
+
+                assignment.unassign(&var);
+            }
+        }
+
+        stats.backtracks += 1;
+        SearchOutcome::Exhausted
+    }
+
+    /// Find the `k` solutions with the highest objective values, best first
+    pub fn find_k_best_solutions<T, D, F, VS, VO>(
+        csp: &Csp<T, D>,
+        k: usize,
+        objective: F,
+        select_variable: VS,
+        order_values: VO,
+    ) -> Vec<(Assignment<T>, f64)>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+        F: Fn(&Assignment<T>) -> f64,
+        VS: Fn(&Assignment<T>, &Csp<T, D>) -> Option<Variable<T>>,
+        VO: Fn(&Variable<T>, &D, &Assignment<T>, &Csp<T, D>) -> Vec<T>,
+    {
+        Self::find_k_best_solutions_bounded(
+            csp,
+            k,
+            objective,
+            |_| f64::INFINITY,
+            select_variable,
+            order_values,
+        )
+    }
+
+    /// Like `find_k_best_solutions`, but prunes any branch whose `upper_bound`
+    /// (an optimistic score for every completion of a partial assignment)
+    /// cannot beat the worst of the current best `k`
+    pub fn find_k_best_solutions_bounded<T, D, F, B, VS, VO>(
+        csp: &Csp<T, D>,
+        k: usize,
+        objective: F,
+        upper_bound: B,
+        select_variable: VS,
+        order_values: VO,
+    ) -> Vec<(Assignment<T>, f64)>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+        F: Fn(&Assignment<T>) -> f64,
+        B: Fn(&Assignment<T>) -> f64,
+        VS: Fn(&Assignment<T>, &Csp<T, D>) -> Option<Variable<T>>,
+        VO: Fn(&Variable<T>, &D, &Assignment<T>, &Csp<T, D>) -> Vec<T>,
+    {
+        #[cfg(debug_assertions)]
+        csp.debug_validate();
+
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let mut best = Vec::with_capacity(k + 1);
+        Self::backtrack_k_best(
+            &mut Assignment::new(),
+            csp,
+            &select_variable,
+            &order_values,
+            &objective,
+            &upper_bound,
+            k,
+            &mut best,
+        );
+        best
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn backtrack_k_best<T, D, VS, VO, F, B>(
+        assignment: &mut Assignment<T>,
+        csp: &Csp<T, D>,
+        select_variable: &VS,
+        order_values: &VO,
+        objective: &F,
+        upper_bound: &B,
+        k: usize,
+        best: &mut Vec<(Assignment<T>, f64)>,
+    ) where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+        VS: Fn(&Assignment<T>, &Csp<T, D>) -> Option<Variable<T>>,
+        VO: Fn(&Variable<T>, &D, &Assignment<T>, &Csp<T, D>) -> Vec<T>,
+        F: Fn(&Assignment<T>) -> f64,
+        B: Fn(&Assignment<T>) -> f64,
+    {
+        // best is kept sorted descending, so the last entry is the one to beat
+        let threshold = if best.len() == k {
+            best.last().map(|(_, s)| *s)
+        } else {
+            None
+        };
+
+        if assignment.is_complete(csp.num_variables()) {
+            // the objective is only ever evaluated on complete assignments
+            let value = objective(assignment);
+            if threshold.is_none_or(|t| value > t) {
+                let position = best.partition_point(|(_, s)| *s >= value);
+                best.insert(position, (assignment.clone(), value));
+                best.truncate(k);
+            }
+            return;
+        }
+
+        if let Some(t) = threshold
+            && upper_bound(assignment) <= t
+        {
+            return;
+        }
+
+        if let Some(var) = select_variable(assignment, csp)
+            && let Some(domain) = csp.get_domain(&var)
+        {
+            for value in order_values(&var, domain, assignment, csp) {
+                assignment.assign(var.clone(), value);
+
+                if csp.is_consistent(assignment) {
+                    Self::backtrack_k_best(
+                        assignment,
+                        csp,
+                        select_variable,
+                        order_values,
+                        objective,
+                        upper_bound,
+                        k,
+                        best,
+                    );
+                }
+
+                assignment.unassign(&var);
+            }
+        }
+    }
+
     // Convenience methods for common use cases
 
     /// Simple backtracking search - finds a single solution
@@ -242,6 +884,16 @@ impl BacktrackingSolver {
         Self::find_solution(csp, first_unassigned, least_constraining_value)
     }
 
+    /// LCVar search - finds a single solution branching on the most flexible
+    /// variable first, the opposite of MRV
+    pub fn lcvar_search<T, D>(csp: &Csp<T, D>) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        Self::find_solution(csp, least_constraining_variable, domain_order)
+    }
+
     /// MRV+LCV search - finds a single solution with both heuristics
     pub fn mrv_lcv_search<T, D>(csp: &Csp<T, D>) -> Option<Assignment<T>>
     where
@@ -251,6 +903,292 @@ impl BacktrackingSolver {
         Self::find_solution(csp, minimum_remaining_values, least_constraining_value)
     }
 
+    /// Value promise search - finds a single solution trying the values that
+    /// leave neighbors the most room first
+    pub fn find_solution_value_promise<T, D>(csp: &Csp<T, D>) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        Self::find_solution(csp, first_unassigned, value_promise)
+    }
+
+    /// Smart search - branches on the variable suggested by the first constraint
+    /// that has a suggestion for the current consistent domains, falling back to
+    /// MRV when none does
+    pub fn find_solution_smart<T, D>(csp: &Csp<T, D>) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        Self::find_solution(csp, constraint_suggested_variable, domain_order)
+    }
+
+    /// Tree search - solves a CSP whose constraint graph is a tree (or forest)
+    /// without backtracking: directional arc consistency from the leaves to the
+    /// root, then a single root-to-leaves assignment pass.
+    /// Falls back to MRV search when the constraint graph has a cycle
+    pub fn tree_solve<T, D>(csp: &Csp<T, D>) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        #[cfg(debug_assertions)]
+        csp.debug_validate();
+
+        let order = match topological_variable_order(csp) {
+            Some(order) => order,
+            None => return Self::mrv_search(csp),
+        };
+        let position: HashMap<&Variable<T>, usize> =
+            order.iter().enumerate().map(|(i, var)| (var, i)).collect();
+
+        // Start from the values allowed by unary constraints
+        let mut domains: HashMap<Variable<T>, Vec<T>> = HashMap::new();
+        for var in &order {
+            let values = csp
+                .get_domain_values(var)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|value| {
+                    let mut test_assignment = Assignment::new();
+                    test_assignment.assign(var.clone(), value.clone());
+                    csp.get_constraints_for_variable(var)
+                        .iter()
+                        .all(|constraint| constraint.is_satisfied(&test_assignment))
+                })
+                .collect();
+            domains.insert(var.clone(), values);
+        }
+
+        // Make each parent arc consistent with its child, leaves first
+        for (i, child) in order.iter().enumerate().rev() {
+            let parent = match csp
+                .adjacent_variables(child)
+                .into_iter()
+                .find(|neighbor| position[neighbor] < i)
+            {
+                Some(parent) => parent,
+                None => continue,
+            };
+
+            let constraints: Vec<_> = csp
+                .get_constraints_for_variable(child)
+                .into_iter()
+                .filter(|constraint| constraint.involves(parent))
+                .collect();
+            let supported: Vec<T> = domains[parent]
+                .iter()
+                .filter(|parent_value| {
+                    domains[child].iter().any(|child_value| {
+                        let mut test_assignment = Assignment::new();
+                        test_assignment.assign(parent.clone(), (*parent_value).clone());
+                        test_assignment.assign(child.clone(), child_value.clone());
+                        constraints.iter().all(|c| c.is_satisfied(&test_assignment))
+                    })
+                })
+                .cloned()
+                .collect();
+
+            if supported.is_empty() {
+                return None;
+            }
+            domains.insert(parent.clone(), supported);
+        }
+
+        // Every remaining parent value has support, so a greedy pass succeeds
+        let mut assignment = Assignment::new();
+        for var in &order {
+            let mut chosen = None;
+            for value in &domains[var] {
+                assignment.assign(var.clone(), value.clone());
+                if csp
+                    .get_constraints_for_variable(var)
+                    .iter()
+                    .all(|constraint| constraint.is_satisfied(&assignment))
+                {
+                    chosen = Some(value.clone());
+                    break;
+                }
+                assignment.unassign(var);
+            }
+            chosen?;
+        }
+
+        Some(assignment)
+    }
+
+    /// dom/ddeg search - finds a single solution using the dom/ddeg heuristic
+    pub fn dom_ddeg_search<T, D>(csp: &Csp<T, D>) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        Self::find_solution(csp, dom_ddeg, domain_order)
+    }
+
+    /// dom/wdeg search - finds a single solution, weighting constraints by
+    /// how often they cause failures and branching on heavily weighted variables first
+    pub fn wdeg_search<T, D>(csp: &Csp<T, D>) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        Self::wdeg_search_with_stats(csp).0
+    }
+
+    /// dom/wdeg search that also reports the nodes explored and backtracks
+    pub fn wdeg_search_with_stats<T, D>(csp: &Csp<T, D>) -> (Option<Assignment<T>>, SolverStats)
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        #[cfg(debug_assertions)]
+        csp.debug_validate();
+
+        let start = Instant::now();
+        let weights = Arc::new(Mutex::new(ConstraintWeights::new()));
+        let select_variable = wdeg_selector(Arc::clone(&weights));
+        let mut assignment = Assignment::new();
+        let mut stats = SolverStats::default();
+
+        let found =
+            Self::backtrack_weighted(&mut assignment, csp, &select_variable, &weights, &mut stats);
+        stats.elapsed = start.elapsed();
+        (found.then_some(assignment), stats)
+    }
+
+    fn backtrack_weighted<T, D, VS>(
+        assignment: &mut Assignment<T>,
+        csp: &Csp<T, D>,
+        select_variable: &VS,
+        weights: &Mutex<ConstraintWeights<T>>,
+        stats: &mut SolverStats,
+    ) -> bool
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+        VS: Fn(&Assignment<T>, &Csp<T, D>) -> Option<Variable<T>>,
+    {
+        if assignment.is_complete(csp.num_variables()) {
+            return true;
+        }
+
+        if let Some(var) = select_variable(assignment, csp)
+            && let Some(domain) = csp.get_domain(&var)
+        {
+            for value in domain.values() {
+                stats.nodes_explored += 1;
+                assignment.assign(var.clone(), value);
+
+                // Blame the first violated constraint for the failure
+                match csp
+                    .get_constraints()
+                    .iter()
+                    .find(|constraint| !constraint.is_satisfied(assignment))
+                {
+                    Some(constraint) => weights.lock().unwrap().increment(constraint.name()),
+                    None => {
+                        if Self::backtrack_weighted(
+                            assignment,
+                            csp,
+                            select_variable,
+                            weights,
+                            stats,
+                        ) {
+                            return true;
+                        }
+                    }
+                }
+
+                assignment.unassign(&var);
+            }
+        }
+
+        stats.backtracks += 1;
+        false
+    }
+
+    /// Count all solutions with simple backtracking, without storing them
+    pub fn count_solutions<T, D>(csp: &Csp<T, D>) -> u64
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        Self::count_solutions_bounded(csp, u64::MAX)
+    }
+
+    /// Count solutions like `count_solutions`, stopping once `max` have been found
+    pub fn count_solutions_bounded<T, D>(csp: &Csp<T, D>, max: u64) -> u64
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        #[cfg(debug_assertions)]
+        csp.debug_validate();
+
+        let mut variables = csp.get_variables();
+        variables.sort_by(|a, b| a.name.cmp(&b.name));
+
+        // only the constraints on the newly assigned variable can become violated
+        let constraints: Vec<_> = variables
+            .iter()
+            .map(|var| csp.get_constraints_for_variable(var))
+            .collect();
+
+        let mut count = 0;
+        Self::count_backtrack(
+            0,
+            &variables,
+            &constraints,
+            csp,
+            &mut Assignment::new(),
+            &mut count,
+            max,
+        );
+        count
+    }
+
+    fn count_backtrack<T, D>(
+        index: usize,
+        variables: &[Variable<T>],
+        constraints: &[Vec<&crate::csp::Constraint<T>>],
+        csp: &Csp<T, D>,
+        assignment: &mut Assignment<T>,
+        count: &mut u64,
+        max: u64,
+    ) where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        let Some(var) = variables.get(index) else {
+            *count += 1;
+            return;
+        };
+
+        for value in csp.get_domain_values(var).unwrap_or_default() {
+            if *count >= max {
+                break;
+            }
+            assignment.assign(var.clone(), value);
+            if constraints[index]
+                .iter()
+                .all(|constraint| constraint.is_satisfied(assignment))
+            {
+                Self::count_backtrack(
+                    index + 1,
+                    variables,
+                    constraints,
+                    csp,
+                    assignment,
+                    count,
+                    max,
+                );
+            }
+            assignment.unassign(var);
+        }
+    }
+
     /// Find all solutions using simple backtracking
     pub fn find_all_backtracking<T, D>(csp: &Csp<T, D>) -> Vec<Assignment<T>>
     where
@@ -287,3 +1225,13 @@ impl BacktrackingSolver {
         Self::find_all_solutions(csp, minimum_remaining_values, least_constraining_value)
     }
 }
+
+impl<T, D> Solver<T, D> for BacktrackingSolver
+where
+    T: Clone + Eq + Hash + Debug + Display,
+    D: Domain<T>,
+{
+    fn solve(&self, csp: &Csp<T, D>) -> Option<Assignment<T>> {
+        BacktrackingSolver::backtrack_search(csp)
+    }
+}
@@ -1,3 +1,5 @@
+use super::Solver;
+use super::arc_consistency::ArcConsistencySolver;
 use crate::csp::{Assignment, Domain, Variable, csp::Csp};
 use std::collections::HashMap;
 use std::fmt::{Debug, Display};
@@ -5,53 +7,263 @@ use std::hash::Hash;
 
 pub struct ForwardCheckingSolver;
 
+/// What a forward checking search does on top of pruning neighbor domains
+#[derive(Debug, Clone, Copy, Default)]
+struct SearchOptions {
+    /// Run AC-3 after each forward check (MAC)
+    maintain_ac: bool,
+    /// Keep searching after a solution and return all of them
+    collect_all: bool,
+    /// Order values by how much forward checking each would leave
+    look_ahead: bool,
+}
+
+/// Statistics describing how much pruning forward checking performed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForwardCheckingStats {
+    /// Number of times a domain was reduced
+    pub total_domain_reductions: u64,
+    /// Number of times pruning wiped out a domain
+    pub empty_domain_detections: u64,
+    /// Total number of values removed from domains
+    pub values_pruned: u64,
+    /// Number of values tried
+    pub nodes_explored: u64,
+}
+
 impl ForwardCheckingSolver {
     pub fn solve<T, D>(csp: &Csp<T, D>) -> Option<Assignment<T>>
     where
         T: Clone + Eq + Hash + Debug + Display,
         D: Domain<T>,
     {
-        let mut assignment = Assignment::new();
+        let mut stats = ForwardCheckingStats::default();
+        Self::solve_internal(
+            csp,
+            &Self::select_variable,
+            SearchOptions::default(),
+            &mut stats,
+        )
+        .into_iter()
+        .next()
+    }
+
+    /// Find a single solution and report domain-reduction statistics
+    pub fn solve_with_stats<T, D>(csp: &Csp<T, D>) -> (Option<Assignment<T>>, ForwardCheckingStats)
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        let mut stats = ForwardCheckingStats::default();
+        let solution = Self::solve_internal(
+            csp,
+            &Self::select_variable,
+            SearchOptions::default(),
+            &mut stats,
+        )
+        .into_iter()
+        .next();
+        (solution, stats)
+    }
+
+    /// Find a single solution, branching on the variable chosen by `select_variable`
+    /// from the current assignment and the forward-checked domains
+    pub fn solve_with_heuristics<T, D, VS>(
+        csp: &Csp<T, D>,
+        select_variable: VS,
+    ) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+        VS: Fn(&Assignment<T>, &HashMap<Variable<T>, D>) -> Option<Variable<T>>,
+    {
+        let mut stats = ForwardCheckingStats::default();
+        Self::solve_internal(csp, &select_variable, SearchOptions::default(), &mut stats)
+            .into_iter()
+            .next()
+    }
+
+    /// Forward checking branching on unassigned variables in name order
+    pub fn solve_first_unassigned<T, D>(csp: &Csp<T, D>) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        Self::solve_with_heuristics(csp, |assignment, domains: &HashMap<Variable<T>, D>| {
+            domains
+                .keys()
+                .filter(|var| !assignment.is_assigned(var))
+                .min_by(|a, b| a.name.cmp(&b.name))
+                .cloned()
+        })
+    }
+
+    /// Forward checking branching on the variable constrained with the most
+    /// unassigned variables (ties broken by name)
+    pub fn solve_degree<T, D>(csp: &Csp<T, D>) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        Self::solve_with_heuristics(csp, |assignment, domains: &HashMap<Variable<T>, D>| {
+            domains
+                .keys()
+                .filter(|var| !assignment.is_assigned(var))
+                .map(|var| {
+                    let degree = csp
+                        .adjacent_variables(var)
+                        .into_iter()
+                        .filter(|neighbor| !assignment.is_assigned(neighbor))
+                        .count();
+                    (degree, var)
+                })
+                .min_by(|(a, x), (b, y)| b.cmp(a).then_with(|| x.name.cmp(&y.name)))
+                .map(|(_, var)| var.clone())
+        })
+    }
+
+    /// Forward checking that tries the most promising value first: each candidate
+    /// is forward checked ahead of time and values are ordered by the smallest
+    /// neighbor domain they would leave. Costs a forward check per value but
+    /// explores fewer nodes
+    pub fn solve_with_look_ahead<T, D>(csp: &Csp<T, D>) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        Self::solve_with_look_ahead_stats(csp).0
+    }
+
+    /// `solve_with_look_ahead`, also reporting statistics for the search itself
+    pub fn solve_with_look_ahead_stats<T, D>(
+        csp: &Csp<T, D>,
+    ) -> (Option<Assignment<T>>, ForwardCheckingStats)
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        let mut stats = ForwardCheckingStats::default();
+        let options = SearchOptions {
+            look_ahead: true,
+            ..SearchOptions::default()
+        };
+        let solution = Self::solve_internal(csp, &Self::select_variable, options, &mut stats)
+            .into_iter()
+            .next();
+        (solution, stats)
+    }
+
+    /// Find all solutions, maintaining arc consistency (AC-3) after each assignment
+    pub fn solve_all_mac<T, D>(csp: &Csp<T, D>) -> Vec<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        Self::solve_all_mac_with_stats(csp).0
+    }
+
+    /// `solve_all_mac`, also reporting statistics for the whole search
+    pub fn solve_all_mac_with_stats<T, D>(
+        csp: &Csp<T, D>,
+    ) -> (Vec<Assignment<T>>, ForwardCheckingStats)
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        let mut stats = ForwardCheckingStats::default();
+        let options = SearchOptions {
+            maintain_ac: true,
+            collect_all: true,
+            ..SearchOptions::default()
+        };
+        let solutions = Self::solve_internal(csp, &Self::select_variable, options, &mut stats);
+        (solutions, stats)
+    }
+
+    fn solve_internal<T, D, VS>(
+        csp: &Csp<T, D>,
+        select_variable: &VS,
+        options: SearchOptions,
+        stats: &mut ForwardCheckingStats,
+    ) -> Vec<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+        VS: Fn(&Assignment<T>, &HashMap<Variable<T>, D>) -> Option<Variable<T>>,
+    {
+        #[cfg(debug_assertions)]
+        csp.debug_validate();
+
+        let mut solutions = Vec::new();
         let mut domains: HashMap<Variable<T>, D> = csp
             .get_variables()
             .into_iter()
             .filter_map(|var| csp.get_domain(&var).map(|domain| (var, domain.clone())))
             .collect();
 
-        if Self::backtrack_fc(&mut assignment, csp, &mut domains) {
-            Some(assignment)
-        } else {
-            None
-        }
+        Self::backtrack_fc(
+            &mut Assignment::new(),
+            csp,
+            select_variable,
+            &mut domains,
+            &mut solutions,
+            options,
+            stats,
+        );
+        solutions
     }
 
-    fn backtrack_fc<T, D>(
+    fn backtrack_fc<T, D, VS>(
         assignment: &mut Assignment<T>,
         csp: &Csp<T, D>,
+        select_variable: &VS,
         domains: &mut HashMap<Variable<T>, D>,
+        solutions: &mut Vec<Assignment<T>>,
+        options: SearchOptions,
+        stats: &mut ForwardCheckingStats,
     ) -> bool
     where
         T: Clone + Eq + Hash + Debug + Display,
         D: Domain<T>,
+        VS: Fn(&Assignment<T>, &HashMap<Variable<T>, D>) -> Option<Variable<T>>,
     {
         if assignment.is_complete(csp.num_variables()) {
-            return true;
+            solutions.push(assignment.clone());
+            return !options.collect_all;
         }
 
-        let var = Self::select_variable(assignment, domains);
+        let var = select_variable(assignment, domains);
         if let Some(var) = var {
             let domain = domains.get(&var).unwrap().clone();
+            let values = if options.look_ahead {
+                Self::order_by_promise(&var, domain.values(), assignment, csp, domains)
+            } else {
+                domain.values()
+            };
 
-            for value in domain.values() {
+            for value in values {
+                stats.nodes_explored += 1;
                 assignment.assign(var.clone(), value.clone());
 
                 if csp.is_consistent(assignment) {
                     let saved_domains = domains.clone();
 
-                    if Self::forward_check(&var, &value, assignment, csp, domains) {
-                        if Self::backtrack_fc(assignment, csp, domains) {
-                            return true;
-                        }
+                    if Self::forward_check(&var, &value, assignment, csp, domains, stats)
+                        && (!options.maintain_ac
+                            || ArcConsistencySolver::maintain_arc_consistency(
+                                &var, &value, csp, domains,
+                            ))
+                        && Self::backtrack_fc(
+                            assignment,
+                            csp,
+                            select_variable,
+                            domains,
+                            solutions,
+                            options,
+                            stats,
+                        )
+                    {
+                        return true;
                     }
 
                     *domains = saved_domains;
@@ -64,6 +276,55 @@ impl ForwardCheckingSolver {
         false
     }
 
+    /// Sort values by the smallest neighbor domain forward checking would leave
+    /// behind, largest first; values that wipe out a domain go last. Ties keep
+    /// domain order
+    fn order_by_promise<T, D>(
+        var: &Variable<T>,
+        values: Vec<T>,
+        assignment: &mut Assignment<T>,
+        csp: &Csp<T, D>,
+        domains: &HashMap<Variable<T>, D>,
+    ) -> Vec<T>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        // pruning done while looking ahead isn't part of the search statistics
+        let mut scratch = ForwardCheckingStats::default();
+        let mut scored: Vec<(usize, T)> = values
+            .into_iter()
+            .map(|value| {
+                assignment.assign(var.clone(), value.clone());
+                let mut predicted = domains.clone();
+                let promise = if csp.is_consistent(assignment)
+                    && Self::forward_check(
+                        var,
+                        &value,
+                        assignment,
+                        csp,
+                        &mut predicted,
+                        &mut scratch,
+                    ) {
+                    csp.adjacent_variables(var)
+                        .into_iter()
+                        .filter(|neighbor| !assignment.is_assigned(neighbor))
+                        .map(|neighbor| predicted.get(neighbor).unwrap().size())
+                        .min()
+                        .unwrap_or(usize::MAX)
+                } else {
+                    0
+                };
+                assignment.unassign(var);
+                (promise, value)
+            })
+            .collect();
+
+        scored.sort_by(|(a, _), (b, _)| b.cmp(a));
+        scored.into_iter().map(|(_, value)| value).collect()
+    }
+
+    /// MRV over the forward-checked domains, the default selector
     fn select_variable<T, D>(
         assignment: &Assignment<T>,
         domains: &HashMap<Variable<T>, D>,
@@ -75,7 +336,12 @@ impl ForwardCheckingSolver {
         domains
             .keys()
             .filter(|var| !assignment.is_assigned(var))
-            .min_by_key(|var| domains.get(var).unwrap().size())
+            // break ties by name so the search order is reproducible
+            .min_by(|a, b| {
+                let a_size = domains.get(a).unwrap().size();
+                let b_size = domains.get(b).unwrap().size();
+                a_size.cmp(&b_size).then_with(|| a.name.cmp(&b.name))
+            })
             .cloned()
     }
 
@@ -85,6 +351,7 @@ impl ForwardCheckingSolver {
         assignment: &Assignment<T>,
         csp: &Csp<T, D>,
         domains: &mut HashMap<Variable<T>, D>,
+        stats: &mut ForwardCheckingStats,
     ) -> bool
     where
         T: Clone + Eq + Hash + Debug + Display,
@@ -109,7 +376,14 @@ impl ForwardCheckingSolver {
                     }
                 }
 
+                let pruned = current_domain.size() - valid_values.len();
+                if pruned > 0 {
+                    stats.total_domain_reductions += 1;
+                    stats.values_pruned += pruned as u64;
+                }
+
                 if valid_values.is_empty() {
+                    stats.empty_domain_detections += 1;
                     return false;
                 }
 
@@ -121,3 +395,13 @@ impl ForwardCheckingSolver {
         true
     }
 }
+
+impl<T, D> Solver<T, D> for ForwardCheckingSolver
+where
+    T: Clone + Eq + Hash + Debug + Display,
+    D: Domain<T>,
+{
+    fn solve(&self, csp: &Csp<T, D>) -> Option<Assignment<T>> {
+        ForwardCheckingSolver::solve(csp)
+    }
+}
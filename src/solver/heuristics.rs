@@ -1,6 +1,9 @@
-use crate::csp::{Assignment, Domain, Variable, csp::Csp};
+use super::utils::domain_order;
+use crate::csp::{Assignment, Domain, IntelligentConstraint, Variable, csp::Csp};
+use std::collections::HashMap;
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
+use std::sync::{Arc, Mutex};
 
 pub fn minimum_remaining_values<T, D>(
     assignment: &Assignment<T>,
@@ -14,29 +17,106 @@ where
         .into_iter()
         .filter(|var| !assignment.is_assigned(var))
         .min_by_key(|var| {
-            if let Some(domain) = csp.get_domain(var) {
-                let valid_count = domain
-                    .values()
+            if let Some(values) = csp.get_domain_values(var) {
+                values
                     .into_iter()
                     .filter(|val| {
-                        let all_consistent =
-                            csp.get_constraints_for_variable(var)
-                                .iter()
-                                .all(|constraint| {
-                                    let mut temp_assignment = assignment.clone();
-                                    temp_assignment.assign(var.clone(), val.clone());
-                                    constraint.is_satisfied(&temp_assignment)
-                                });
-                        all_consistent
+                        csp.get_constraints_for_variable(var)
+                            .iter()
+                            .all(|constraint| {
+                                let mut temp_assignment = assignment.clone();
+                                temp_assignment.assign(var.clone(), val.clone());
+                                constraint.is_satisfied(&temp_assignment)
+                            })
                     })
-                    .count();
-                valid_count
+                    .count()
             } else {
                 usize::MAX
             }
         })
 }
 
+/// MRV with singleton detection: an unassigned variable with no consistent
+/// value left (a dead end) or exactly one (a forced assignment) is returned as
+/// soon as it is found, without scoring the rest; otherwise the variable with
+/// the fewest consistent values. Ties are broken by name
+pub fn fail_first_with_forced<T, D>(
+    assignment: &Assignment<T>,
+    csp: &Csp<T, D>,
+) -> Option<Variable<T>>
+where
+    T: Clone + Eq + Hash + Debug + Display,
+    D: Domain<T>,
+{
+    let mut unassigned: Vec<Variable<T>> = csp
+        .get_variables()
+        .into_iter()
+        .filter(|var| !assignment.is_assigned(var))
+        .collect();
+    unassigned.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut best: Option<(Variable<T>, usize)> = None;
+    for var in unassigned {
+        let remaining = if let Some(values) = csp.get_domain_values(&var) {
+            let constraints = csp.get_constraints_for_variable(&var);
+            values
+                .into_iter()
+                .filter(|val| {
+                    let mut temp_assignment = assignment.clone();
+                    temp_assignment.assign(var.clone(), val.clone());
+                    constraints
+                        .iter()
+                        .all(|constraint| constraint.is_satisfied(&temp_assignment))
+                })
+                .count()
+        } else {
+            usize::MAX
+        };
+
+        if remaining <= 1 {
+            return Some(var);
+        }
+        if best.as_ref().is_none_or(|(_, fewest)| remaining < *fewest) {
+            best = Some((var, remaining));
+        }
+    }
+    best.map(|(var, _)| var)
+}
+
+/// Least constraining variable: the opposite of MRV, picks the unassigned
+/// variable with the most consistent values left (ties broken by name)
+pub fn least_constraining_variable<T, D>(
+    assignment: &Assignment<T>,
+    csp: &Csp<T, D>,
+) -> Option<Variable<T>>
+where
+    T: Clone + Eq + Hash + Debug + Display,
+    D: Domain<T>,
+{
+    csp.get_variables()
+        .into_iter()
+        .filter(|var| !assignment.is_assigned(var))
+        .map(|var| {
+            let remaining = csp.get_domain_values(&var).map_or(0, |values| {
+                values
+                    .into_iter()
+                    .filter(|val| {
+                        csp.get_constraints_for_variable(&var)
+                            .iter()
+                            .all(|constraint| {
+                                let mut temp_assignment = assignment.clone();
+                                temp_assignment.assign(var.clone(), val.clone());
+                                constraint.is_satisfied(&temp_assignment)
+                            })
+                    })
+                    .count()
+            });
+            (remaining, var)
+        })
+        .min_by(|(a, x), (b, y)| b.cmp(a).then_with(|| x.name.cmp(&y.name)))
+        .map(|(_, var)| var)
+}
+
 pub fn least_constraining_value<T, D>(
     var: &Variable<T>,
     domain: &D,
@@ -52,13 +132,12 @@ where
         .into_iter()
         .map(|val| {
             let constraints_imposed = csp
-                .get_variables()
+                .adjacent_variables(var)
                 .into_iter()
-                .filter(|other_var| !assignment.is_assigned(other_var) && other_var != var)
+                .filter(|other_var| !assignment.is_assigned(other_var))
                 .map(|other_var| {
-                    if let Some(other_domain) = csp.get_domain(&other_var) {
-                        other_domain
-                            .values()
+                    if let Some(values) = csp.get_domain_values(other_var) {
+                        values
                             .into_iter()
                             .filter(|other_val| {
                                 let mut test_assignment = assignment.clone();
@@ -81,6 +160,105 @@ where
     value_scores.into_iter().map(|(val, _)| val).collect()
 }
 
+// value promise: prefer values leaving the largest product of neighbor domain sizes
+pub fn value_promise<T, D>(
+    var: &Variable<T>,
+    domain: &D,
+    assignment: &Assignment<T>,
+    csp: &Csp<T, D>,
+) -> Vec<T>
+where
+    T: Clone + Eq + Hash + Debug + Display,
+    D: Domain<T>,
+{
+    let neighbors: Vec<&Variable<T>> = csp
+        .adjacent_variables(var)
+        .into_iter()
+        .filter(|neighbor| !assignment.is_assigned(neighbor))
+        .collect();
+
+    let mut value_scores: Vec<(T, f64)> = domain
+        .values()
+        .into_iter()
+        .map(|val| {
+            let mut test_assignment = assignment.clone();
+            test_assignment.assign(var.clone(), val.clone());
+
+            // forward check each neighbor; a wiped-out neighbor makes the product zero
+            let promise = neighbors
+                .iter()
+                .map(|neighbor| {
+                    csp.get_domain_values(neighbor)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter(|neighbor_val| {
+                            let mut check = test_assignment.clone();
+                            check.assign((*neighbor).clone(), neighbor_val.clone());
+                            csp.is_consistent(&check)
+                        })
+                        .count() as f64
+                })
+                .product::<f64>();
+
+            (val, promise)
+        })
+        .collect();
+
+    value_scores.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    value_scores.into_iter().map(|(val, _)| val).collect()
+}
+
+// promise value ordering: LCV first, ties broken by the larger product of the
+// neighbors' remaining domain sizes
+pub fn promise_value_ordering<T, D>(
+    var: &Variable<T>,
+    domain: &D,
+    assignment: &Assignment<T>,
+    csp: &Csp<T, D>,
+) -> Vec<T>
+where
+    T: Clone + Eq + Hash + Debug + Display,
+    D: Domain<T>,
+{
+    let neighbors: Vec<&Variable<T>> = csp
+        .adjacent_variables(var)
+        .into_iter()
+        .filter(|neighbor| !assignment.is_assigned(neighbor))
+        .collect();
+
+    let mut value_scores: Vec<(T, usize, f64)> = domain
+        .values()
+        .into_iter()
+        .map(|val| {
+            let mut test_assignment = assignment.clone();
+            test_assignment.assign(var.clone(), val.clone());
+
+            let mut eliminated = 0;
+            let mut product = 1.0;
+            for neighbor in &neighbors {
+                let size = csp.get_domain_size(neighbor).unwrap_or(0);
+                let removed = csp
+                    .get_domain_values(neighbor)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|neighbor_val| {
+                        let mut check = test_assignment.clone();
+                        check.assign((*neighbor).clone(), neighbor_val.clone());
+                        !csp.is_consistent(&check)
+                    })
+                    .count();
+                eliminated += removed;
+                product *= size.saturating_sub(removed) as f64;
+            }
+
+            (val, eliminated, product)
+        })
+        .collect();
+
+    value_scores.sort_by(|(_, e1, p1), (_, e2, p2)| e1.cmp(e2).then_with(|| p2.total_cmp(p1)));
+    value_scores.into_iter().map(|(val, _, _)| val).collect()
+}
+
 // degree heuristic for tie-breaking with mrv
 pub fn degree_heuristic<T, D>(assignment: &Assignment<T>, csp: &Csp<T, D>) -> Option<Variable<T>>
 where
@@ -90,18 +268,19 @@ where
     csp.get_variables()
         .into_iter()
         .filter(|var| !assignment.is_assigned(var))
-        .max_by_key(|var| {
-            csp.get_constraints_for_variable(var)
-                .iter()
-                .map(|constraint| {
-                    constraint
-                        .variables()
-                        .iter()
-                        .filter(|v| !assignment.is_assigned(v))
-                        .count()
-                })
-                .sum::<usize>()
-        })
+        .max_by_key(|var| unassigned_degree(var, assignment, csp))
+}
+
+// number of unassigned variables sharing a constraint with `var`
+fn unassigned_degree<T, D>(var: &Variable<T>, assignment: &Assignment<T>, csp: &Csp<T, D>) -> usize
+where
+    T: Clone + Eq + Hash + Debug + Display,
+    D: Domain<T>,
+{
+    csp.adjacent_variables(var)
+        .into_iter()
+        .filter(|neighbor| !assignment.is_assigned(neighbor))
+        .count()
 }
 
 pub fn mrv_degree<T, D>(assignment: &Assignment<T>, csp: &Csp<T, D>) -> Option<Variable<T>>
@@ -122,9 +301,8 @@ where
     let min_remaining = unassigned
         .iter()
         .map(|var| {
-            if let Some(domain) = csp.get_domain(var) {
-                domain
-                    .values()
+            if let Some(values) = csp.get_domain_values(var) {
+                values
                     .into_iter()
                     .filter(|val| {
                         let mut temp_assignment = assignment.clone();
@@ -142,9 +320,8 @@ where
     unassigned
         .into_iter()
         .filter(|var| {
-            if let Some(domain) = csp.get_domain(var) {
-                let remaining = domain
-                    .values()
+            if let Some(values) = csp.get_domain_values(var) {
+                let remaining = values
                     .into_iter()
                     .filter(|val| {
                         let mut temp_assignment = assignment.clone();
@@ -157,16 +334,265 @@ where
                 false
             }
         })
-        .max_by_key(|var| {
-            csp.get_constraints_for_variable(var)
+        .max_by_key(|var| unassigned_degree(var, assignment, csp))
+}
+
+// dom/ddeg: smallest ratio of remaining values to dynamic degree
+pub fn dom_ddeg<T, D>(assignment: &Assignment<T>, csp: &Csp<T, D>) -> Option<Variable<T>>
+where
+    T: Clone + Eq + Hash + Debug + Display,
+    D: Domain<T>,
+{
+    csp.get_variables()
+        .into_iter()
+        .filter(|var| !assignment.is_assigned(var))
+        .map(|var| {
+            let remaining = if let Some(values) = csp.get_domain_values(&var) {
+                values
+                    .into_iter()
+                    .filter(|val| {
+                        let mut temp_assignment = assignment.clone();
+                        temp_assignment.assign(var.clone(), val.clone());
+                        csp.is_consistent(&temp_assignment)
+                    })
+                    .count()
+            } else {
+                usize::MAX
+            };
+
+            // count constraints that still involve another unassigned variable
+            let dynamic_degree = csp
+                .get_constraints_for_variable(&var)
                 .iter()
-                .map(|constraint| {
+                .filter(|constraint| {
                     constraint
                         .variables()
                         .iter()
-                        .filter(|v| !assignment.is_assigned(v))
-                        .count()
+                        .any(|v| v != &var && !assignment.is_assigned(v))
+                })
+                .count();
+
+            // a variable with no value left is a dead end, whatever its degree
+            let ratio = if remaining == 0 {
+                0.0
+            } else if dynamic_degree == 0 {
+                usize::MAX as f64
+            } else {
+                remaining as f64 / dynamic_degree as f64
+            };
+
+            (var, ratio)
+        })
+        // break ties by name so the search order is reproducible
+        .min_by(|(a, a_ratio), (b, b_ratio)| {
+            a_ratio.total_cmp(b_ratio).then_with(|| a.name.cmp(&b.name))
+        })
+        .map(|(var, _)| var)
+}
+
+/// Constraint-directed selection: the variable suggested by the first
+/// constraint with a suggestion, given the values of each unassigned variable
+/// still consistent with the assignment. Falls back to MRV when none suggests one
+pub fn constraint_suggested_variable<T, D>(
+    assignment: &Assignment<T>,
+    csp: &Csp<T, D>,
+) -> Option<Variable<T>>
+where
+    T: Clone + Eq + Hash + Debug + Display,
+    D: Domain<T>,
+{
+    let live = consistent_domains(assignment, csp);
+    csp.get_constraints()
+        .iter()
+        .find_map(|constraint| constraint.suggest_next_variable(assignment, &live))
+        .or_else(|| minimum_remaining_values(assignment, csp))
+}
+
+// the domain of each unassigned variable cut down to the values consistent
+// with the constraints on it under `assignment`
+fn consistent_domains<T, D>(assignment: &Assignment<T>, csp: &Csp<T, D>) -> HashMap<Variable<T>, D>
+where
+    T: Clone + Eq + Hash + Debug + Display,
+    D: Domain<T>,
+{
+    let mut trial = assignment.clone();
+    csp.get_variables()
+        .into_iter()
+        .filter(|var| !assignment.is_assigned(var))
+        .filter_map(|var| {
+            let domain = csp.get_domain(&var)?;
+            let constraints = csp.get_constraints_for_variable(&var);
+            let consistent: Vec<T> = domain
+                .values()
+                .into_iter()
+                .filter(|value| {
+                    trial.assign(var.clone(), value.clone());
+                    let ok = constraints
+                        .iter()
+                        .all(|constraint| constraint.is_satisfied(&trial));
+                    trial.unassign(&var);
+                    ok
                 })
-                .sum::<usize>()
+                .collect();
+            let live = domain.restrict_to(consistent);
+            Some((var, live))
         })
+        .collect()
+}
+
+/// Failure counts per constraint, used by the weighted degree heuristic
+#[derive(Debug, Clone)]
+pub struct ConstraintWeights<T> {
+    weights: HashMap<String, f64>,
+    _phantom: std::marker::PhantomData<T>,
+}
+
+impl<T: Clone + Eq + Hash + Debug> ConstraintWeights<T> {
+    /// Create weights where every constraint starts at 1.0
+    pub fn new() -> Self {
+        ConstraintWeights {
+            weights: HashMap::new(),
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Get the weight of the named constraint
+    pub fn weight(&self, constraint_name: &str) -> f64 {
+        self.weights.get(constraint_name).copied().unwrap_or(1.0)
+    }
+
+    /// Record a failure caused by the named constraint
+    pub fn increment(&mut self, constraint_name: &str) {
+        *self
+            .weights
+            .entry(constraint_name.to_string())
+            .or_insert(1.0) += 1.0;
+    }
+
+    /// Sum of weights of the constraints on `var` that still involve another unassigned variable
+    pub fn variable_weight<D: Domain<T>>(
+        &self,
+        var: &Variable<T>,
+        assignment: &Assignment<T>,
+        csp: &Csp<T, D>,
+    ) -> f64 {
+        csp.get_constraints_for_variable(var)
+            .iter()
+            .filter(|constraint| {
+                constraint
+                    .variables()
+                    .iter()
+                    .any(|v| v != var && !assignment.is_assigned(v))
+            })
+            .map(|constraint| self.weight(constraint.name()))
+            .sum()
+    }
+}
+
+impl<T: Clone + Eq + Hash + Debug> Default for ConstraintWeights<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The last value each variable held, for phase saving
+#[derive(Debug, Clone)]
+pub struct PhaseCache<T> {
+    phases: HashMap<Variable<T>, T>,
+}
+
+impl<T: Clone + Eq + Hash + Debug> PhaseCache<T> {
+    /// Create an empty cache
+    pub fn new() -> Self {
+        PhaseCache {
+            phases: HashMap::new(),
+        }
+    }
+
+    /// The value last saved for `var`
+    pub fn get(&self, var: &Variable<T>) -> Option<&T> {
+        self.phases.get(var)
+    }
+
+    /// Remember the value of every assigned variable, e.g. a solution
+    pub fn save(&mut self, assignment: &Assignment<T>) {
+        for (var, value) in assignment.iter() {
+            self.phases.insert(var.clone(), value.clone());
+        }
+    }
+}
+
+impl<T: Clone + Eq + Hash + Debug> Default for PhaseCache<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Phase saving, from CDCL SAT solvers: try the value `var` last held first,
+/// if it is still in the domain, then the rest in domain order. Every call saves
+/// the values of the variables assigned so far, so the cache follows the search;
+/// share it between calls through a `RefCell` captured by the ordering closure
+pub fn phase_saving_value_order<T, D>(
+    var: &Variable<T>,
+    domain: &D,
+    assignment: &Assignment<T>,
+    csp: &Csp<T, D>,
+    cache: &mut PhaseCache<T>,
+) -> Vec<T>
+where
+    T: Clone + Eq + Hash + Debug,
+    D: Domain<T>,
+{
+    cache.save(assignment);
+
+    let mut values = domain_order(var, domain, assignment, csp);
+    if let Some(saved) = cache.get(var)
+        && let Some(position) = values.iter().position(|value| value == saved)
+    {
+        let saved = values.remove(position);
+        values.insert(0, saved);
+    }
+    values
+}
+
+// dom/wdeg: smallest ratio of remaining values to weighted degree
+pub fn wdeg_selector<T, D>(
+    weights: Arc<Mutex<ConstraintWeights<T>>>,
+) -> impl Fn(&Assignment<T>, &Csp<T, D>) -> Option<Variable<T>>
+where
+    T: Clone + Eq + Hash + Debug + Display,
+    D: Domain<T>,
+{
+    move |assignment, csp| {
+        let weights = weights.lock().unwrap();
+        csp.get_variables()
+            .into_iter()
+            .filter(|var| !assignment.is_assigned(var))
+            .map(|var| {
+                let remaining = csp
+                    .get_domain_values(&var)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|val| {
+                        let mut temp_assignment = assignment.clone();
+                        temp_assignment.assign(var.clone(), val.clone());
+                        csp.is_consistent(&temp_assignment)
+                    })
+                    .count();
+
+                let weight = weights.variable_weight(&var, assignment, csp);
+                let ratio = if weight > 0.0 {
+                    remaining as f64 / weight
+                } else {
+                    f64::INFINITY
+                };
+
+                (var, ratio)
+            })
+            // break ties by name so the search order is reproducible
+            .min_by(|(a, a_ratio), (b, b_ratio)| {
+                a_ratio.total_cmp(b_ratio).then_with(|| a.name.cmp(&b.name))
+            })
+            .map(|(var, _)| var)
+    }
 }
@@ -0,0 +1,121 @@
+use super::utils::SeededRng;
+use crate::csp::{Assignment, Domain, Variable, csp::Csp};
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+
+/// WalkSat adapted to CSPs: repairs a complete assignment by alternating
+/// greedy min-conflict moves with random walks
+pub struct WalkSatSolver;
+
+impl WalkSatSolver {
+    /// Local search from a random complete assignment. Each step repairs a random
+    /// violated constraint; with probability `noise_param` one of its variables
+    /// gets a random value, otherwise the move that leaves the fewest violations
+    /// is taken. Returns None if no solution is reached within `max_steps`
+    pub fn solve<T, D>(
+        csp: &Csp<T, D>,
+        noise_param: f64,
+        max_steps: usize,
+        seed: u64,
+    ) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+    {
+        #[cfg(debug_assertions)]
+        csp.debug_validate();
+
+        let mut rng = SeededRng::new(seed);
+        let constraints = csp.get_constraints();
+
+        let mut variables = csp.get_variables();
+        variables.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut domains: HashMap<Variable<T>, Vec<T>> = HashMap::new();
+        let mut involved: HashMap<Variable<T>, Vec<usize>> = HashMap::new();
+        let mut assignment = Assignment::new();
+        for var in &variables {
+            let values = csp.get_domain_values(var).unwrap_or_default();
+            if values.is_empty() {
+                return None;
+            }
+            assignment.assign(var.clone(), values[rng.below(values.len())].clone());
+            domains.insert(var.clone(), values);
+            involved.insert(var.clone(), Vec::new());
+        }
+        for (index, constraint) in constraints.iter().enumerate() {
+            for var in constraint.variables() {
+                if let Some(indices) = involved.get_mut(var) {
+                    indices.push(index);
+                }
+            }
+        }
+
+        // violations among the constraints touching `var` if it took `value`
+        let local_violations = |assignment: &mut Assignment<T>, var: &Variable<T>, value: &T| {
+            let previous = assignment.get(var).cloned();
+            assignment.assign(var.clone(), value.clone());
+            let count = involved[var]
+                .iter()
+                .filter(|&&index| !constraints[index].is_satisfied(assignment))
+                .count();
+            if let Some(previous) = previous {
+                assignment.assign(var.clone(), previous);
+            }
+            count
+        };
+
+        for _ in 0..max_steps {
+            let violated: Vec<usize> = (0..constraints.len())
+                .filter(|&index| !constraints[index].is_satisfied(&assignment))
+                .collect();
+            if violated.is_empty() {
+                return Some(assignment);
+            }
+
+            let scope = constraints[violated[rng.below(violated.len())]].variables();
+            if scope.is_empty() {
+                return None;
+            }
+
+            if rng.unit() < noise_param {
+                // random walk: any variable of the constraint, any value
+                let var = &scope[rng.below(scope.len())];
+                let values = &domains[var];
+                assignment.assign(var.clone(), values[rng.below(values.len())].clone());
+                continue;
+            }
+
+            // greedy: the (variable, value) change with the lowest violation
+            // delta, ties broken at random
+            let mut best: Vec<(&Variable<T>, &T)> = Vec::new();
+            let mut best_delta = isize::MAX;
+            for var in scope {
+                let current = assignment.get(var).cloned();
+                let before = match &current {
+                    Some(value) => local_violations(&mut assignment, var, value) as isize,
+                    None => 0,
+                };
+                for value in &domains[var] {
+                    if current.as_ref() == Some(value) {
+                        continue;
+                    }
+                    let delta = local_violations(&mut assignment, var, value) as isize - before;
+                    if delta < best_delta {
+                        best_delta = delta;
+                        best.clear();
+                    }
+                    if delta == best_delta {
+                        best.push((var, value));
+                    }
+                }
+            }
+            if !best.is_empty() {
+                let (var, value) = best[rng.below(best.len())];
+                assignment.assign(var.clone(), value.clone());
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,119 @@
+use super::backtracking::SolverStats;
+use crate::csp::{Assignment, Domain, Variable, csp::Csp};
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::time::Instant;
+
+/// Limited Discrepancy Search: follows the value ordering heuristic and only
+/// deviates from its first choice a bounded number of times
+pub struct LimitedDiscrepancySolver;
+
+impl LimitedDiscrepancySolver {
+    /// Search with discrepancy limits 0, 1, ..., `max_discrepancy` until a solution is found
+    pub fn solve<T, D, VS, VO>(
+        csp: &Csp<T, D>,
+        max_discrepancy: usize,
+        select_variable: VS,
+        order_values: VO,
+    ) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+        VS: Fn(&Assignment<T>, &Csp<T, D>) -> Option<Variable<T>>,
+        VO: Fn(&Variable<T>, &D, &Assignment<T>, &Csp<T, D>) -> Vec<T>,
+    {
+        Self::solve_with_stats(csp, max_discrepancy, select_variable, order_values).0
+    }
+
+    /// Like `solve`, also reporting the nodes explored and backtracks summed over every probe
+    pub fn solve_with_stats<T, D, VS, VO>(
+        csp: &Csp<T, D>,
+        max_discrepancy: usize,
+        select_variable: VS,
+        order_values: VO,
+    ) -> (Option<Assignment<T>>, SolverStats)
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+        VS: Fn(&Assignment<T>, &Csp<T, D>) -> Option<Variable<T>>,
+        VO: Fn(&Variable<T>, &D, &Assignment<T>, &Csp<T, D>) -> Vec<T>,
+    {
+        #[cfg(debug_assertions)]
+        csp.debug_validate();
+
+        let start = Instant::now();
+        let mut stats = SolverStats::default();
+        for discrepancies in 0..=max_discrepancy {
+            let mut assignment = Assignment::new();
+            if Self::probe(
+                &mut assignment,
+                csp,
+                &select_variable,
+                &order_values,
+                discrepancies,
+                &mut stats,
+            ) {
+                stats.elapsed = start.elapsed();
+                return (Some(assignment), stats);
+            }
+        }
+
+        stats.elapsed = start.elapsed();
+        (None, stats)
+    }
+
+    /// Depth-first probe where every choice other than the heuristic's first
+    /// value spends one discrepancy
+    fn probe<T, D, VS, VO>(
+        assignment: &mut Assignment<T>,
+        csp: &Csp<T, D>,
+        select_variable: &VS,
+        order_values: &VO,
+        discrepancies: usize,
+        stats: &mut SolverStats,
+    ) -> bool
+    where
+        T: Clone + Eq + Hash + Debug + Display,
+        D: Domain<T>,
+        VS: Fn(&Assignment<T>, &Csp<T, D>) -> Option<Variable<T>>,
+        VO: Fn(&Variable<T>, &D, &Assignment<T>, &Csp<T, D>) -> Vec<T>,
+    {
+        if assignment.is_complete(csp.num_variables()) {
+            return true;
+        }
+
+        if let Some(var) = select_variable(assignment, csp)
+            && let Some(domain) = csp.get_domain(&var)
+        {
+            let ordered_values = order_values(&var, domain, assignment, csp);
+
+            for (rank, value) in ordered_values.into_iter().enumerate() {
+                let cost = usize::from(rank > 0);
+                if cost > discrepancies {
+                    break;
+                }
+
+                stats.nodes_explored += 1;
+                assignment.assign(var.clone(), value);
+
+                if csp.is_consistent(assignment)
+                    && Self::probe(
+                        assignment,
+                        csp,
+                        select_variable,
+                        order_values,
+                        discrepancies - cost,
+                        stats,
+                    )
+                {
+                    return true;
+                }
+
+                assignment.unassign(&var);
+            }
+            stats.backtracks += 1;
+        }
+
+        false
+    }
+}
@@ -1,9 +1,53 @@
+pub mod advanced;
+pub mod analysis;
 pub mod arc_consistency;
+pub mod backjumping;
 pub mod backtracking;
 pub mod forward_checking;
 pub mod heuristics;
+pub mod local_search;
+pub mod look_ahead;
+pub mod portfolio;
+pub mod preprocessing;
+pub mod profiler;
+pub mod symmetry;
 pub mod utils;
 
-pub use arc_consistency::ArcConsistencySolver;
-pub use backtracking::BacktrackingSolver;
-pub use forward_checking::ForwardCheckingSolver;
+pub use advanced::{ClauseSet, MacNogoodSolver, MacNogoodStats};
+pub use arc_consistency::{ArcConsistencySolver, TreeDecomposition};
+pub use backjumping::{CbjSolver, FcCbjSolver, FcCbjStats};
+pub use backtracking::{BacktrackingSolver, ProofTree, SolveResult, SolverStats};
+pub use forward_checking::{ForwardCheckingSolver, ForwardCheckingStats};
+pub use local_search::WalkSatSolver;
+pub use look_ahead::LimitedDiscrepancySolver;
+pub use portfolio::{CancellationToken, PortfolioSolver};
+pub use profiler::{CspProfile, CspProfiler};
+
+use crate::csp::{Assignment, Domain, csp::Csp};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// Common interface over the solvers, so they can be chosen at runtime
+pub trait Solver<T: Clone + Eq + Hash + Debug, D: Domain<T>> {
+    /// Find a single solution, or None if the CSP has none
+    fn solve(&self, csp: &Csp<T, D>) -> Option<Assignment<T>>;
+}
+
+/// A solver behind dynamic dispatch, for collections of mixed solvers.
+/// Thread-safe so it can join a `PortfolioSolver` race
+pub struct BoxedSolver<T: Clone + Eq + Hash + Debug, D: Domain<T>>(
+    pub Box<dyn Solver<T, D> + Send + Sync>,
+);
+
+impl<T: Clone + Eq + Hash + Debug, D: Domain<T>> BoxedSolver<T, D> {
+    /// Box any solver
+    pub fn new<S: Solver<T, D> + Send + Sync + 'static>(solver: S) -> Self {
+        BoxedSolver(Box::new(solver))
+    }
+}
+
+impl<T: Clone + Eq + Hash + Debug, D: Domain<T>> Solver<T, D> for BoxedSolver<T, D> {
+    fn solve(&self, csp: &Csp<T, D>) -> Option<Assignment<T>> {
+        self.0.solve(csp)
+    }
+}
@@ -0,0 +1,197 @@
+use super::Solver;
+use crate::csp::{Assignment, Domain, csp::Csp};
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A solver function raced by the portfolio, with the token that tells it to stop
+type SolverFn<T, D> =
+    Arc<dyn Fn(&Csp<T, D>, &CancellationToken) -> Option<Assignment<T>> + Send + Sync>;
+
+/// Tells a portfolio solver to give up: set once another solver has won the
+/// race, and expired once the portfolio's time budget has run out
+#[derive(Debug, Clone)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    /// A token that is only cancelled explicitly
+    pub fn new() -> Self {
+        CancellationToken {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            deadline: None,
+        }
+    }
+
+    /// A token that also counts as cancelled once `deadline` has gone by
+    pub fn with_deadline(deadline: Option<Instant>) -> Self {
+        CancellationToken {
+            deadline,
+            ..Self::new()
+        }
+    }
+
+    /// Cancel this token and every clone of it
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns true once the token was cancelled or its deadline has passed
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// The deadline of the portfolio's time budget, if it has one
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Portfolio solver that races several algorithms and returns the first solution found
+///
+/// Every solver runs on its own thread over a shared copy of the CSP, so the
+/// values, domains and solvers must be `Send + Sync`. Once a solver finds a
+/// solution, or the time budget runs out, the others are cancelled through
+/// their `CancellationToken`; a solver that doesn't check the token keeps its
+/// thread busy until it finishes, but its result is ignored.
+pub struct PortfolioSolver<T, D>
+where
+    T: Clone + Eq + Hash + Debug + Display,
+    D: Domain<T>,
+{
+    solvers: Vec<SolverFn<T, D>>,
+    time_budget: Option<Duration>,
+}
+
+impl<T, D> PortfolioSolver<T, D>
+where
+    T: Clone + Eq + Hash + Debug + Display + Send + Sync + 'static,
+    D: Domain<T> + Send + Sync + 'static,
+{
+    /// Create an empty portfolio
+    pub fn new() -> Self {
+        PortfolioSolver {
+            solvers: Vec::new(),
+            time_budget: None,
+        }
+    }
+
+    /// Add a solver function to the portfolio
+    pub fn add_fn<F>(self, solver: F) -> Self
+    where
+        F: Fn(&Csp<T, D>) -> Option<Assignment<T>> + Send + Sync + 'static,
+    {
+        self.add_fn_cancellable(move |csp: &Csp<T, D>, _: &CancellationToken| solver(csp))
+    }
+
+    /// Add a solver function that gives up with `None` once the deadline it is
+    /// passed has gone by, so the time budget stops its thread too
+    pub fn add_fn_with_deadline<F>(self, solver: F) -> Self
+    where
+        F: Fn(&Csp<T, D>, Option<Instant>) -> Option<Assignment<T>> + Send + Sync + 'static,
+    {
+        self.add_fn_cancellable(move |csp: &Csp<T, D>, token: &CancellationToken| {
+            solver(csp, token.deadline())
+        })
+    }
+
+    /// Add a solver function that polls its token and stops when it is cancelled
+    pub fn add_fn_cancellable<F>(mut self, solver: F) -> Self
+    where
+        F: Fn(&Csp<T, D>, &CancellationToken) -> Option<Assignment<T>> + Send + Sync + 'static,
+    {
+        self.solvers.push(Arc::new(solver));
+        self
+    }
+
+    /// Add any `Solver` implementation to the portfolio
+    pub fn add_solver<S: Solver<T, D> + Send + Sync + 'static>(self, solver: S) -> Self {
+        self.add_fn(move |csp: &Csp<T, D>| solver.solve(csp))
+    }
+
+    /// Stop waiting for the solvers once `budget` has passed and return `None`
+    pub fn with_time_budget(mut self, budget: Duration) -> Self {
+        self.time_budget = Some(budget);
+        self
+    }
+
+    /// Number of solvers in the portfolio
+    pub fn len(&self) -> usize {
+        self.solvers.len()
+    }
+
+    /// Returns true if the portfolio has no solvers
+    pub fn is_empty(&self) -> bool {
+        self.solvers.is_empty()
+    }
+
+    /// Race the solvers and return the first solution found, or `None` if
+    /// every solver failed or the time budget ran out first
+    pub fn solve(&self, csp: &Csp<T, D>) -> Option<Assignment<T>> {
+        let deadline = self.time_budget.map(|budget| Instant::now() + budget);
+        let token = CancellationToken::with_deadline(deadline);
+        let csp = Arc::new(csp.clone());
+        let (sender, receiver) = mpsc::channel();
+
+        for solver in &self.solvers {
+            let (solver, csp, token, sender) = (
+                Arc::clone(solver),
+                Arc::clone(&csp),
+                token.clone(),
+                sender.clone(),
+            );
+            thread::spawn(move || {
+                // the receiver is gone once the race is decided
+                let _ = sender.send(solver(&csp, &token));
+            });
+        }
+        drop(sender);
+
+        let mut solution = None;
+        for _ in 0..self.solvers.len() {
+            let received = match deadline {
+                Some(deadline) => receiver
+                    .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                    .ok(),
+                None => receiver.recv().ok(),
+            };
+            match received {
+                Some(Some(found)) => {
+                    solution = Some(found);
+                    break;
+                }
+                Some(None) => {}
+                // out of time, or every remaining solver panicked
+                None => break,
+            }
+        }
+
+        token.cancel();
+        solution
+    }
+}
+
+impl<T, D> Default for PortfolioSolver<T, D>
+where
+    T: Clone + Eq + Hash + Debug + Display + Send + Sync + 'static,
+    D: Domain<T> + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,153 @@
+use super::ArcConsistencySolver;
+use crate::csp::{Assignment, Constraint, Domain, Variable, csp::Csp};
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+
+/// Prune every value that has no supporting combination of the other values
+/// in some constraint, in a single pass over all constraints of any arity.
+/// Returns `None` if a domain is wiped out.
+pub fn domain_filtering<T, D>(csp: &Csp<T, D>) -> Option<HashMap<Variable<T>, D>>
+where
+    T: Clone + Eq + Hash + Debug,
+    D: Domain<T>,
+{
+    let mut domains: HashMap<Variable<T>, D> = csp
+        .get_variables()
+        .into_iter()
+        .filter_map(|var| csp.get_domain(&var).map(|domain| (var, domain.clone())))
+        .collect();
+
+    for constraint in csp.get_constraints() {
+        for var in constraint.variables() {
+            let domain = domains.get(var)?.clone();
+            let supported: Vec<T> = domain
+                .values()
+                .into_iter()
+                .filter(|value| {
+                    let mut assignment = Assignment::new();
+                    assignment.assign(var.clone(), value.clone());
+                    has_support(constraint, &domains, &mut assignment, 0)
+                })
+                .collect();
+
+            if supported.len() < domain.size() {
+                let filtered = domain.restrict_to(supported);
+                if filtered.is_empty() {
+                    return None;
+                }
+                domains.insert(var.clone(), filtered);
+            }
+        }
+    }
+
+    Some(domains)
+}
+
+/// Search the Cartesian product of the remaining scope for a satisfying tuple
+pub(crate) fn has_support<T, D>(
+    constraint: &Constraint<T>,
+    domains: &HashMap<Variable<T>, D>,
+    assignment: &mut Assignment<T>,
+    index: usize,
+) -> bool
+where
+    T: Clone + Eq + Hash + Debug,
+    D: Domain<T>,
+{
+    let Some(var) = constraint.variables().get(index) else {
+        return constraint.is_satisfied(assignment);
+    };
+    if assignment.is_assigned(var) {
+        return has_support(constraint, domains, assignment, index + 1);
+    }
+
+    let Some(domain) = domains.get(var) else {
+        return false;
+    };
+    for value in domain.values() {
+        assignment.assign(var.clone(), value);
+        if has_support(constraint, domains, assignment, index + 1) {
+            assignment.unassign(var);
+            return true;
+        }
+    }
+    assignment.unassign(var);
+    false
+}
+
+/// Singleton arc consistency: remove each value whose assignment makes AC-3
+/// fail, repeating until no domain changes. Returns false if a domain empties
+pub fn singleton_arc_consistency<T, D>(csp: &mut Csp<T, D>) -> bool
+where
+    T: Clone + Eq + Hash + Debug + Display,
+    D: Domain<T>,
+{
+    prune_to_fixpoint(csp, |csp, var, value| {
+        let mut domains: HashMap<Variable<T>, D> = csp
+            .get_variables()
+            .into_iter()
+            .filter_map(|var| csp.get_domain(&var).map(|domain| (var, domain.clone())))
+            .collect();
+        let fixed = domains[var].restrict_to([value.clone()]);
+        domains.insert(var.clone(), fixed);
+        ArcConsistencySolver::ac3(csp, &mut domains)
+    })
+}
+
+/// Neighborhood inverse consistency: remove each value that can't be extended
+/// to a consistent assignment of the variable's whole neighborhood, checked by
+/// solving the neighborhood sub-problem with AC-3 and search.
+/// SAC propagates an assignment through the whole problem but only to arc
+/// consistency; NIC looks at the neighbors alone but needs a real solution
+/// there. Neither is stronger in general: NIC catches conflicts among the
+/// neighbors that AC misses, SAC catches chains reaching beyond them.
+/// Repeats until no domain changes; returns false if a domain empties
+pub fn neighborhood_inverse_consistency<T, D>(csp: &mut Csp<T, D>) -> bool
+where
+    T: Clone + Eq + Hash + Debug + Display,
+    D: Domain<T>,
+{
+    prune_to_fixpoint(csp, |csp, var, value| {
+        let mut neighborhood: Vec<Variable<T>> =
+            csp.adjacent_variables(var).into_iter().cloned().collect();
+        neighborhood.push(var.clone());
+        let sub = csp
+            .induce_subproblem(&neighborhood)
+            .with_assumption(var, value.clone());
+        ArcConsistencySolver::solve(&sub).is_some()
+    })
+}
+
+/// Remove every value failing `supported`, sweeping the variables in name order
+/// until a sweep changes nothing. Returns false if a domain empties
+fn prune_to_fixpoint<T, D, F>(csp: &mut Csp<T, D>, supported: F) -> bool
+where
+    T: Clone + Eq + Hash + Debug,
+    D: Domain<T>,
+    F: Fn(&Csp<T, D>, &Variable<T>, &T) -> bool,
+{
+    let mut variables = csp.get_variables();
+    variables.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for var in &variables {
+            for value in csp.get_domain_values(var).unwrap_or_default() {
+                if supported(csp, var, &value) {
+                    continue;
+                }
+                let pruned = csp.get_domain(var).unwrap().remove(&value);
+                let wiped_out = pruned.is_empty();
+                csp.set_domain(var, pruned).unwrap();
+                if wiped_out {
+                    return false;
+                }
+                changed = true;
+            }
+        }
+    }
+
+    true
+}
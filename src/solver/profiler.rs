@@ -0,0 +1,128 @@
+use crate::csp::{Assignment, Constraint, Domain, Variable, csp::Csp};
+use crate::solver::backtracking::SolveResult;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Variable selection heuristic as seen by a profiled solver
+pub type VariableSelector<'a, T, D> =
+    dyn Fn(&Assignment<T>, &Csp<T, D>) -> Option<Variable<T>> + 'a;
+
+/// Value ordering heuristic as seen by a profiled solver
+pub type ValueOrdering<'a, T, D> =
+    dyn Fn(&Variable<T>, &D, &Assignment<T>, &Csp<T, D>) -> Vec<T> + 'a;
+
+/// Where a profiled solve spent its time and effort
+#[derive(Debug, Clone, Default)]
+pub struct CspProfile {
+    pub variable_selection_time: Duration,
+    pub value_ordering_time: Duration,
+    pub constraint_check_time: Duration,
+    /// Number of constraint evaluations
+    pub constraint_checks: u64,
+    /// Number of domain values handed out by value ordering
+    pub domain_operations: u64,
+    /// Number of times a variable ran out of values and the search backed up
+    pub backtracks: u64,
+}
+
+/// Records a `CspProfile` across every solve run through `wrap_solver`
+#[derive(Debug, Clone, Default)]
+pub struct CspProfiler {
+    profile: Arc<Mutex<CspProfile>>,
+}
+
+impl CspProfiler {
+    /// Create a profiler with all counters at zero
+    pub fn new() -> Self {
+        CspProfiler::default()
+    }
+
+    /// Snapshot of the profile collected so far
+    pub fn profile(&self) -> CspProfile {
+        self.profile.lock().unwrap().clone()
+    }
+
+    /// Reset all counters to zero
+    pub fn reset(&self) {
+        *self.profile.lock().unwrap() = CspProfile::default();
+    }
+
+    /// Wrap a solver so that its heuristics and constraint checks are timed.
+    /// `solver_fn` receives an instrumented copy of the CSP together with the
+    /// instrumented heuristics and must search using them.
+    pub fn wrap_solver<T, D, VS, VO, F>(
+        &self,
+        select_variable: VS,
+        order_values: VO,
+        solver_fn: F,
+    ) -> impl Fn(&Csp<T, D>) -> Option<Assignment<T>>
+    where
+        T: Clone + Eq + Hash + Debug + Display + Send + Sync + 'static,
+        D: Domain<T>,
+        VS: Fn(&Assignment<T>, &Csp<T, D>) -> Option<Variable<T>>,
+        VO: Fn(&Variable<T>, &D, &Assignment<T>, &Csp<T, D>) -> Vec<T>,
+        F: Fn(&Csp<T, D>, &VariableSelector<T, D>, &ValueOrdering<T, D>) -> SolveResult<T>,
+    {
+        let profile = self.profile.clone();
+        move |csp| {
+            let instrumented = Self::instrument(csp, &profile);
+
+            let profiled_select = |assignment: &Assignment<T>, csp: &Csp<T, D>| {
+                let start = Instant::now();
+                let var = select_variable(assignment, csp);
+                profile.lock().unwrap().variable_selection_time += start.elapsed();
+                var
+            };
+            let profiled_order =
+                |var: &Variable<T>, domain: &D, assignment: &Assignment<T>, csp: &Csp<T, D>| {
+                    let start = Instant::now();
+                    let values = order_values(var, domain, assignment, csp);
+                    let mut profile = profile.lock().unwrap();
+                    profile.value_ordering_time += start.elapsed();
+                    profile.domain_operations += values.len() as u64;
+                    values
+                };
+
+            let result = solver_fn(&instrumented, &profiled_select, &profiled_order);
+
+            profile.lock().unwrap().backtracks += result.stats().backtracks;
+            result.solution().cloned()
+        }
+    }
+
+    /// Copy of the CSP whose constraints record their evaluation time
+    fn instrument<T, D>(csp: &Csp<T, D>, profile: &Arc<Mutex<CspProfile>>) -> Csp<T, D>
+    where
+        T: Clone + Eq + Hash + Debug + Send + Sync + 'static,
+        D: Domain<T>,
+    {
+        let mut instrumented = Csp::new();
+        for var in csp.get_variables() {
+            if let Some(domain) = csp.get_domain(&var) {
+                instrumented.add_variable(var, domain.clone()).unwrap();
+            }
+        }
+
+        for constraint in csp.get_constraints() {
+            let inner = constraint.clone();
+            let profile = profile.clone();
+            let timed = Constraint::new_partial(
+                constraint.name(),
+                constraint.variables().to_vec(),
+                move |assignment| {
+                    let start = Instant::now();
+                    let satisfied = inner.is_satisfied(assignment);
+                    let mut profile = profile.lock().unwrap();
+                    profile.constraint_check_time += start.elapsed();
+                    profile.constraint_checks += 1;
+                    satisfied
+                },
+            );
+            instrumented.add_constraint(timed).unwrap();
+        }
+
+        instrumented
+    }
+}
@@ -0,0 +1,55 @@
+use super::backtracking::BacktrackingSolver;
+use crate::csp::{Assignment, Domain, csp::Csp};
+use std::collections::HashSet;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+
+/// A symmetry of a CSP, mapping each solution to an equivalent solution
+pub type Symmetry<T> = Box<dyn Fn(&Assignment<T>) -> Assignment<T>>;
+
+/// Lexicographically minimum representation of an assignment's orbit under the group
+/// The identity is always included, so the group only needs the non-trivial symmetries
+pub fn canonical_form<T>(
+    assignment: &Assignment<T>,
+    symmetry_group: &[Symmetry<T>],
+) -> Vec<(String, T)>
+where
+    T: Clone + Eq + Hash + Debug + Ord,
+{
+    let mut canonical = sorted_pairs(assignment);
+    for symmetry in symmetry_group {
+        let image = sorted_pairs(&symmetry(assignment));
+        if image < canonical {
+            canonical = image;
+        }
+    }
+    canonical
+}
+
+/// Count solutions, treating solutions related by a symmetry as the same
+pub fn count_solutions_up_to_symmetry<T, D>(
+    csp: &Csp<T, D>,
+    symmetry_group: &[Symmetry<T>],
+) -> usize
+where
+    T: Clone + Eq + Hash + Debug + Display + Ord,
+    D: Domain<T>,
+{
+    BacktrackingSolver::find_all_backtracking(csp)
+        .iter()
+        .map(|solution| canonical_form(solution, symmetry_group))
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+fn sorted_pairs<T>(assignment: &Assignment<T>) -> Vec<(String, T)>
+where
+    T: Clone + Eq + Hash + Debug + Ord,
+{
+    let mut pairs: Vec<(String, T)> = assignment
+        .iter()
+        .map(|(var, value)| (var.name.clone(), value.clone()))
+        .collect();
+    pairs.sort();
+    pairs
+}
@@ -1,4 +1,5 @@
 use crate::csp::{Assignment, Domain, Variable, csp::Csp};
+use std::collections::{HashSet, VecDeque};
 use std::fmt::Debug;
 use std::hash::Hash;
 
@@ -26,3 +27,87 @@ where
 {
     domain.values()
 }
+
+/// Helper function: Order variables so each appears after its parent in the
+/// constraint graph (root to leaves). Returns None if the graph has a cycle
+pub fn topological_variable_order<T, D>(csp: &Csp<T, D>) -> Option<Vec<Variable<T>>>
+where
+    T: Clone + Eq + Debug + Hash,
+    D: Domain<T>,
+{
+    let mut variables = csp.get_variables();
+    variables.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut order = Vec::with_capacity(variables.len());
+    let mut visited: HashSet<Variable<T>> = HashSet::new();
+
+    // breadth-first from each unvisited root, so forests are supported
+    for root in variables {
+        if visited.contains(&root) {
+            continue;
+        }
+        visited.insert(root.clone());
+        let mut queue = VecDeque::from([(root, None::<Variable<T>>)]);
+
+        while let Some((var, parent)) = queue.pop_front() {
+            let mut neighbors = csp.adjacent_variables(&var);
+            neighbors.sort_by(|a, b| a.name.cmp(&b.name));
+
+            for neighbor in neighbors {
+                if parent.as_ref() == Some(neighbor) {
+                    continue;
+                }
+                // reaching a visited variable by another path closes a cycle
+                if !visited.insert(neighbor.clone()) {
+                    return None;
+                }
+                queue.push_back((neighbor.clone(), Some(var.clone())));
+            }
+            order.push(var);
+        }
+    }
+
+    Some(order)
+}
+
+/// Helper: Small seeded xorshift generator for the randomized solvers, so
+/// runs are reproducible without pulling in an external crate
+#[derive(Debug, Clone)]
+pub(crate) struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub(crate) fn new(seed: u64) -> Self {
+        // splitmix the seed so nearby seeds (and zero) give unrelated streams
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        Self {
+            state: (z ^ (z >> 31)) | 1,
+        }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// Uniform index in 0..bound (bound must be non-zero)
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Uniform float in [0, 1)
+    pub(crate) fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub(crate) fn shuffle<V>(&mut self, items: &mut [V]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}